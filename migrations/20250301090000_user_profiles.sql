@@ -0,0 +1,17 @@
+-- Public profile fields for users
+ALTER TABLE users
+    ADD COLUMN display_name VARCHAR(255),
+    ADD COLUMN location VARCHAR(255),
+    ADD COLUMN bio TEXT,
+    ADD COLUMN avatar_url TEXT,
+    ADD COLUMN is_dealer BOOLEAN NOT NULL DEFAULT FALSE;
+
+-- Remember who listed an auction; the vehicle owner changes once it is sold
+ALTER TABLE auctions ADD COLUMN seller_username VARCHAR(255);
+
+UPDATE auctions a SET seller_username = v.owner_username
+FROM vehicles v
+WHERE v.id = a.vehicle_id AND a.closed IS NOT TRUE;
+
+CREATE INDEX idx_vehicles_owner_username ON vehicles(owner_username);
+CREATE INDEX idx_auctions_seller_username ON auctions(seller_username);
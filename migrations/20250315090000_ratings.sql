@@ -0,0 +1,25 @@
+-- Settlement details needed to know who may rate whom and until when
+ALTER TABLE auctions
+    ADD COLUMN winner_username VARCHAR(255),
+    ADD COLUMN closed_at TIMESTAMP,
+    ADD COLUMN min_buyer_rating NUMERIC(3, 2);
+
+UPDATE auctions a SET winner_username = (
+    SELECT b.bidder_username FROM bids b WHERE b.auction_id = a.id ORDER BY b.bid_amount DESC LIMIT 1
+)
+WHERE a.closed = TRUE;
+
+-- One rating per party per settled auction; ratee_role is the role of the rated user
+CREATE TABLE ratings (
+    id SERIAL PRIMARY KEY,
+    auction_id INT NOT NULL REFERENCES auctions(id) ON DELETE CASCADE,
+    rater_username VARCHAR(255) NOT NULL,
+    ratee_username VARCHAR(255) NOT NULL,
+    ratee_role TEXT NOT NULL CHECK (ratee_role IN ('buyer', 'seller')),
+    score SMALLINT NOT NULL CHECK (score BETWEEN 1 AND 5),
+    comment TEXT,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (auction_id, rater_username)
+);
+
+CREATE INDEX idx_ratings_ratee_username ON ratings(ratee_username);
@@ -0,0 +1,7 @@
+-- Accounts are anonymized instead of deleted so other users' bid history survives
+ALTER TABLE users ADD COLUMN deleted_at TIMESTAMP;
+
+ALTER TABLE bids DROP CONSTRAINT bids_bidder_username_fkey;
+ALTER TABLE bids
+    ADD CONSTRAINT bids_bidder_username_fkey FOREIGN KEY (bidder_username)
+    REFERENCES users(username) ON UPDATE CASCADE ON DELETE RESTRICT;
@@ -0,0 +1,51 @@
+-- Reference users by id instead of by free-text username
+
+-- Usernames that no longer match an account become tombstone users so nothing is orphaned
+INSERT INTO users (username, password, deleted_at)
+SELECT DISTINCT name, '', NOW() FROM (
+    SELECT owner_username AS name FROM vehicles
+    UNION SELECT seller_username FROM auctions
+    UNION SELECT winner_username FROM auctions
+    UNION SELECT rater_username FROM ratings
+    UNION SELECT ratee_username FROM ratings
+) refs
+WHERE name IS NOT NULL AND NOT EXISTS (SELECT 1 FROM users u WHERE u.username = refs.name);
+
+-- vehicles.owner_username -> owner_id
+ALTER TABLE vehicles ADD COLUMN owner_id INT REFERENCES users(id) ON DELETE RESTRICT;
+UPDATE vehicles v SET owner_id = u.id FROM users u WHERE u.username = v.owner_username;
+ALTER TABLE vehicles ALTER COLUMN owner_id SET NOT NULL;
+DROP INDEX idx_vehicles_owner_username;
+ALTER TABLE vehicles DROP COLUMN owner_username;
+CREATE INDEX idx_vehicles_owner_id ON vehicles(owner_id);
+
+-- bids.bidder_username -> bidder_id
+ALTER TABLE bids ADD COLUMN bidder_id INT REFERENCES users(id) ON DELETE RESTRICT;
+UPDATE bids b SET bidder_id = u.id FROM users u WHERE u.username = b.bidder_username;
+ALTER TABLE bids ALTER COLUMN bidder_id SET NOT NULL;
+ALTER TABLE bids DROP COLUMN bidder_username;
+CREATE INDEX idx_bids_bidder_id ON bids(bidder_id);
+CREATE INDEX idx_bids_auction_id_amount ON bids(auction_id, bid_amount DESC);
+
+-- auctions.seller_username / winner_username -> seller_id / winner_id
+ALTER TABLE auctions
+    ADD COLUMN seller_id INT REFERENCES users(id) ON DELETE RESTRICT,
+    ADD COLUMN winner_id INT REFERENCES users(id) ON DELETE RESTRICT;
+UPDATE auctions a SET seller_id = u.id FROM users u WHERE u.username = a.seller_username;
+UPDATE auctions a SET winner_id = u.id FROM users u WHERE u.username = a.winner_username;
+DROP INDEX idx_auctions_seller_username;
+ALTER TABLE auctions DROP COLUMN seller_username, DROP COLUMN winner_username;
+CREATE INDEX idx_auctions_seller_id ON auctions(seller_id);
+
+-- ratings.rater_username / ratee_username -> rater_id / ratee_id
+ALTER TABLE ratings
+    ADD COLUMN rater_id INT REFERENCES users(id) ON DELETE RESTRICT,
+    ADD COLUMN ratee_id INT REFERENCES users(id) ON DELETE RESTRICT;
+UPDATE ratings r SET rater_id = u.id FROM users u WHERE u.username = r.rater_username;
+UPDATE ratings r SET ratee_id = u.id FROM users u WHERE u.username = r.ratee_username;
+ALTER TABLE ratings ALTER COLUMN rater_id SET NOT NULL, ALTER COLUMN ratee_id SET NOT NULL;
+DROP INDEX idx_ratings_ratee_username;
+ALTER TABLE ratings DROP CONSTRAINT ratings_auction_id_rater_username_key;
+ALTER TABLE ratings DROP COLUMN rater_username, DROP COLUMN ratee_username;
+ALTER TABLE ratings ADD CONSTRAINT ratings_auction_id_rater_id_key UNIQUE (auction_id, rater_id);
+CREATE INDEX idx_ratings_ratee_id ON ratings(ratee_id);
@@ -0,0 +1,12 @@
+-- Edits made while a vehicle is in an open auction, shown to bidders
+CREATE TABLE vehicle_edits (
+    id SERIAL PRIMARY KEY,
+    vehicle_id INT NOT NULL REFERENCES vehicles(id) ON DELETE CASCADE,
+    auction_id INT NOT NULL REFERENCES auctions(id) ON DELETE CASCADE,
+    field TEXT NOT NULL,
+    old_value TEXT,
+    new_value TEXT,
+    edited_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX idx_vehicle_edits_vehicle_id ON vehicle_edits(vehicle_id);
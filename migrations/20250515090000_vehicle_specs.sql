@@ -0,0 +1,25 @@
+-- Structured specification for classic vehicles
+ALTER TABLE vehicles
+    ADD COLUMN make VARCHAR(100),
+    ADD COLUMN model VARCHAR(100),
+    ADD COLUMN year SMALLINT CHECK (year BETWEEN 1885 AND 2100),
+    ADD COLUMN vin VARCHAR(17),
+    ADD COLUMN mileage INT CHECK (mileage >= 0),
+    ADD COLUMN mileage_unit TEXT CHECK (mileage_unit IN ('km', 'mi')),
+    ADD COLUMN fuel_type TEXT CHECK (fuel_type IN ('petrol', 'diesel', 'electric', 'hybrid', 'lpg', 'other')),
+    ADD COLUMN transmission TEXT CHECK (transmission IN ('manual', 'automatic', 'semi_automatic')),
+    ADD COLUMN body_style TEXT CHECK (body_style IN (
+        'coupe', 'convertible', 'roadster', 'sedan', 'hatchback', 'wagon', 'suv', 'pickup', 'van', 'motorcycle', 'other'
+    )),
+    ADD COLUMN colour VARCHAR(50),
+    ADD COLUMN engine VARCHAR(100),
+    ADD COLUMN matching_numbers BOOLEAN,
+    ADD COLUMN registration_country CHAR(2) CHECK (registration_country ~ '^[A-Z]{2}$'),
+    ADD COLUMN condition_grade SMALLINT CHECK (condition_grade BETWEEN 1 AND 5),
+    ADD CONSTRAINT vehicles_mileage_requires_unit CHECK (mileage IS NULL OR mileage_unit IS NOT NULL);
+
+CREATE INDEX idx_vehicles_make_model ON vehicles(lower(make), lower(model));
+CREATE INDEX idx_vehicles_year ON vehicles(year);
+CREATE INDEX idx_vehicles_vin ON vehicles(vin);
+CREATE INDEX idx_vehicles_body_style ON vehicles(body_style);
+CREATE INDEX idx_vehicles_fuel_type ON vehicles(fuel_type);
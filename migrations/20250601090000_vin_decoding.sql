@@ -0,0 +1,27 @@
+-- Pre-1981 chassis numbers may carry separators and run past 17 characters
+ALTER TABLE vehicles ALTER COLUMN vin TYPE VARCHAR(20);
+
+-- VINs are compared in their normalized, upper case form
+UPDATE vehicles SET vin = upper(btrim(vin)) WHERE vin IS NOT NULL;
+
+-- VINs cleared from vehicles that duplicated an earlier one, kept for administrators to resolve
+CREATE TABLE vehicle_vin_conflicts (
+    id SERIAL PRIMARY KEY,
+    vehicle_id INT NOT NULL REFERENCES vehicles(id) ON DELETE CASCADE,
+    vin VARCHAR(20) NOT NULL,
+    detected_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+WITH duplicates AS (
+    SELECT id, vin FROM (
+        SELECT id, vin, row_number() OVER (PARTITION BY vin ORDER BY id) AS position
+        FROM vehicles WHERE vin IS NOT NULL
+    ) listed
+    WHERE position > 1
+), flagged AS (
+    INSERT INTO vehicle_vin_conflicts (vehicle_id, vin) SELECT id, vin FROM duplicates RETURNING vehicle_id
+)
+UPDATE vehicles SET vin = NULL WHERE id IN (SELECT vehicle_id FROM flagged);
+
+-- The application checks first for a friendly error; this catches concurrent writers
+CREATE UNIQUE INDEX idx_vehicles_vin_listed ON vehicles(vin) WHERE vin IS NOT NULL;
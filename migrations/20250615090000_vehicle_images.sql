@@ -0,0 +1,16 @@
+-- Photos attached to a vehicle listing; files live in the configured storage backend
+CREATE TABLE vehicle_images (
+    id SERIAL PRIMARY KEY,
+    vehicle_id INT NOT NULL REFERENCES vehicles(id) ON DELETE CASCADE,
+    position INT NOT NULL,
+    is_primary BOOLEAN NOT NULL DEFAULT FALSE,
+    web_key TEXT NOT NULL,
+    thumbnail_key TEXT NOT NULL,
+    content_type TEXT NOT NULL,
+    width INT NOT NULL,
+    height INT NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_vehicle_images_vehicle ON vehicle_images(vehicle_id, position);
+CREATE UNIQUE INDEX idx_vehicle_images_primary ON vehicle_images(vehicle_id) WHERE is_primary;
@@ -0,0 +1,49 @@
+-- Paperwork attached to a vehicle: service history, title scans, inspection reports
+CREATE TABLE vehicle_documents (
+    id SERIAL PRIMARY KEY,
+    vehicle_id INT NOT NULL REFERENCES vehicles(id) ON DELETE CASCADE,
+    doc_type TEXT NOT NULL CHECK (doc_type IN (
+        'service_history', 'title', 'registration', 'inspection_report', 'invoice', 'other'
+    )),
+    title VARCHAR(255) NOT NULL,
+    storage_key TEXT NOT NULL,
+    content_type TEXT NOT NULL,
+    byte_size INT NOT NULL,
+    uploaded_by INT NOT NULL REFERENCES users(id) ON DELETE RESTRICT,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_vehicle_documents_vehicle ON vehicle_documents(vehicle_id);
+
+-- Structured condition reports; rust rating runs from 1 (none) to 5 (severe)
+CREATE TABLE condition_reports (
+    id SERIAL PRIMARY KEY,
+    vehicle_id INT NOT NULL REFERENCES vehicles(id) ON DELETE CASCADE,
+    author_id INT NOT NULL REFERENCES users(id) ON DELETE RESTRICT,
+    inspection_date DATE NOT NULL,
+    rust_rating SMALLINT NOT NULL CHECK (rust_rating BETWEEN 1 AND 5),
+    overall_notes TEXT,
+    third_party_inspected BOOLEAN NOT NULL DEFAULT FALSE,
+    inspector_name VARCHAR(255),
+    inspection_document_id INT REFERENCES vehicle_documents(id) ON DELETE SET NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    CONSTRAINT condition_reports_inspector_required CHECK (NOT third_party_inspected OR inspector_name IS NOT NULL)
+);
+
+CREATE INDEX idx_condition_reports_vehicle ON condition_reports(vehicle_id);
+
+CREATE TABLE condition_report_items (
+    id SERIAL PRIMARY KEY,
+    report_id INT NOT NULL REFERENCES condition_reports(id) ON DELETE CASCADE,
+    section TEXT NOT NULL CHECK (section IN ('panel', 'mechanical')),
+    item TEXT NOT NULL,
+    rating TEXT NOT NULL CHECK (rating IN ('excellent', 'good', 'fair', 'poor', 'damaged', 'missing')),
+    notes TEXT,
+    UNIQUE (report_id, section, item)
+);
+
+CREATE TABLE condition_report_photos (
+    report_id INT NOT NULL REFERENCES condition_reports(id) ON DELETE CASCADE,
+    image_id INT NOT NULL REFERENCES vehicle_images(id) ON DELETE CASCADE,
+    PRIMARY KEY (report_id, image_id)
+);
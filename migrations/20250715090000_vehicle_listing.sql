@@ -0,0 +1,10 @@
+-- Keyset pagination needs a total order on creation time
+UPDATE vehicles SET created_at = NOW() WHERE created_at IS NULL;
+ALTER TABLE vehicles ALTER COLUMN created_at SET NOT NULL;
+
+CREATE INDEX idx_vehicles_created_at_id ON vehicles(created_at, id);
+CREATE INDEX idx_vehicles_starting_price_id ON vehicles(starting_price, id);
+
+-- Open auction and last auction lookups per vehicle
+CREATE INDEX idx_auctions_vehicle_id ON auctions(vehicle_id, id);
+CREATE INDEX idx_auctions_open_vehicle ON auctions(vehicle_id) WHERE closed = FALSE;
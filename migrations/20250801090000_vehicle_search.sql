@@ -0,0 +1,19 @@
+CREATE EXTENSION IF NOT EXISTS pg_trgm;
+
+-- Weighted full-text document: identity fields rank above specs, specs above the description
+ALTER TABLE vehicles
+    ADD COLUMN search_vector tsvector GENERATED ALWAYS AS (
+        setweight(to_tsvector('english', coalesce(name, '')), 'A') ||
+        setweight(to_tsvector('english', coalesce(make, '') || ' ' || coalesce(model, '') || ' ' || coalesce(vin, '')), 'A') ||
+        setweight(to_tsvector('english',
+            coalesce(year::text, '') || ' ' || coalesce(engine, '') || ' ' || coalesce(body_style, '') || ' ' ||
+            coalesce(colour, '') || ' ' || coalesce(fuel_type, '') || ' ' || coalesce(transmission, '')), 'B') ||
+        setweight(to_tsvector('english', coalesce(description, '')), 'C')
+    ) STORED,
+    -- Short text for typo-tolerant trigram matching
+    ADD COLUMN search_text TEXT GENERATED ALWAYS AS (
+        lower(coalesce(name, '') || ' ' || coalesce(make, '') || ' ' || coalesce(model, '') || ' ' || coalesce(year::text, ''))
+    ) STORED;
+
+CREATE INDEX idx_vehicles_search_vector ON vehicles USING GIN (search_vector);
+CREATE INDEX idx_vehicles_search_text ON vehicles USING GIN (search_text gin_trgm_ops);
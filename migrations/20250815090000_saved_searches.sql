@@ -0,0 +1,45 @@
+-- In-app notifications; `payload` carries ids the client needs to link through
+CREATE TABLE notifications (
+    id SERIAL PRIMARY KEY,
+    user_id INT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    kind TEXT NOT NULL,
+    message TEXT NOT NULL,
+    payload JSONB NOT NULL DEFAULT '{}',
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    read_at TIMESTAMP
+);
+
+CREATE INDEX idx_notifications_user ON notifications(user_id, created_at DESC);
+
+CREATE TABLE saved_searches (
+    id SERIAL PRIMARY KEY,
+    user_id INT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    name VARCHAR(100) NOT NULL,
+    frequency TEXT NOT NULL CHECK (frequency IN ('immediate', 'daily')),
+    q VARCHAR(200),
+    make VARCHAR(100),
+    model VARCHAR(100),
+    year_min SMALLINT,
+    year_max SMALLINT,
+    price_min NUMERIC(12, 2),
+    price_max NUMERIC(12, 2),
+    body_style TEXT,
+    fuel_type TEXT,
+    transmission TEXT,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    last_digest_at TIMESTAMP
+);
+
+CREATE INDEX idx_saved_searches_user ON saved_searches(user_id);
+
+-- Auctions matched by a saved search; `notified_at` stays NULL until the digest goes out
+CREATE TABLE saved_search_matches (
+    id SERIAL PRIMARY KEY,
+    saved_search_id INT NOT NULL REFERENCES saved_searches(id) ON DELETE CASCADE,
+    auction_id INT NOT NULL REFERENCES auctions(id) ON DELETE CASCADE,
+    matched_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    notified_at TIMESTAMP,
+    UNIQUE (saved_search_id, auction_id)
+);
+
+CREATE INDEX idx_saved_search_matches_pending ON saved_search_matches(saved_search_id) WHERE notified_at IS NULL;
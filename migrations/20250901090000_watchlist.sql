@@ -0,0 +1,18 @@
+CREATE TABLE auction_watchers (
+    user_id INT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    auction_id INT NOT NULL REFERENCES auctions(id) ON DELETE CASCADE,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (user_id, auction_id)
+);
+
+CREATE INDEX idx_auction_watchers_auction ON auction_watchers(auction_id);
+
+-- One row per "ending soon" reminder sent, keyed by the offset (minutes before end_time) it was sent for
+CREATE TABLE watch_reminders (
+    user_id INT NOT NULL,
+    auction_id INT NOT NULL,
+    offset_minutes INT NOT NULL,
+    sent_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (user_id, auction_id, offset_minutes),
+    FOREIGN KEY (user_id, auction_id) REFERENCES auction_watchers(user_id, auction_id) ON DELETE CASCADE
+);
@@ -0,0 +1,53 @@
+-- Append-only chain of custody. Platform users are referenced by id; owners from before the
+-- vehicle was listed here only have a name and must be backed by a document of the vehicle.
+CREATE TABLE vehicle_ownership (
+    id SERIAL PRIMARY KEY,
+    vehicle_id INT NOT NULL REFERENCES vehicles(id) ON DELETE CASCADE,
+    kind TEXT NOT NULL CHECK (kind IN ('registration', 'sale', 'provenance')),
+    from_user_id INT REFERENCES users(id),
+    to_user_id INT REFERENCES users(id),
+    from_name VARCHAR(100),
+    to_name VARCHAR(100),
+    auction_id INT REFERENCES auctions(id) ON DELETE CASCADE,
+    price NUMERIC(12, 2),
+    transferred_at TIMESTAMP NOT NULL,
+    notes TEXT,
+    evidence_document_id INT REFERENCES vehicle_documents(id),
+    recorded_by INT REFERENCES users(id),
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    CHECK (kind = 'provenance' OR to_user_id IS NOT NULL),
+    CHECK (kind <> 'provenance' OR (to_name IS NOT NULL AND evidence_document_id IS NOT NULL)),
+    CHECK (kind <> 'sale' OR auction_id IS NOT NULL)
+);
+
+CREATE INDEX idx_vehicle_ownership_vehicle ON vehicle_ownership(vehicle_id, transferred_at, id);
+
+-- Rows may only disappear together with their vehicle, i.e. through a cascading delete
+CREATE FUNCTION vehicle_ownership_append_only() RETURNS trigger AS $$
+BEGIN
+    IF TG_OP = 'DELETE' AND pg_trigger_depth() > 1 THEN
+        RETURN OLD;
+    END IF;
+    RAISE EXCEPTION 'vehicle_ownership is append-only';
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER vehicle_ownership_append_only
+    BEFORE UPDATE OR DELETE ON vehicle_ownership
+    FOR EACH ROW EXECUTE FUNCTION vehicle_ownership_append_only();
+
+-- Backfill: the first seller (or the current owner when never auctioned) registered the vehicle
+INSERT INTO vehicle_ownership (vehicle_id, kind, to_user_id, transferred_at)
+SELECT v.id, 'registration',
+       COALESCE((SELECT a.seller_id FROM auctions a WHERE a.vehicle_id = v.id ORDER BY a.id LIMIT 1), v.owner_id),
+       v.created_at
+FROM vehicles v;
+
+-- ...and every settled auction moved it from the seller to the winner at the winning bid
+INSERT INTO vehicle_ownership (vehicle_id, kind, from_user_id, to_user_id, auction_id, price, transferred_at)
+SELECT a.vehicle_id, 'sale', a.seller_id, a.winner_id, a.id,
+       (SELECT MAX(b.bid_amount) FROM bids b WHERE b.auction_id = a.id AND b.bidder_id = a.winner_id),
+       COALESCE(a.closed_at, a.end_time)
+FROM auctions a
+WHERE a.winner_id IS NOT NULL
+ORDER BY COALESCE(a.closed_at, a.end_time), a.id;
@@ -0,0 +1,18 @@
+-- Vehicles are archived rather than deleted so auctions and bids stay available for audit
+ALTER TABLE vehicles
+    ADD COLUMN deleted_at TIMESTAMP,
+    ADD COLUMN deleted_by INT REFERENCES users(id);
+
+CREATE INDEX idx_vehicles_live ON vehicles(id) WHERE deleted_at IS NULL;
+
+-- A deleted vehicle's VIN may be listed again
+DROP INDEX idx_vehicles_vin_listed;
+CREATE UNIQUE INDEX idx_vehicles_vin_listed ON vehicles(vin) WHERE vin IS NOT NULL AND deleted_at IS NULL;
+
+ALTER TABLE users ADD COLUMN is_admin BOOLEAN NOT NULL DEFAULT FALSE;
+
+-- A hard delete must never take the bidding record with it
+ALTER TABLE auctions DROP CONSTRAINT auctions_vehicle_id_fkey,
+    ADD CONSTRAINT auctions_vehicle_id_fkey FOREIGN KEY (vehicle_id) REFERENCES vehicles(id) ON DELETE RESTRICT;
+ALTER TABLE bids DROP CONSTRAINT bids_auction_id_fkey,
+    ADD CONSTRAINT bids_auction_id_fkey FOREIGN KEY (auction_id) REFERENCES auctions(id) ON DELETE RESTRICT;
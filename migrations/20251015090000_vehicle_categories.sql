@@ -0,0 +1,38 @@
+-- Admin-managed category tree; a category cannot be removed while it has children or vehicles
+CREATE TABLE categories (
+    id SERIAL PRIMARY KEY,
+    parent_id INT REFERENCES categories(id) ON DELETE RESTRICT,
+    name VARCHAR(100) NOT NULL,
+    slug VARCHAR(100) NOT NULL UNIQUE,
+    position INT NOT NULL DEFAULT 0,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    CHECK (parent_id IS NULL OR parent_id <> id)
+);
+
+CREATE INDEX idx_categories_parent ON categories(parent_id);
+
+-- Every category paired with itself and each of its ancestors
+CREATE VIEW category_closure AS
+WITH RECURSIVE closure(category_id, ancestor_id, depth) AS (
+    SELECT id, id, 0 FROM categories
+    UNION ALL
+    SELECT closure.category_id, c.parent_id, closure.depth + 1
+    FROM closure INNER JOIN categories c ON c.id = closure.ancestor_id
+    WHERE c.parent_id IS NOT NULL
+)
+SELECT category_id, ancestor_id, depth FROM closure;
+
+INSERT INTO categories (name, slug, position) VALUES
+    ('Cars', 'cars', 0),
+    ('Motorcycles', 'motorcycles', 1),
+    ('Commercial', 'commercial', 2),
+    ('Parts', 'parts', 3),
+    ('Automobilia', 'automobilia', 4);
+INSERT INTO categories (parent_id, name, slug)
+    SELECT id, 'Sports cars', 'sports-cars' FROM categories WHERE slug = 'cars';
+INSERT INTO categories (parent_id, name, slug)
+    SELECT id, 'Roadsters', 'roadsters' FROM categories WHERE slug = 'sports-cars';
+
+ALTER TABLE vehicles ADD COLUMN category_id INT REFERENCES categories(id) ON DELETE RESTRICT;
+
+CREATE INDEX idx_vehicles_category ON vehicles(category_id);
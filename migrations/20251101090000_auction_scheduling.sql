@@ -0,0 +1,11 @@
+-- Auctions can be prepared as drafts and scheduled to open later; `closed` still marks the end
+ALTER TABLE auctions
+    ADD COLUMN status VARCHAR(20) NOT NULL DEFAULT 'live' CHECK (status IN ('draft', 'scheduled', 'live')),
+    ADD COLUMN start_time TIMESTAMP DEFAULT NOW();
+
+UPDATE auctions SET start_time = COALESCE(created_at, NOW());
+
+ALTER TABLE auctions ADD CONSTRAINT auctions_start_time_check
+    CHECK (status = 'draft' OR start_time IS NOT NULL);
+
+CREATE INDEX idx_auctions_scheduled ON auctions(start_time) WHERE status = 'scheduled';
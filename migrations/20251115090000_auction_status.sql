@@ -0,0 +1,48 @@
+-- The auction lifecycle becomes one status; every change goes through the application's transition
+-- function and is logged. `closed` remains as a read-only flag derived from the status.
+CREATE TYPE auction_status AS ENUM (
+    'draft', 'scheduled', 'live', 'ended_unsold', 'reserve_not_met', 'sold', 'cancelled', 'disputed'
+);
+
+ALTER TABLE auctions DROP CONSTRAINT auctions_start_time_check, DROP CONSTRAINT auctions_status_check;
+DROP INDEX idx_auctions_scheduled;
+DROP INDEX idx_auctions_open_vehicle;
+
+ALTER TABLE auctions ALTER COLUMN status DROP DEFAULT;
+ALTER TABLE auctions ALTER COLUMN status TYPE auction_status USING (
+    CASE
+        WHEN closed AND winner_id IS NOT NULL THEN 'sold'
+        WHEN closed THEN 'ended_unsold'
+        ELSE status
+    END
+)::auction_status;
+ALTER TABLE auctions ALTER COLUMN status SET DEFAULT 'live';
+
+ALTER TABLE auctions DROP COLUMN closed;
+ALTER TABLE auctions ADD COLUMN closed BOOLEAN NOT NULL
+    GENERATED ALWAYS AS (status NOT IN ('draft', 'scheduled', 'live')) STORED;
+
+-- Drafts may be discarded before they were ever given a start time
+ALTER TABLE auctions ADD CONSTRAINT auctions_start_time_check
+    CHECK (start_time IS NOT NULL OR status IN ('draft', 'cancelled'));
+CREATE INDEX idx_auctions_scheduled ON auctions(start_time) WHERE status = 'scheduled';
+CREATE INDEX idx_auctions_open_vehicle ON auctions(vehicle_id) WHERE closed = FALSE;
+
+CREATE TABLE auction_transitions (
+    id SERIAL PRIMARY KEY,
+    auction_id INT NOT NULL REFERENCES auctions(id) ON DELETE RESTRICT,
+    -- NULL for the entry recording the auction's creation
+    from_status auction_status,
+    to_status auction_status NOT NULL,
+    -- NULL when the system made the change, e.g. the scheduler opening an auction
+    actor_id INT REFERENCES users(id),
+    reason TEXT,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_auction_transitions_auction ON auction_transitions(auction_id, id);
+
+INSERT INTO auction_transitions (auction_id, from_status, to_status, actor_id, reason, created_at)
+SELECT id, NULL, status, seller_id, 'Recorded when auction statuses were introduced',
+       COALESCE(closed_at, created_at, NOW())
+FROM auctions ORDER BY id;
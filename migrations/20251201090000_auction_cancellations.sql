@@ -0,0 +1,17 @@
+-- Seller requests to withdraw an auction; those with bids need a fee or an administrator's approval
+CREATE TABLE auction_cancellations (
+    id SERIAL PRIMARY KEY,
+    auction_id INT NOT NULL REFERENCES auctions(id) ON DELETE RESTRICT,
+    requested_by INT NOT NULL REFERENCES users(id),
+    reason TEXT NOT NULL,
+    -- Charged to the seller when they chose to pay rather than wait for approval
+    fee NUMERIC(12, 2),
+    -- Requests still pending when the auction closes some other way expire with it
+    status TEXT NOT NULL CHECK (status IN ('pending', 'approved', 'rejected', 'completed', 'expired')),
+    decided_by INT REFERENCES users(id),
+    decided_at TIMESTAMP,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+-- At most one request awaiting a decision per auction
+CREATE UNIQUE INDEX idx_auction_cancellations_pending ON auction_cancellations(auction_id) WHERE status = 'pending';
@@ -0,0 +1,4 @@
+-- An auction relisted after ending unsold points at the listing it replaces
+ALTER TABLE auctions ADD COLUMN relisted_from INT REFERENCES auctions(id) ON DELETE RESTRICT;
+
+CREATE INDEX idx_auctions_relisted_from ON auctions(relisted_from) WHERE relisted_from IS NOT NULL;
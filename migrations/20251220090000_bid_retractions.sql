@@ -0,0 +1,14 @@
+-- Withdrawn bids are moved here so every high-bid calculation over `bids` stays correct
+CREATE TABLE bid_retractions (
+    id SERIAL PRIMARY KEY,
+    -- The id the bid had in `bids`
+    bid_id INT NOT NULL UNIQUE,
+    auction_id INT NOT NULL REFERENCES auctions(id) ON DELETE RESTRICT,
+    bidder_id INT NOT NULL REFERENCES users(id) ON DELETE RESTRICT,
+    bid_amount DECIMAL(10, 2) NOT NULL,
+    placed_at TIMESTAMP,
+    reason TEXT NOT NULL,
+    retracted_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_bid_retractions_bidder_id ON bid_retractions(bidder_id);
@@ -0,0 +1,17 @@
+CREATE TYPE auction_type AS ENUM ('english', 'sealed');
+
+ALTER TABLE auctions ADD COLUMN auction_type auction_type NOT NULL DEFAULT 'english';
+
+-- One encrypted bid per bidder on a sealed-bid auction; moved to `bids` in one go at settlement
+CREATE TABLE sealed_bids (
+    id SERIAL PRIMARY KEY,
+    auction_id INT NOT NULL REFERENCES auctions(id) ON DELETE RESTRICT,
+    bidder_id INT NOT NULL REFERENCES users(id) ON DELETE RESTRICT,
+    -- AES-256-GCM nonce, tag and ciphertext of the amount, bound to auction and bidder
+    sealed_amount BYTEA NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    UNIQUE (auction_id, bidder_id)
+);
+
+CREATE INDEX idx_sealed_bids_bidder_id ON sealed_bids(bidder_id);
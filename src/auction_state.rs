@@ -0,0 +1,136 @@
+use serde::{Deserialize, Serialize};
+use sqlx::PgConnection;
+use crate::errors::{ApiError, ErrorCode};
+
+/// Lifecycle of an auction, stored as the `auction_status` Postgres enum.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "auction_status", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum AuctionStatus {
+    /// Being prepared; only the seller can see it.
+    Draft,
+    /// Published with a future start time.
+    Scheduled,
+    /// Open for bids.
+    Live,
+    EndedUnsold,
+    ReserveNotMet,
+    Sold,
+    Cancelled,
+    /// A sale contested by the buyer or seller.
+    Disputed,
+}
+
+impl AuctionStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AuctionStatus::Draft => "draft",
+            AuctionStatus::Scheduled => "scheduled",
+            AuctionStatus::Live => "live",
+            AuctionStatus::EndedUnsold => "ended_unsold",
+            AuctionStatus::ReserveNotMet => "reserve_not_met",
+            AuctionStatus::Sold => "sold",
+            AuctionStatus::Cancelled => "cancelled",
+            AuctionStatus::Disputed => "disputed",
+        }
+    }
+
+    /// Whether bidding is over for good; mirrored by the `auctions.closed` column.
+    pub fn is_closed(self) -> bool {
+        !matches!(self, AuctionStatus::Draft | AuctionStatus::Scheduled | AuctionStatus::Live)
+    }
+
+    pub fn can_become(self, to: AuctionStatus) -> bool {
+        use AuctionStatus::*;
+        matches!(
+            (self, to),
+            (Draft, Scheduled | Live | Cancelled)
+                | (Scheduled, Live | Cancelled)
+                | (Live, EndedUnsold | ReserveNotMet | Sold | Cancelled)
+                | (Sold, Disputed)
+                | (Disputed, Sold | Cancelled)
+        )
+    }
+}
+
+/// Log the status a new auction was created with.
+pub async fn record_creation(
+    conn: &mut PgConnection,
+    auction_id: i32,
+    status: AuctionStatus,
+    actor_id: i32,
+) -> Result<(), ApiError> {
+    sqlx::query!(
+        "INSERT INTO auction_transitions (auction_id, from_status, to_status, actor_id) VALUES ($1, NULL, $2, $3)",
+        auction_id,
+        status as AuctionStatus,
+        actor_id
+    )
+    .execute(conn)
+    .await?;
+
+    Ok(())
+}
+
+/// Move an auction to `to`, refusing illegal transitions, and log who did it and why.
+///
+/// Locks the auction row for the rest of the caller's transaction. `actor_id` is `None` for
+/// changes made by the system. Returns the previous status.
+pub async fn transition(
+    conn: &mut PgConnection,
+    auction_id: i32,
+    to: AuctionStatus,
+    actor_id: Option<i32>,
+    reason: Option<&str>,
+) -> Result<AuctionStatus, ApiError> {
+    let from = sqlx::query_scalar!(
+        r#"SELECT status as "status: AuctionStatus" FROM auctions WHERE id = $1 FOR UPDATE"#,
+        auction_id
+    )
+    .fetch_optional(&mut *conn)
+    .await?
+    .ok_or_else(|| ApiError::new(ErrorCode::AuctionNotFound, "Auction not found"))?;
+
+    if !from.can_become(to) {
+        return Err(ApiError::new(
+            ErrorCode::IllegalAuctionTransition,
+            format!("An auction cannot go from {} to {}", from.as_str(), to.as_str()),
+        ));
+    }
+
+    // closed_at marks when bidding stopped, so later changes such as disputes keep it
+    sqlx::query!(
+        "UPDATE auctions SET status = $2, closed_at = CASE WHEN $3 THEN COALESCE(closed_at, NOW()) END
+         WHERE id = $1",
+        auction_id,
+        to as AuctionStatus,
+        to.is_closed()
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    // A request to withdraw the auction has nothing left to decide once bidding is over
+    if to.is_closed() && !from.is_closed() {
+        sqlx::query!(
+            "UPDATE auction_cancellations SET status = 'expired', decided_at = NOW()
+             WHERE auction_id = $1 AND status = 'pending'",
+            auction_id
+        )
+        .execute(&mut *conn)
+        .await?;
+    }
+
+    sqlx::query!(
+        "INSERT INTO auction_transitions (auction_id, from_status, to_status, actor_id, reason)
+         VALUES ($1, $2, $3, $4, $5)",
+        auction_id,
+        from as AuctionStatus,
+        to as AuctionStatus,
+        actor_id,
+        reason
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(from)
+}
@@ -0,0 +1,245 @@
+use actix_web::{error, http::StatusCode, web, HttpResponse, ResponseError};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Stable, machine-readable error codes returned in every error body.
+///
+/// Clients should branch on these rather than on the human readable message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    ValidationFailed,
+    InvalidPayload,
+    MissingSession,
+    InvalidSession,
+    InvalidCredentials,
+    UsernameTaken,
+    UserNotFound,
+    VehicleNotFound,
+    AuctionNotFound,
+    ImageNotFound,
+    DocumentNotFound,
+    SavedSearchNotFound,
+    NotificationNotFound,
+    CategoryNotFound,
+    CancellationNotFound,
+    BidNotFound,
+    NotOwner,
+    NotAdmin,
+    AuctionAlreadyOpen,
+    AuctionEnded,
+    AuctionClosed,
+    AuctionNotStarted,
+    AuctionNotEnded,
+    AuctionNotDraft,
+    AuctionNotRelistable,
+    IllegalAuctionTransition,
+    BidTooLow,
+    NoBids,
+    NotAuctionParty,
+    RatingWindowClosed,
+    AlreadyRated,
+    BuyerRatingTooLow,
+    AccountDeletionBlocked,
+    VehicleLocked,
+    VehicleHasLiveBids,
+    DuplicateVin,
+    SavedSearchLimitReached,
+    DocumentInUse,
+    CategoryInUse,
+    CancellationWindowClosed,
+    CancellationPending,
+    RetractionNotAllowed,
+    InvalidIdempotencyKey,
+    IdempotencyKeyInUse,
+    IdempotencyKeyReused,
+    SealedBiddingUnavailable,
+    DatabaseError,
+    CacheError,
+    StorageError,
+    InternalError,
+}
+
+impl ErrorCode {
+    pub fn status(self) -> StatusCode {
+        match self {
+            ErrorCode::ValidationFailed
+            | ErrorCode::InvalidPayload
+            | ErrorCode::AuctionEnded
+            | ErrorCode::AuctionClosed
+            | ErrorCode::AuctionNotStarted
+            | ErrorCode::AuctionNotEnded
+            | ErrorCode::BidTooLow
+            | ErrorCode::NoBids
+            | ErrorCode::RatingWindowClosed
+            | ErrorCode::InvalidIdempotencyKey => StatusCode::BAD_REQUEST,
+            ErrorCode::MissingSession
+            | ErrorCode::InvalidSession
+            | ErrorCode::InvalidCredentials => StatusCode::UNAUTHORIZED,
+            ErrorCode::NotOwner
+            | ErrorCode::NotAdmin
+            | ErrorCode::NotAuctionParty
+            | ErrorCode::BuyerRatingTooLow => StatusCode::FORBIDDEN,
+            ErrorCode::UserNotFound
+            | ErrorCode::VehicleNotFound
+            | ErrorCode::AuctionNotFound
+            | ErrorCode::ImageNotFound
+            | ErrorCode::DocumentNotFound
+            | ErrorCode::SavedSearchNotFound
+            | ErrorCode::NotificationNotFound
+            | ErrorCode::CategoryNotFound
+            | ErrorCode::CancellationNotFound
+            | ErrorCode::BidNotFound => StatusCode::NOT_FOUND,
+            ErrorCode::UsernameTaken
+            | ErrorCode::AuctionAlreadyOpen
+            | ErrorCode::AuctionNotDraft
+            | ErrorCode::AuctionNotRelistable
+            | ErrorCode::IllegalAuctionTransition
+            | ErrorCode::AlreadyRated
+            | ErrorCode::AccountDeletionBlocked
+            | ErrorCode::VehicleLocked
+            | ErrorCode::VehicleHasLiveBids
+            | ErrorCode::DuplicateVin
+            | ErrorCode::SavedSearchLimitReached
+            | ErrorCode::DocumentInUse
+            | ErrorCode::CategoryInUse
+            | ErrorCode::CancellationWindowClosed
+            | ErrorCode::CancellationPending
+            | ErrorCode::RetractionNotAllowed
+            | ErrorCode::IdempotencyKeyInUse => StatusCode::CONFLICT,
+            ErrorCode::IdempotencyKeyReused => StatusCode::UNPROCESSABLE_ENTITY,
+            ErrorCode::SealedBiddingUnavailable => StatusCode::SERVICE_UNAVAILABLE,
+            ErrorCode::DatabaseError
+            | ErrorCode::CacheError
+            | ErrorCode::StorageError
+            | ErrorCode::InternalError => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+/// JSON body sent for every failed request.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ErrorBody {
+    pub code: ErrorCode,
+    pub message: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub details: Vec<FieldError>,
+}
+
+/// Crate-wide error type for HTTP handlers.
+#[derive(Debug)]
+pub struct ApiError {
+    pub code: ErrorCode,
+    pub message: String,
+    pub details: Vec<FieldError>,
+}
+
+impl ApiError {
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        ApiError {
+            code,
+            message: message.into(),
+            details: Vec::new(),
+        }
+    }
+
+    pub fn validation(details: Vec<FieldError>) -> Self {
+        ApiError {
+            code: ErrorCode::ValidationFailed,
+            message: "Request validation failed".to_string(),
+            details,
+        }
+    }
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl ResponseError for ApiError {
+    fn status_code(&self) -> StatusCode {
+        self.code.status()
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        HttpResponse::build(self.status_code()).json(ErrorBody {
+            code: self.code,
+            message: self.message.clone(),
+            details: self.details.clone(),
+        })
+    }
+}
+
+impl From<sqlx::Error> for ApiError {
+    fn from(err: sqlx::Error) -> Self {
+        eprintln!("Database error: {:?}", err);
+        ApiError::new(ErrorCode::DatabaseError, "Database query error")
+    }
+}
+
+impl From<redis::RedisError> for ApiError {
+    fn from(err: redis::RedisError) -> Self {
+        eprintln!("Redis error: {:?}", err);
+        ApiError::new(ErrorCode::CacheError, "Session store error")
+    }
+}
+
+impl From<std::io::Error> for ApiError {
+    fn from(err: std::io::Error) -> Self {
+        eprintln!("Storage error: {:?}", err);
+        ApiError::new(ErrorCode::StorageError, "File storage error")
+    }
+}
+
+impl From<reqwest::Error> for ApiError {
+    fn from(err: reqwest::Error) -> Self {
+        eprintln!("Storage error: {:?}", err);
+        ApiError::new(ErrorCode::StorageError, "File storage error")
+    }
+}
+
+/// Collects field errors while validating a request payload.
+#[derive(Default)]
+pub struct Validator {
+    details: Vec<FieldError>,
+}
+
+impl Validator {
+    pub fn check(&mut self, ok: bool, field: &str, message: &str) -> &mut Self {
+        if !ok {
+            self.details.push(FieldError {
+                field: field.to_string(),
+                message: message.to_string(),
+            });
+        }
+        self
+    }
+
+    pub fn finish(&mut self) -> Result<(), ApiError> {
+        if self.details.is_empty() {
+            Ok(())
+        } else {
+            Err(ApiError::validation(std::mem::take(&mut self.details)))
+        }
+    }
+}
+
+/// JSON extractor config that reports malformed bodies with the common error model.
+pub fn json_config() -> web::JsonConfig {
+    web::JsonConfig::default().error_handler(|err, _req| {
+        let message = err.to_string();
+        error::InternalError::from_response(
+            err,
+            ApiError::new(ErrorCode::InvalidPayload, message).error_response(),
+        )
+        .into()
+    })
+}
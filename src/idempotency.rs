@@ -0,0 +1,256 @@
+use actix_web::body::{to_bytes, BoxBody, MessageBody};
+use actix_web::dev::{Payload, ServiceRequest, ServiceResponse};
+use actix_web::http::{header, Method, StatusCode};
+use actix_web::middleware::Next;
+use actix_web::{web, Error, HttpMessage, HttpResponse, ResponseError};
+use bytes::{Bytes, BytesMut};
+use futures_util::StreamExt;
+use redis::AsyncCommands;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
+use crate::errors::{ApiError, ErrorCode, FieldError};
+use crate::routes::document::MAX_DOCUMENT_BYTES;
+use crate::session::require_user;
+
+/// Request header carrying the client's idempotency key.
+pub const IDEMPOTENCY_KEY_HEADER: &str = "Idempotency-Key";
+/// Response header set when a stored response is replayed.
+pub const REPLAYED_HEADER: &str = "Idempotent-Replayed";
+/// How long completed responses are kept, unless `IDEMPOTENCY_TTL_SECONDS` overrides it.
+pub const DEFAULT_TTL_SECONDS: u64 = 24 * 3600;
+/// How long a key stays reserved without a refresh, so a request lost in a crash blocks retries at most this long.
+pub const IN_PROGRESS_TTL_SECONDS: u64 = 60;
+/// How often the reservation is renewed while its handler runs, well within [`IN_PROGRESS_TTL_SECONDS`].
+pub const IN_PROGRESS_REFRESH_SECONDS: u64 = IN_PROGRESS_TTL_SECONDS / 3;
+pub const MAX_KEY_LENGTH: usize = 255;
+/// Largest body buffered for a keyed request: the largest upload plus room for multipart framing.
+pub const MAX_BODY_BYTES: usize = MAX_DOCUMENT_BYTES + 64 * 1024;
+
+/// Completed-response TTL from `IDEMPOTENCY_TTL_SECONDS`.
+pub fn ttl_seconds() -> u64 {
+    std::env::var("IDEMPOTENCY_TTL_SECONDS")
+        .ok()
+        .and_then(|value| value.trim().parse().ok())
+        .filter(|seconds| *seconds > 0)
+        .unwrap_or(DEFAULT_TTL_SECONDS)
+}
+
+/// What Redis holds for a key; `status` stays empty while the first request is in flight.
+#[derive(Serialize, Deserialize)]
+struct StoredResponse {
+    fingerprint: String,
+    status: Option<u16>,
+    /// Header names with hex encoded values, as header values are not necessarily UTF-8.
+    headers: Vec<(String, String)>,
+    /// Hex encoded, as responses are not necessarily UTF-8.
+    body: String,
+}
+
+fn sha256_hex(parts: &[&[u8]]) -> String {
+    let mut hasher = Sha256::new();
+    for part in parts {
+        hasher.update((part.len() as u64).to_be_bytes());
+        hasher.update(part);
+    }
+    hex::encode(hasher.finalize())
+}
+
+enum Reservation {
+    /// The key is new; the request may go ahead.
+    Reserved,
+    /// The request was handled before; send this back.
+    Replay(HttpResponse),
+}
+
+/// Claim the key for this request, or find the response it already got.
+async fn reserve(
+    redis_conn: &mut redis::aio::MultiplexedConnection,
+    redis_key: &str,
+    fingerprint: &str,
+) -> Result<Reservation, ApiError> {
+    let reservation = serde_json::to_string(&StoredResponse {
+        fingerprint: fingerprint.to_string(),
+        status: None,
+        headers: Vec::new(),
+        body: String::new(),
+    })
+    .map_err(|_| ApiError::new(ErrorCode::InternalError, "Failed to store idempotency key"))?;
+    let reserved: Option<String> = redis::cmd("SET")
+        .arg(redis_key)
+        .arg(reservation)
+        .arg("NX")
+        .arg("EX")
+        .arg(IN_PROGRESS_TTL_SECONDS)
+        .query_async(&mut *redis_conn)
+        .await?;
+    if reserved.is_some() {
+        return Ok(Reservation::Reserved);
+    }
+
+    let in_progress = || {
+        ApiError::new(ErrorCode::IdempotencyKeyInUse, "A request with this idempotency key is in progress")
+    };
+    let stored: Option<String> = redis_conn.get(redis_key).await?;
+    let stored = stored
+        .and_then(|stored| serde_json::from_str::<StoredResponse>(&stored).ok())
+        .ok_or_else(in_progress)?;
+    if stored.fingerprint != fingerprint {
+        return Err(ApiError::new(
+            ErrorCode::IdempotencyKeyReused,
+            "This idempotency key was already used for a different request",
+        ));
+    }
+    let status = stored
+        .status
+        .and_then(|status| StatusCode::from_u16(status).ok())
+        .ok_or_else(in_progress)?;
+
+    let corrupt = || ApiError::new(ErrorCode::InternalError, "Stored response is corrupt");
+    let mut replay = HttpResponse::build(status);
+    for (name, value) in stored.headers {
+        replay.append_header((name, hex::decode(value).map_err(|_| corrupt())?));
+    }
+    replay.insert_header((REPLAYED_HEADER, "true"));
+    let body = hex::decode(stored.body).map_err(|_| corrupt())?;
+    Ok(Reservation::Replay(replay.body(body)))
+}
+
+/// Keep the response for replays; server errors release the key instead so the request can be retried.
+async fn finish(
+    redis_conn: &mut redis::aio::MultiplexedConnection,
+    redis_key: &str,
+    fingerprint: String,
+    status: StatusCode,
+    headers: &header::HeaderMap,
+    body: &[u8],
+) -> Result<(), ApiError> {
+    if status.is_server_error() {
+        let _: () = redis_conn.del(redis_key).await?;
+        return Ok(());
+    }
+
+    // The replayed body is sent whole, so its framing headers are worked out again
+    let headers = headers
+        .iter()
+        .filter(|(name, _)| !matches!(*name, &header::CONTENT_LENGTH | &header::TRANSFER_ENCODING))
+        .map(|(name, value)| (name.to_string(), hex::encode(value.as_bytes())))
+        .collect();
+    let stored = serde_json::to_string(&StoredResponse {
+        fingerprint,
+        status: Some(status.as_u16()),
+        headers,
+        body: hex::encode(body),
+    })
+    .map_err(|_| ApiError::new(ErrorCode::InternalError, "Failed to store idempotency key"))?;
+    let _: () = redis_conn.set_ex(redis_key, stored, ttl_seconds()).await?;
+    Ok(())
+}
+
+/// Make retried mutating requests safe.
+///
+/// A request with an `Idempotency-Key` header is handled once per key and user; repeats get the
+/// stored response back and reusing the key for a different request is refused. Server errors are
+/// not stored, so those requests can be retried with the same key. Requests without a valid session
+/// have nobody to scope the key to and are handled as if it were absent.
+///
+/// The key stays reserved for as long as the handler runs, however slow; only if the server dies
+/// mid-request does the reservation lapse, after at most [`IN_PROGRESS_TTL_SECONDS`].
+pub async fn idempotency(
+    mut req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<BoxBody>, Error> {
+    let key = match req.headers().get(IDEMPOTENCY_KEY_HEADER) {
+        Some(key) if !matches!(*req.method(), Method::GET | Method::HEAD | Method::OPTIONS) => key,
+        _ => return next.call(req).await.map(ServiceResponse::map_into_boxed_body),
+    };
+    let Some(key) = key.to_str().ok().filter(|key| !key.is_empty() && key.len() <= MAX_KEY_LENGTH) else {
+        let err = ApiError::new(
+            ErrorCode::InvalidIdempotencyKey,
+            format!("{} must be 1 to {} visible ASCII characters", IDEMPOTENCY_KEY_HEADER, MAX_KEY_LENGTH),
+        );
+        return Ok(req.into_response(err.error_response()));
+    };
+    let (Some(pool), Some(redis_client)) = (
+        req.app_data::<web::Data<PgPool>>().cloned(),
+        req.app_data::<web::Data<redis::Client>>().cloned(),
+    ) else {
+        return next.call(req).await.map(ServiceResponse::map_into_boxed_body);
+    };
+    // Keys are per user, so clients cannot collide or read each other's responses, and a retry
+    // after logging in again still finds its first attempt
+    let user = match require_user(req.request(), &pool, &redis_client).await {
+        Ok(user) => user,
+        Err(err) if matches!(err.code, ErrorCode::MissingSession | ErrorCode::InvalidSession) => {
+            return next.call(req).await.map(ServiceResponse::map_into_boxed_body);
+        }
+        Err(err) => return Ok(req.into_response(err.error_response())),
+    };
+    let redis_key = format!("idempotency:{}:{}", user.id, key);
+
+    // The handler still needs the body, so it is read here and put back; handlers cap it only later
+    let mut payload = req.take_payload();
+    let mut body = BytesMut::new();
+    while let Some(chunk) = payload.next().await {
+        let chunk = chunk?;
+        if body.len() + chunk.len() > MAX_BODY_BYTES {
+            let err = ApiError::validation(vec![FieldError {
+                field: "body".to_string(),
+                message: format!("must be at most {} bytes", MAX_BODY_BYTES),
+            }]);
+            return Ok(req.into_response(err.error_response()));
+        }
+        body.extend_from_slice(&chunk);
+    }
+    let body = body.freeze();
+    let fingerprint = sha256_hex(&[req.method().as_str().as_bytes(), req.uri().to_string().as_bytes(), &body]);
+    req.set_payload(Payload::Stream { payload: Box::pin(futures_util::stream::once(async move { Ok(body) })) });
+
+    let reservation = match redis_client.get_multiplexed_async_connection().await {
+        Ok(mut redis_conn) => reserve(&mut redis_conn, &redis_key, &fingerprint)
+            .await
+            .map(|reservation| (redis_conn, reservation)),
+        Err(err) => Err(ApiError::from(err)),
+    };
+    let mut redis_conn = match reservation {
+        Ok((redis_conn, Reservation::Reserved)) => redis_conn,
+        Ok((_, Reservation::Replay(replay))) => return Ok(req.into_response(replay)),
+        Err(err) => return Ok(req.into_response(err.error_response())),
+    };
+
+    // Renew the reservation while the handler runs, so a slow upload is not run again by a retry
+    let handled = next.call(req);
+    tokio::pin!(handled);
+    let mut refresh = tokio::time::interval(std::time::Duration::from_secs(IN_PROGRESS_REFRESH_SECONDS));
+    refresh.tick().await;
+    let handled = loop {
+        tokio::select! {
+            handled = &mut handled => break handled,
+            _ = refresh.tick() => {
+                let _: Result<bool, _> = redis_conn.expire(&redis_key, IN_PROGRESS_TTL_SECONDS as i64).await;
+            }
+        }
+    };
+
+    let res = match handled {
+        Ok(res) => res,
+        Err(err) => {
+            let _: Result<(), _> = redis_conn.del(&redis_key).await;
+            return Err(err);
+        }
+    };
+
+    let (http_req, http_res) = res.into_parts();
+    let (http_res, res_body) = http_res.into_parts();
+    let res_body: Bytes = match to_bytes(res_body).await {
+        Ok(res_body) => res_body,
+        Err(_) => {
+            let _: Result<(), _> = redis_conn.del(&redis_key).await;
+            return Err(ApiError::new(ErrorCode::InternalError, "Failed to read response").into());
+        }
+    };
+    // The request went through either way; without a stored response a retry simply runs again
+    let _ = finish(&mut redis_conn, &redis_key, fingerprint, http_res.status(), http_res.headers(), &res_body).await;
+
+    Ok(ServiceResponse::new(http_req, http_res.set_body(BoxBody::new(res_body))))
+}
@@ -0,0 +1,86 @@
+//! Decoding and resizing of uploaded vehicle photos.
+//!
+//! Only re-encoded derivatives are kept, never the uploaded file, so EXIF metadata
+//! (GPS position included) is dropped after its orientation has been applied.
+
+use image::codecs::jpeg::JpegEncoder;
+use image::{DynamicImage, ImageDecoder, ImageFormat, ImageReader, Limits};
+use std::io::Cursor;
+use crate::errors::{ApiError, ErrorCode, FieldError};
+
+/// Largest upload accepted, in bytes.
+pub const MAX_IMAGE_BYTES: usize = 10 * 1024 * 1024;
+/// Longest edge of the web-sized derivative.
+pub const WEB_MAX_DIMENSION: u32 = 1600;
+/// Longest edge of the thumbnail.
+pub const THUMBNAIL_MAX_DIMENSION: u32 = 320;
+/// Longest edge of an upload we are willing to decode.
+const MAX_SOURCE_DIMENSION: u32 = 12000;
+const JPEG_QUALITY: u8 = 85;
+
+pub const DERIVATIVE_CONTENT_TYPE: &str = "image/jpeg";
+
+pub struct ProcessedImage {
+    pub web: Vec<u8>,
+    pub thumbnail: Vec<u8>,
+    pub width: u32,
+    pub height: u32,
+}
+
+pub fn invalid_image(message: &str) -> ApiError {
+    ApiError::validation(vec![FieldError {
+        field: "image".to_string(),
+        message: message.to_string(),
+    }])
+}
+
+fn encode_jpeg(image: &DynamicImage) -> Result<Vec<u8>, ApiError> {
+    let mut buffer = Vec::new();
+    JpegEncoder::new_with_quality(&mut buffer, JPEG_QUALITY)
+        .encode_image(&image.to_rgb8())
+        .map_err(|_| ApiError::new(ErrorCode::InternalError, "Failed to encode image"))?;
+    Ok(buffer)
+}
+
+/// Validate an upload and produce its web-sized and thumbnail JPEGs.
+///
+/// CPU heavy; call it from a blocking task.
+pub fn process(data: &[u8]) -> Result<ProcessedImage, ApiError> {
+    if data.len() > MAX_IMAGE_BYTES {
+        return Err(invalid_image("must be at most 10 MB"));
+    }
+
+    let format = image::guess_format(data).ok();
+    if !matches!(format, Some(ImageFormat::Jpeg | ImageFormat::Png | ImageFormat::WebP)) {
+        return Err(invalid_image("must be a JPEG, PNG or WebP image"));
+    }
+
+    let mut limits = Limits::default();
+    limits.max_image_width = Some(MAX_SOURCE_DIMENSION);
+    limits.max_image_height = Some(MAX_SOURCE_DIMENSION);
+
+    let mut reader = ImageReader::new(Cursor::new(data));
+    reader.set_format(format.expect("format checked above"));
+    reader.limits(limits);
+
+    let mut decoder = reader.into_decoder().map_err(|_| invalid_image("could not be decoded"))?;
+    let orientation = decoder.orientation().ok();
+    let mut image = DynamicImage::from_decoder(decoder).map_err(|_| invalid_image("could not be decoded"))?;
+    if let Some(orientation) = orientation {
+        image.apply_orientation(orientation);
+    }
+
+    let web = if image.width() > WEB_MAX_DIMENSION || image.height() > WEB_MAX_DIMENSION {
+        image.resize(WEB_MAX_DIMENSION, WEB_MAX_DIMENSION, image::imageops::FilterType::Lanczos3)
+    } else {
+        image
+    };
+    let thumbnail = web.thumbnail(THUMBNAIL_MAX_DIMENSION, THUMBNAIL_MAX_DIMENSION);
+
+    Ok(ProcessedImage {
+        web: encode_jpeg(&web)?,
+        thumbnail: encode_jpeg(&thumbnail)?,
+        width: web.width(),
+        height: web.height(),
+    })
+}
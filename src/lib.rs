@@ -1,2 +1,12 @@
 pub mod routes;
-pub mod models;
\ No newline at end of file
+pub mod models;
+pub mod errors;
+pub mod session;
+pub mod vin;
+pub mod images;
+pub mod storage;
+pub mod notifications;
+pub mod scheduler;
+pub mod auction_state;
+pub mod idempotency;
+pub mod sealed;
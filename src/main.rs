@@ -1,12 +1,52 @@
-use actix_web::{web, App, HttpServer};
+use actix_web::{middleware, web, App, HttpServer};
 use sqlx::{Pool, Postgres};
 use sqlx::migrate;
 use redis::Client;
 mod routes;
 mod models;
-use crate::routes::user::{user_register, user_login} ;
-use crate::routes::vehicle::{create_vehicle, list_vehicles, delete_vehicle} ;
-use crate::routes::auction::{create_auction, place_bid, close_auction} ;
+mod errors;
+mod session;
+mod vin;
+mod images;
+mod storage;
+mod notifications;
+mod scheduler;
+mod auction_state;
+mod idempotency;
+mod sealed;
+use crate::routes::user::{
+    user_register, user_login, get_my_profile, update_my_profile, get_public_profile, export_my_data,
+    delete_my_account, change_username,
+};
+use crate::routes::vehicle::{
+    create_vehicle, list_vehicles, delete_vehicle, get_vehicle, update_vehicle, decode_vin, restore_vehicle,
+};
+use crate::routes::auction::{
+    create_auction, place_bid, close_auction, get_auction, preview_auction, update_auction, publish_auction,
+    delete_draft_auction, relist_auction,
+};
+use crate::routes::rating::rate_auction;
+use crate::routes::image::{
+    upload_vehicle_image, list_vehicle_images, reorder_vehicle_images, set_primary_image, delete_vehicle_image,
+    serve_media,
+};
+use crate::routes::document::{
+    upload_vehicle_document, list_vehicle_documents, download_vehicle_document, delete_vehicle_document,
+};
+use crate::routes::condition::{create_condition_report, list_condition_reports};
+use crate::routes::search::search_vehicles;
+use crate::routes::inventory::{import_vehicles, export_vehicles};
+use crate::routes::saved_search::{
+    create_saved_search, list_saved_searches, update_saved_search, delete_saved_search,
+};
+use crate::routes::notification::{list_notifications, mark_notification_read};
+use crate::routes::history::{get_vehicle_history, add_provenance_entry};
+use crate::routes::watchlist::{watch_auction, unwatch_auction, get_my_watchlist};
+use crate::routes::category::{list_categories, create_category, update_category, delete_category};
+use crate::routes::bid::{retract_bid, list_auction_bids};
+use crate::routes::cancellation::{
+    cancel_auction, list_pending_cancellations, approve_cancellation, reject_cancellation,
+};
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
@@ -21,13 +61,21 @@ async fn main() -> std::io::Result<()> {
     let redis_client = Client::open(redis_client_url).expect("Failed to create redis_client");
 
     // Run database migrations
-    let _migrate = migrate!("./migrations").run(&pool).await.expect("Failed to run migrations");
+    migrate!("./migrations").run(&pool).await.expect("Failed to run migrations");
+
+    let storage = web::Data::from(storage::from_env());
+
+    // Background jobs: scheduled auction starts, saved-search digests and watchlist reminders
+    scheduler::spawn(pool.clone());
 
     // Start Actix Web server
     HttpServer::new(move || {
         App::new()
             .app_data(web::Data::new(pool.clone()))
             .app_data(web::Data::new(redis_client.clone()))
+            .app_data(storage.clone())
+            .app_data(errors::json_config())
+            .wrap(middleware::from_fn(idempotency::idempotency))
             .configure(routes)
     })
     .bind("127.0.0.1:8080")?
@@ -39,13 +87,67 @@ fn routes(cfg: &mut web::ServiceConfig) {
     cfg
         .service(web::scope("/users")
             .route("/register", web::post().to(user_register))
-            .route("/login", web::post().to(user_login)))
+            .route("/login", web::post().to(user_login))
+            .route("/me", web::get().to(get_my_profile))
+            .route("/me", web::patch().to(update_my_profile))
+            .route("/me", web::delete().to(delete_my_account))
+            .route("/me/export", web::get().to(export_my_data))
+            .route("/me/username", web::patch().to(change_username))
+            .route("/me/saved-searches", web::post().to(create_saved_search))
+            .route("/me/saved-searches", web::get().to(list_saved_searches))
+            .route("/me/saved-searches/{id}", web::patch().to(update_saved_search))
+            .route("/me/saved-searches/{id}", web::delete().to(delete_saved_search))
+            .route("/me/watchlist", web::get().to(get_my_watchlist))
+            .route("/me/notifications", web::get().to(list_notifications))
+            .route("/me/notifications/{id}/read", web::post().to(mark_notification_read))
+            .route("/{username}", web::get().to(get_public_profile)))
         .service(web::scope("/vehicles")
             .route("/create", web::post().to(create_vehicle))
             .route("/list", web::get().to(list_vehicles))
-            .route("/delete/{id}", web::delete().to(delete_vehicle)))
+            .route("/search", web::get().to(search_vehicles))
+            .route("/import", web::post().to(import_vehicles))
+            .route("/export", web::get().to(export_vehicles))
+            .route("/delete/{id}", web::delete().to(delete_vehicle))
+            .route("/vin/{vin}", web::get().to(decode_vin))
+            .route("/{id}", web::get().to(get_vehicle))
+            .route("/{id}", web::patch().to(update_vehicle))
+            .route("/{id}/images", web::post().to(upload_vehicle_image))
+            .route("/{id}/images", web::get().to(list_vehicle_images))
+            .route("/{id}/images/order", web::put().to(reorder_vehicle_images))
+            .route("/{id}/images/{image_id}/primary", web::post().to(set_primary_image))
+            .route("/{id}/images/{image_id}", web::delete().to(delete_vehicle_image))
+            .route("/{id}/documents", web::post().to(upload_vehicle_document))
+            .route("/{id}/documents", web::get().to(list_vehicle_documents))
+            .route("/{id}/documents/{document_id}/file", web::get().to(download_vehicle_document))
+            .route("/{id}/documents/{document_id}", web::delete().to(delete_vehicle_document))
+            .route("/{id}/condition-reports", web::post().to(create_condition_report))
+            .route("/{id}/condition-reports", web::get().to(list_condition_reports))
+            .route("/{id}/history", web::get().to(get_vehicle_history))
+            .route("/{id}/history", web::post().to(add_provenance_entry)))
+        .route("/media/{key:.*}", web::get().to(serve_media))
+        .route("/categories", web::get().to(list_categories))
         .service(web::scope("/auctions")
             .route("/create", web::post().to(create_auction))
             .route("/bid", web::post().to(place_bid))
-            .route("/close/{id}", web::post().to(close_auction)));
+            .route("/bids/{id}/retract", web::post().to(retract_bid))
+            .route("/close/{id}", web::post().to(close_auction))
+            .route("/{id}", web::get().to(get_auction))
+            .route("/{id}", web::patch().to(update_auction))
+            .route("/{id}", web::delete().to(delete_draft_auction))
+            .route("/{id}/preview", web::get().to(preview_auction))
+            .route("/{id}/publish", web::post().to(publish_auction))
+            .route("/{id}/cancel", web::post().to(cancel_auction))
+            .route("/{id}/relist", web::post().to(relist_auction))
+            .route("/{id}/bids", web::get().to(list_auction_bids))
+            .route("/{id}/rating", web::post().to(rate_auction))
+            .route("/{id}/watch", web::post().to(watch_auction))
+            .route("/{id}/watch", web::delete().to(unwatch_auction)))
+        .service(web::scope("/admin")
+            .route("/vehicles/{id}/restore", web::post().to(restore_vehicle))
+            .route("/categories", web::post().to(create_category))
+            .route("/categories/{id}", web::patch().to(update_category))
+            .route("/categories/{id}", web::delete().to(delete_category))
+            .route("/cancellations", web::get().to(list_pending_cancellations))
+            .route("/cancellations/{id}/approve", web::post().to(approve_cancellation))
+            .route("/cancellations/{id}/reject", web::post().to(reject_cancellation)));
 }
@@ -1,6 +1,9 @@
 use serde::{Deserialize, Serialize, Deserializer};
-use chrono::NaiveDateTime;
+use chrono::{DateTime, Datelike, NaiveDate, NaiveDateTime, Utc};
 use bigdecimal::BigDecimal;
+use crate::auction_state::AuctionStatus;
+use crate::errors::{ApiError, Validator};
+use crate::vin;
 
 fn deserialize_naive_datetime<'de, D>(deserializer: D) -> Result<NaiveDateTime, D::Error>
 where
@@ -11,12 +14,63 @@ where
             .map_err(serde::de::Error::custom)
     }
 
+fn deserialize_optional_naive_datetime<'de, D>(deserializer: D) -> Result<Option<NaiveDateTime>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Option::<String>::deserialize(deserializer)?
+        .map(|s| NaiveDateTime::parse_from_str(&s, "%Y-%m-%dT%H:%M:%S").map_err(serde::de::Error::custom))
+        .transpose()
+}
+
+/// Tells an explicit `null` apart from an absent field in partial updates.
+fn deserialize_present<'de, T, D>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    T: Deserialize<'de>,
+    D: Deserializer<'de>,
+{
+    T::deserialize(deserializer).map(Some)
+}
+
+/// Deleted accounts are renamed to this prefix followed by their id.
+pub const DELETED_USER_PREFIX: &str = "deleted-user-";
+
 #[derive(Deserialize)]
 pub struct UserRegister {
     pub username: String,
     pub password: String,
 }
 
+fn check_username(validator: &mut Validator, username: &str) {
+    validator
+        .check(!username.trim().is_empty(), "username", "must not be empty")
+        .check(username.len() <= 255, "username", "must be at most 255 characters")
+        .check(!username.starts_with(DELETED_USER_PREFIX), "username", "is reserved");
+}
+
+impl UserRegister {
+    pub fn validate(&self) -> Result<(), ApiError> {
+        let mut validator = Validator::default();
+        check_username(&mut validator, &self.username);
+        validator
+            .check(!self.password.is_empty(), "password", "must not be empty")
+            .finish()
+    }
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct ChangeUsername {
+    pub username: String,
+}
+
+impl ChangeUsername {
+    pub fn validate(&self) -> Result<(), ApiError> {
+        let mut validator = Validator::default();
+        check_username(&mut validator, &self.username);
+        validator.finish()
+    }
+}
+
 #[derive(Deserialize)]
 pub struct UserLogin {
     // #[serde(skip_deserializing)]
@@ -25,21 +79,569 @@ pub struct UserLogin {
     pub password: String,
 }
 
-#[derive(Deserialize, Serialize)]
+#[derive(Serialize, Deserialize)]
+pub struct UserSummary {
+    pub id: i32,
+    pub username: String,
+    pub created_at: Option<NaiveDateTime>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct LoginResponse {
+    pub session_code: String,
+    pub expires_at: DateTime<Utc>,
+    pub user: UserSummary,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct UserProfile {
+    pub id: i32,
+    pub username: String,
+    pub display_name: Option<String>,
+    pub location: Option<String>,
+    pub bio: Option<String>,
+    pub avatar_url: Option<String>,
+    pub is_dealer: bool,
+    pub created_at: Option<NaiveDateTime>,
+}
+
+/// Partial profile update; absent fields are left unchanged and empty strings clear a field.
+#[derive(Deserialize, Serialize, Default)]
+pub struct UpdateProfile {
+    pub display_name: Option<String>,
+    pub location: Option<String>,
+    pub bio: Option<String>,
+    pub avatar_url: Option<String>,
+    pub is_dealer: Option<bool>,
+}
+
+impl UpdateProfile {
+    pub fn validate(&self) -> Result<(), ApiError> {
+        let len = |value: &Option<String>| value.as_deref().map_or(0, str::len);
+        Validator::default()
+            .check(len(&self.display_name) <= 255, "display_name", "must be at most 255 characters")
+            .check(len(&self.location) <= 255, "location", "must be at most 255 characters")
+            .check(len(&self.bio) <= 2000, "bio", "must be at most 2000 characters")
+            .check(
+                self.avatar_url.as_deref().is_none_or(|url| {
+                    url.is_empty() || url.starts_with("https://") || url.starts_with("http://")
+                }),
+                "avatar_url",
+                "must be an http(s) URL",
+            )
+            .finish()
+    }
+}
+
+/// Personal data archive for `GET /users/me/export`.
+#[derive(Serialize, Deserialize)]
+pub struct UserExport {
+    pub exported_at: DateTime<Utc>,
+    pub profile: UserProfile,
+    pub vehicles: Vec<Vehicle>,
+    pub auctions: Vec<AuctionSummary>,
+    pub bids: Vec<BidReceipt>,
+    pub retracted_bids: Vec<RetractedBid>,
+    /// Bids on sealed-bid auctions not settled yet; only the bidder ever sees these amounts early.
+    pub sealed_bids: Vec<SealedBid>,
+    pub ratings_given: Vec<Rating>,
+    pub ratings_received: Vec<Rating>,
+    pub saved_searches: Vec<SavedSearch>,
+    pub notifications: Vec<Notification>,
+    pub watchlist: Vec<WatchedAuction>,
+}
+
+/// Public seller page for `GET /users/{username}`.
+#[derive(Serialize, Deserialize)]
+pub struct PublicProfile {
+    pub profile: UserProfile,
+    pub vehicles: Vec<Vehicle>,
+    pub active_auctions: Vec<AuctionSummary>,
+    pub sales_count: i64,
+    pub reputation: Reputation,
+}
+
+pub const MILEAGE_UNITS: &[&str] = &["km", "mi"];
+pub const FUEL_TYPES: &[&str] = &["petrol", "diesel", "electric", "hybrid", "lpg", "other"];
+pub const TRANSMISSIONS: &[&str] = &["manual", "automatic", "semi_automatic"];
+pub const BODY_STYLES: &[&str] = &[
+    "coupe", "convertible", "roadster", "sedan", "hatchback", "wagon", "suv", "pickup", "van", "motorcycle", "other",
+];
+
+/// Structured specification shared by vehicle create and update payloads.
+///
+/// Condition grade follows the classic car scale: 1 concours to 5 project car.
+#[derive(Deserialize, Serialize, Default, Clone)]
+pub struct VehicleSpec {
+    pub make: Option<String>,
+    pub model: Option<String>,
+    pub year: Option<i16>,
+    pub vin: Option<String>,
+    pub mileage: Option<i32>,
+    pub mileage_unit: Option<String>,
+    pub fuel_type: Option<String>,
+    pub transmission: Option<String>,
+    pub body_style: Option<String>,
+    pub colour: Option<String>,
+    pub engine: Option<String>,
+    pub matching_numbers: Option<bool>,
+    pub registration_country: Option<String>,
+    pub condition_grade: Option<i16>,
+}
+
+impl VehicleSpec {
+    fn check(&self, validator: &mut Validator) {
+        let max_len = |value: &Option<String>, max: usize| value.as_deref().is_none_or(|v| v.len() <= max);
+        let one_of = |value: &Option<String>, allowed: &[&str]| value.as_deref().is_none_or(|v| allowed.contains(&v));
+        let max_year = Utc::now().year() as i16 + 1;
+
+        validator
+            .check(max_len(&self.make, 100), "make", "must be at most 100 characters")
+            .check(max_len(&self.model, 100), "model", "must be at most 100 characters")
+            .check(
+                self.year.is_none_or(|year| (1885..=max_year).contains(&year)),
+                "year",
+                "must be between 1885 and next year",
+            )
+            .check(self.mileage.is_none_or(|mileage| mileage >= 0), "mileage", "must not be negative")
+            .check(one_of(&self.mileage_unit, MILEAGE_UNITS), "mileage_unit", "must be km or mi")
+            .check(
+                self.mileage.is_none() || self.mileage_unit.is_some(),
+                "mileage_unit",
+                "is required when mileage is given",
+            )
+            .check(one_of(&self.fuel_type, FUEL_TYPES), "fuel_type", "is not a supported fuel type")
+            .check(one_of(&self.transmission, TRANSMISSIONS), "transmission", "is not a supported transmission")
+            .check(one_of(&self.body_style, BODY_STYLES), "body_style", "is not a supported body style")
+            .check(max_len(&self.colour, 50), "colour", "must be at most 50 characters")
+            .check(max_len(&self.engine, 100), "engine", "must be at most 100 characters")
+            .check(
+                self.registration_country
+                    .as_deref()
+                    .is_none_or(|c| c.len() == 2 && c.chars().all(|ch| ch.is_ascii_uppercase())),
+                "registration_country",
+                "must be an ISO 3166-1 alpha-2 code",
+            )
+            .check(
+                self.condition_grade.is_none_or(|grade| (1..=5).contains(&grade)),
+                "condition_grade",
+                "must be between 1 and 5",
+            );
+    }
+
+    /// Check the VIN against the model year it will be stored with.
+    pub fn check_vin(&self, validator: &mut Validator, year: Option<i16>) {
+        if let Some(Err(message)) = self.vin.as_deref().map(|vin| vin::validate(&vin::normalize(vin), year)) {
+            validator.check(false, "vin", message);
+        }
+    }
+
+    /// Store the VIN in its normalized form.
+    pub fn normalize_vin(&mut self) {
+        self.vin = self.vin.as_deref().map(vin::normalize);
+    }
+
+    /// Normalize the VIN and fill a missing make and year from its decoded form.
+    pub fn fill_from_vin(&mut self) {
+        self.normalize_vin();
+        if let Some(info) = self.vin.as_deref().and_then(vin::decode) {
+            self.make = self.make.take().or(info.manufacturer);
+            self.year = self.year.or(info.model_year);
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Default)]
 pub struct CreateVehicle {
     pub name: String,
     pub description: String,
     pub starting_price: f64,
     // #[serde(skip_deserializing)]
     // pub owner_username: i32,
+    pub category_id: Option<i32>,
+    #[serde(flatten)]
+    pub spec: VehicleSpec,
 }
 
-#[derive(Serialize, Deserialize)]
+impl CreateVehicle {
+    pub fn validate(&self) -> Result<(), ApiError> {
+        let mut validator = Validator::default();
+        validator
+            .check(!self.name.trim().is_empty(), "name", "must not be empty")
+            .check(self.name.len() <= 255, "name", "must be at most 255 characters")
+            .check(self.starting_price > 0.0, "starting_price", "must be greater than 0");
+        self.spec.check(&mut validator);
+        self.spec.check_vin(&mut validator, self.spec.year);
+        validator.finish()
+    }
+}
+
+#[derive(Serialize, Deserialize, sqlx::FromRow)]
 pub struct Vehicle {
     pub id: i32,
     pub name: String,
     pub description: String,
     pub starting_price: BigDecimal,
+    pub make: Option<String>,
+    pub model: Option<String>,
+    pub year: Option<i16>,
+    pub vin: Option<String>,
+    pub mileage: Option<i32>,
+    pub mileage_unit: Option<String>,
+    pub fuel_type: Option<String>,
+    pub transmission: Option<String>,
+    pub body_style: Option<String>,
+    pub colour: Option<String>,
+    pub engine: Option<String>,
+    pub matching_numbers: Option<bool>,
+    pub registration_country: Option<String>,
+    pub condition_grade: Option<i16>,
+    pub category_id: Option<i32>,
+}
+
+/// Query string filters for `GET /vehicles/list`.
+#[derive(Deserialize, Serialize, Default)]
+pub struct VehicleFilter {
+    pub make: Option<String>,
+    pub model: Option<String>,
+    pub year_min: Option<i16>,
+    pub year_max: Option<i16>,
+    pub fuel_type: Option<String>,
+    pub transmission: Option<String>,
+    pub body_style: Option<String>,
+    pub matching_numbers: Option<bool>,
+    pub registration_country: Option<String>,
+    /// Only vehicles in at least this condition (1 is best).
+    pub condition_grade_max: Option<i16>,
+    pub price_min: Option<f64>,
+    pub price_max: Option<f64>,
+    /// Username of the current owner.
+    pub owner: Option<String>,
+    pub has_open_auction: Option<bool>,
+    /// Category slug; vehicles in its subcategories match too.
+    pub category: Option<String>,
+    /// Vehicles whose latest auction sold them are hidden unless this is set.
+    #[serde(default)]
+    pub include_sold: bool,
+    /// Free text matched against name, description, make and model.
+    pub q: Option<String>,
+    /// One of `VEHICLE_SORTS`; defaults to `newest`.
+    pub sort: Option<String>,
+    /// Opaque `next_cursor` from the previous page.
+    pub cursor: Option<String>,
+    pub limit: Option<i64>,
+}
+
+pub const VEHICLE_SORTS: &[&str] = &["newest", "oldest", "price_asc", "price_desc"];
+pub const DEFAULT_PAGE_SIZE: i64 = 20;
+pub const MAX_PAGE_SIZE: i64 = 100;
+
+impl VehicleFilter {
+    pub fn validate(&self) -> Result<(), ApiError> {
+        Validator::default()
+            .check(
+                self.sort.as_deref().is_none_or(|sort| VEHICLE_SORTS.contains(&sort)),
+                "sort",
+                "must be newest, oldest, price_asc or price_desc",
+            )
+            .check(
+                self.limit.is_none_or(|limit| (1..=MAX_PAGE_SIZE).contains(&limit)),
+                "limit",
+                "must be between 1 and 100",
+            )
+            .check(self.price_min.is_none_or(|price| price >= 0.0), "price_min", "must not be negative")
+            .check(
+                self.price_min.zip(self.price_max).is_none_or(|(min, max)| min <= max),
+                "price_max",
+                "must not be below price_min",
+            )
+            .check(self.q.as_deref().map_or(0, str::len) <= 200, "q", "must be at most 200 characters")
+            .finish()
+    }
+}
+
+/// One page of `GET /vehicles/list`.
+#[derive(Serialize, Deserialize)]
+pub struct VehiclePage {
+    pub items: Vec<Vehicle>,
+    /// Number of vehicles matching the filters across all pages.
+    pub total_count: i64,
+    /// Pass as `cursor` to fetch the next page; absent on the last page.
+    pub next_cursor: Option<String>,
+    /// Matching vehicles per category slug, each counted in its category and all ancestors.
+    pub category_counts: Vec<FacetCount>,
+}
+
+/// Query string for `GET /vehicles/search`.
+#[derive(Deserialize, Serialize, Default)]
+pub struct SearchQuery {
+    pub q: String,
+    pub make: Option<String>,
+    /// First year of a decade, e.g. 1960.
+    pub decade: Option<i16>,
+    pub price_min: Option<f64>,
+    pub price_max: Option<f64>,
+    /// Category slug; vehicles in its subcategories match too.
+    pub category: Option<String>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+impl SearchQuery {
+    pub fn validate(&self) -> Result<(), ApiError> {
+        Validator::default()
+            .check(!self.q.trim().is_empty(), "q", "must not be empty")
+            .check(self.q.len() <= 200, "q", "must be at most 200 characters")
+            .check(self.decade.is_none_or(|decade| decade % 10 == 0), "decade", "must be the first year of a decade")
+            .check(
+                self.limit.is_none_or(|limit| (1..=MAX_PAGE_SIZE).contains(&limit)),
+                "limit",
+                "must be between 1 and 100",
+            )
+            .check(self.offset.is_none_or(|offset| offset >= 0), "offset", "must not be negative")
+            .finish()
+    }
+}
+
+/// A search result with its relevance and HTML-escaped highlights wrapped in `<mark>`.
+#[derive(Serialize, Deserialize)]
+pub struct SearchHit {
+    #[serde(flatten)]
+    pub vehicle: Vehicle,
+    pub rank: f64,
+    pub name_highlight: String,
+    pub description_highlight: String,
+}
+
+#[derive(Serialize, Deserialize, sqlx::FromRow)]
+pub struct FacetCount {
+    pub value: String,
+    pub count: i64,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct SearchFacets {
+    pub makes: Vec<FacetCount>,
+    pub decades: Vec<FacetCount>,
+    pub price_buckets: Vec<FacetCount>,
+    pub categories: Vec<FacetCount>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct SearchResults {
+    pub items: Vec<SearchHit>,
+    pub total_count: i64,
+    /// Set when nothing matched exactly and results come from fuzzy matching.
+    pub fuzzy: bool,
+    pub facets: SearchFacets,
+}
+
+/// Partial vehicle update; absent fields are left unchanged.
+#[derive(Deserialize, Serialize, Default)]
+pub struct UpdateVehicle {
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub starting_price: Option<f64>,
+    pub category_id: Option<i32>,
+    #[serde(flatten)]
+    pub spec: VehicleSpec,
+}
+
+impl UpdateVehicle {
+    pub fn validate(&self) -> Result<(), ApiError> {
+        let mut validator = Validator::default();
+        validator
+            .check(
+                self.name.as_deref().is_none_or(|name| !name.trim().is_empty() && name.len() <= 255),
+                "name",
+                "must be between 1 and 255 characters",
+            )
+            .check(
+                self.starting_price.is_none_or(|price| price > 0.0),
+                "starting_price",
+                "must be greater than 0",
+            );
+        self.spec.check(&mut validator);
+        validator.finish()
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct VehicleEdit {
+    pub auction_id: i32,
+    pub field: String,
+    pub old_value: Option<String>,
+    pub new_value: Option<String>,
+    pub edited_at: Option<NaiveDateTime>,
+}
+
+/// Full vehicle view for `GET /vehicles/{id}`.
+#[derive(Serialize, Deserialize)]
+pub struct VehicleDetail {
+    #[serde(flatten)]
+    pub vehicle: Vehicle,
+    pub owner_username: String,
+    pub created_at: NaiveDateTime,
+    pub open_auction_id: Option<i32>,
+    /// Changes made while the vehicle was up for auction.
+    pub edits: Vec<VehicleEdit>,
+    pub images: Vec<VehicleImage>,
+}
+
+/// Uploaded photo of a vehicle, in display order.
+#[derive(Serialize, Deserialize)]
+pub struct VehicleImage {
+    pub id: i32,
+    pub position: i32,
+    pub is_primary: bool,
+    pub url: String,
+    pub thumbnail_url: String,
+    pub width: i32,
+    pub height: i32,
+    pub created_at: NaiveDateTime,
+}
+
+/// New display order for `PUT /vehicles/{id}/images/order`, listing every image id.
+#[derive(Serialize, Deserialize)]
+pub struct ReorderImages {
+    pub image_ids: Vec<i32>,
+}
+
+pub const DOCUMENT_TYPES: &[&str] = &["service_history", "title", "registration", "inspection_report", "invoice", "other"];
+
+/// Attachment on a vehicle; only signed-in users may list or download it.
+#[derive(Serialize, Deserialize)]
+pub struct VehicleDocument {
+    pub id: i32,
+    pub doc_type: String,
+    pub title: String,
+    pub content_type: String,
+    pub byte_size: i32,
+    pub uploaded_by: String,
+    pub url: String,
+    pub created_at: NaiveDateTime,
+}
+
+pub const REPORT_PANELS: &[&str] = &[
+    "front_bumper", "bonnet", "front_left_wing", "front_right_wing", "left_door", "right_door", "left_sill",
+    "right_sill", "roof", "boot_lid", "rear_left_quarter", "rear_right_quarter", "rear_bumper", "floor_pans",
+    "chassis_rails", "windscreen",
+];
+pub const MECHANICAL_COMPONENTS: &[&str] = &[
+    "engine", "gearbox", "clutch", "brakes", "suspension", "steering", "exhaust", "cooling", "fuel_system",
+    "electrics", "tyres",
+];
+pub const ITEM_RATINGS: &[&str] = &["excellent", "good", "fair", "poor", "damaged", "missing"];
+
+/// One panel or mechanical component in a condition report.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ConditionItem {
+    pub item: String,
+    pub rating: String,
+    pub notes: Option<String>,
+}
+
+fn check_items(validator: &mut Validator, section: &str, items: &[ConditionItem], allowed: &[&str]) {
+    for (index, entry) in items.iter().enumerate() {
+        let field = format!("{}[{}]", section, index);
+        validator
+            .check(allowed.contains(&entry.item.as_str()), &field, "is not a known item")
+            .check(ITEM_RATINGS.contains(&entry.rating.as_str()), &field, "has an unsupported rating")
+            .check(
+                items[..index].iter().all(|other| other.item != entry.item),
+                &field,
+                "is listed more than once",
+            )
+            .check(
+                entry.notes.as_deref().map_or(0, str::len) <= 1000,
+                &field,
+                "notes must be at most 1000 characters",
+            );
+    }
+}
+
+/// Payload for `POST /vehicles/{id}/condition-reports`.
+///
+/// Rust rating runs from 1 (none) to 5 (severe). Photos reference the vehicle gallery.
+#[derive(Serialize, Deserialize, Default)]
+pub struct CreateConditionReport {
+    pub inspection_date: NaiveDate,
+    pub rust_rating: i16,
+    #[serde(default)]
+    pub overall_notes: Option<String>,
+    #[serde(default)]
+    pub third_party_inspected: bool,
+    #[serde(default)]
+    pub inspector_name: Option<String>,
+    #[serde(default)]
+    pub inspection_document_id: Option<i32>,
+    #[serde(default)]
+    pub panels: Vec<ConditionItem>,
+    #[serde(default)]
+    pub mechanical: Vec<ConditionItem>,
+    #[serde(default)]
+    pub photo_ids: Vec<i32>,
+}
+
+impl CreateConditionReport {
+    pub fn validate(&self) -> Result<(), ApiError> {
+        let mut validator = Validator::default();
+        validator
+            .check(
+                self.inspection_date <= Utc::now().date_naive(),
+                "inspection_date",
+                "must not be in the future",
+            )
+            .check((1..=5).contains(&self.rust_rating), "rust_rating", "must be between 1 and 5")
+            .check(
+                self.overall_notes.as_deref().map_or(0, str::len) <= 5000,
+                "overall_notes",
+                "must be at most 5000 characters",
+            )
+            .check(
+                !self.third_party_inspected
+                    || self.inspector_name.as_deref().is_some_and(|name| !name.trim().is_empty()),
+                "inspector_name",
+                "is required for third-party inspections",
+            )
+            .check(
+                self.inspector_name.as_deref().map_or(0, str::len) <= 255,
+                "inspector_name",
+                "must be at most 255 characters",
+            );
+        check_items(&mut validator, "panels", &self.panels, REPORT_PANELS);
+        check_items(&mut validator, "mechanical", &self.mechanical, MECHANICAL_COMPONENTS);
+        validator.finish()
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ConditionReport {
+    pub id: i32,
+    pub vehicle_id: i32,
+    pub author_username: String,
+    pub inspection_date: NaiveDate,
+    pub rust_rating: i16,
+    pub overall_notes: Option<String>,
+    pub third_party_inspected: bool,
+    pub inspector_name: Option<String>,
+    pub inspection_document_id: Option<i32>,
+    pub panels: Vec<ConditionItem>,
+    pub mechanical: Vec<ConditionItem>,
+    pub photos: Vec<VehicleImage>,
+    pub created_at: NaiveDateTime,
+}
+
+/// How bids are placed and how the winner is found; stored as the `auction_type` Postgres enum.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "auction_type", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum AuctionType {
+    /// Open ascending bids; the highest bid when the auction closes wins.
+    #[default]
+    English,
+    /// One hidden bid per bidder, revealed at settlement; the highest wins at its own price.
+    Sealed,
 }
 
 #[derive(Deserialize, Serialize)]
@@ -48,6 +650,93 @@ pub struct CreateAuction {
     pub starting_price: f64,
     #[serde(deserialize_with = "deserialize_naive_datetime")] 
     pub end_time: NaiveDateTime,
+    /// Bidders need at least this average rating as a buyer.
+    #[serde(default)]
+    pub min_buyer_rating: Option<f64>,
+    /// When bidding opens; a future time schedules the auction, otherwise it opens immediately.
+    #[serde(default, deserialize_with = "deserialize_optional_naive_datetime")]
+    pub start_time: Option<NaiveDateTime>,
+    /// Keep the auction private to the seller until it is published.
+    #[serde(default)]
+    pub draft: bool,
+    #[serde(default)]
+    pub auction_type: AuctionType,
+}
+
+fn check_auction_terms(
+    validator: &mut Validator,
+    starting_price: f64,
+    start_time: Option<NaiveDateTime>,
+    end_time: NaiveDateTime,
+    min_buyer_rating: Option<f64>,
+) {
+    validator
+        .check(starting_price > 0.0, "starting_price", "must be greater than 0")
+        .check(end_time > Utc::now().naive_utc(), "end_time", "must be in the future")
+        .check(start_time.is_none_or(|start_time| start_time < end_time), "start_time", "must be before end_time")
+        .check(
+            min_buyer_rating.is_none_or(|rating| (1.0..=5.0).contains(&rating)),
+            "min_buyer_rating",
+            "must be between 1 and 5",
+        );
+}
+
+impl CreateAuction {
+    pub fn validate(&self) -> Result<(), ApiError> {
+        let mut validator = Validator::default();
+        check_auction_terms(
+            &mut validator,
+            self.starting_price,
+            self.start_time,
+            self.end_time,
+            self.min_buyer_rating,
+        );
+        validator.finish()
+    }
+}
+
+/// Changes to a draft auction; absent fields are left unchanged.
+#[derive(Deserialize, Serialize, Default)]
+pub struct UpdateAuction {
+    pub starting_price: Option<f64>,
+    #[serde(default, deserialize_with = "deserialize_optional_naive_datetime")]
+    pub start_time: Option<NaiveDateTime>,
+    #[serde(default, deserialize_with = "deserialize_optional_naive_datetime")]
+    pub end_time: Option<NaiveDateTime>,
+    pub min_buyer_rating: Option<f64>,
+}
+
+impl UpdateAuction {
+    /// Validate the draft as it will be after this update.
+    pub fn validate_merged(
+        &self,
+        starting_price: f64,
+        start_time: Option<NaiveDateTime>,
+        end_time: NaiveDateTime,
+        min_buyer_rating: Option<f64>,
+    ) -> Result<(), ApiError> {
+        let mut validator = Validator::default();
+        check_auction_terms(
+            &mut validator,
+            self.starting_price.unwrap_or(starting_price),
+            self.start_time.or(start_time),
+            self.end_time.unwrap_or(end_time),
+            self.min_buyer_rating.or(min_buyer_rating),
+        );
+        validator.finish()
+    }
+}
+
+/// Length of a relisted auction when the ended auction's own duration is unknown.
+pub const DEFAULT_RELIST_DAYS: i64 = 7;
+
+/// Body of `POST /auctions/{id}/relist`; absent fields are copied from the ended auction.
+#[derive(Deserialize, Serialize, Default)]
+pub struct RelistAuction {
+    pub starting_price: Option<f64>,
+    /// Defaults to the ended auction's duration counted from now, or [`DEFAULT_RELIST_DAYS`].
+    #[serde(default, deserialize_with = "deserialize_optional_naive_datetime")]
+    pub end_time: Option<NaiveDateTime>,
 }
 
 #[derive(sqlx::FromRow)]
@@ -62,3 +751,590 @@ pub struct PlaceBid {
     pub auction_id: i32,
     pub bid_amount: f64,
 }
+
+impl PlaceBid {
+    pub fn validate(&self) -> Result<(), ApiError> {
+        Validator::default()
+            .check(self.bid_amount > 0.0, "bid_amount", "must be greater than 0")
+            .finish()
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct AuctionSummary {
+    pub id: i32,
+    pub vehicle_id: i32,
+    pub starting_price: BigDecimal,
+    pub start_time: Option<NaiveDateTime>,
+    pub end_time: NaiveDateTime,
+    pub status: AuctionStatus,
+    /// Derived from `status`: bidding is over for good.
+    pub closed: bool,
+    pub auction_type: AuctionType,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct BidReceipt {
+    pub id: i32,
+    pub auction_id: i32,
+    pub bidder_username: String,
+    pub bid_amount: BigDecimal,
+    pub created_at: Option<NaiveDateTime>,
+}
+
+/// Outcome of closing an auction: sold to the highest bidder, or ended unsold without a winner.
+#[derive(Serialize, Deserialize)]
+pub struct AuctionSettlement {
+    pub auction_id: i32,
+    pub vehicle_id: i32,
+    pub status: AuctionStatus,
+    pub winner_username: Option<String>,
+    pub winning_bid: Option<BigDecimal>,
+}
+
+/// Full auction view for `GET /auctions/{id}`.
+#[derive(Serialize, Deserialize)]
+pub struct AuctionDetail {
+    pub id: i32,
+    pub vehicle_id: i32,
+    pub vehicle_name: String,
+    pub seller_username: Option<String>,
+    pub starting_price: BigDecimal,
+    /// Absent while a draft has no start time yet.
+    pub start_time: Option<NaiveDateTime>,
+    pub end_time: NaiveDateTime,
+    pub status: AuctionStatus,
+    pub closed: bool,
+    pub auction_type: AuctionType,
+    /// Sealed-bid auctions show no bids until they are settled.
+    pub highest_bid: Option<BigDecimal>,
+    pub bid_count: i64,
+    pub watcher_count: i64,
+    pub min_buyer_rating: Option<BigDecimal>,
+    pub seller_reputation: Option<Reputation>,
+    /// The unsold auction this one was relisted from.
+    pub relisted_from: Option<i32>,
+    /// Every published listing in this auction's relist chain, this one included, oldest first.
+    pub relist_chain: Vec<RelistedAuction>,
+}
+
+/// One listing in a relist chain.
+#[derive(Serialize, Deserialize)]
+pub struct RelistedAuction {
+    pub id: i32,
+    pub starting_price: BigDecimal,
+    pub start_time: Option<NaiveDateTime>,
+    pub end_time: NaiveDateTime,
+    pub status: AuctionStatus,
+    pub highest_bid: Option<BigDecimal>,
+    pub bid_count: i64,
+}
+
+/// Aggregated ratings a user has received.
+#[derive(Serialize, Deserialize)]
+pub struct Reputation {
+    pub score: Option<f64>,
+    pub rating_count: i64,
+    pub seller_score: Option<f64>,
+    pub buyer_score: Option<f64>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct RateAuction {
+    pub score: i16,
+    pub comment: Option<String>,
+}
+
+impl RateAuction {
+    pub fn validate(&self) -> Result<(), ApiError> {
+        Validator::default()
+            .check((1..=5).contains(&self.score), "score", "must be between 1 and 5")
+            .check(
+                self.comment.as_deref().map_or(0, str::len) <= 1000,
+                "comment",
+                "must be at most 1000 characters",
+            )
+            .finish()
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Rating {
+    pub id: i32,
+    pub auction_id: i32,
+    pub rater_username: String,
+    pub ratee_username: String,
+    pub ratee_role: String,
+    pub score: i16,
+    pub comment: Option<String>,
+    pub created_at: Option<NaiveDateTime>,
+}
+
+pub const ALERT_FREQUENCIES: &[&str] = &["immediate", "daily"];
+/// Saved searches a single user may keep.
+pub const MAX_SAVED_SEARCHES: i64 = 50;
+
+/// What a saved search looks for in newly created auctions. Prices apply to the auction.
+#[derive(Serialize, Deserialize, Default, Clone)]
+pub struct SearchCriteria {
+    /// Full-text query, same syntax as `GET /vehicles/search`.
+    pub q: Option<String>,
+    pub make: Option<String>,
+    pub model: Option<String>,
+    pub year_min: Option<i16>,
+    pub year_max: Option<i16>,
+    pub price_min: Option<f64>,
+    pub price_max: Option<f64>,
+    pub body_style: Option<String>,
+    pub fuel_type: Option<String>,
+    pub transmission: Option<String>,
+}
+
+impl SearchCriteria {
+    fn check(&self, validator: &mut Validator) {
+        let one_of = |value: &Option<String>, allowed: &[&str]| value.as_deref().is_none_or(|v| allowed.contains(&v));
+        let is_empty = self.q.as_deref().is_none_or(|q| q.trim().is_empty())
+            && self.make.is_none()
+            && self.model.is_none()
+            && self.year_min.is_none()
+            && self.year_max.is_none()
+            && self.price_min.is_none()
+            && self.price_max.is_none()
+            && self.body_style.is_none()
+            && self.fuel_type.is_none()
+            && self.transmission.is_none();
+
+        validator
+            .check(!is_empty, "criteria", "must contain at least one criterion")
+            .check(self.q.as_deref().map_or(0, str::len) <= 200, "criteria.q", "must be at most 200 characters")
+            .check(self.make.as_deref().map_or(0, str::len) <= 100, "criteria.make", "must be at most 100 characters")
+            .check(self.model.as_deref().map_or(0, str::len) <= 100, "criteria.model", "must be at most 100 characters")
+            .check(
+                self.year_min.zip(self.year_max).is_none_or(|(min, max)| min <= max),
+                "criteria.year_max",
+                "must not be below year_min",
+            )
+            .check(self.price_min.is_none_or(|price| price >= 0.0), "criteria.price_min", "must not be negative")
+            .check(
+                self.price_min.zip(self.price_max).is_none_or(|(min, max)| min <= max),
+                "criteria.price_max",
+                "must not be below price_min",
+            )
+            .check(one_of(&self.body_style, BODY_STYLES), "criteria.body_style", "is not a supported body style")
+            .check(one_of(&self.fuel_type, FUEL_TYPES), "criteria.fuel_type", "is not a supported fuel type")
+            .check(one_of(&self.transmission, TRANSMISSIONS), "criteria.transmission", "is not a supported transmission");
+    }
+}
+
+fn default_frequency() -> String {
+    "immediate".to_string()
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct CreateSavedSearch {
+    pub name: String,
+    /// `immediate` or `daily` digest.
+    #[serde(default = "default_frequency")]
+    pub frequency: String,
+    pub criteria: SearchCriteria,
+}
+
+impl CreateSavedSearch {
+    pub fn validate(&self) -> Result<(), ApiError> {
+        let mut validator = Validator::default();
+        validator
+            .check(
+                !self.name.trim().is_empty() && self.name.len() <= 100,
+                "name",
+                "must be between 1 and 100 characters",
+            )
+            .check(ALERT_FREQUENCIES.contains(&self.frequency.as_str()), "frequency", "must be immediate or daily");
+        self.criteria.check(&mut validator);
+        validator.finish()
+    }
+}
+
+/// Partial update; `criteria`, when given, replaces all criteria.
+#[derive(Serialize, Deserialize, Default)]
+pub struct UpdateSavedSearch {
+    pub name: Option<String>,
+    pub frequency: Option<String>,
+    pub criteria: Option<SearchCriteria>,
+}
+
+impl UpdateSavedSearch {
+    pub fn validate(&self) -> Result<(), ApiError> {
+        let mut validator = Validator::default();
+        validator
+            .check(
+                self.name.as_deref().is_none_or(|name| !name.trim().is_empty() && name.len() <= 100),
+                "name",
+                "must be between 1 and 100 characters",
+            )
+            .check(
+                self.frequency.as_deref().is_none_or(|f| ALERT_FREQUENCIES.contains(&f)),
+                "frequency",
+                "must be immediate or daily",
+            );
+        if let Some(criteria) = &self.criteria {
+            criteria.check(&mut validator);
+        }
+        validator.finish()
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct SavedSearch {
+    pub id: i32,
+    pub name: String,
+    pub frequency: String,
+    pub criteria: SearchCriteria,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Notification {
+    pub id: i32,
+    pub kind: String,
+    pub message: String,
+    pub payload: serde_json::Value,
+    pub created_at: NaiveDateTime,
+    pub read_at: Option<NaiveDateTime>,
+}
+
+/// Query string for `GET /users/me/notifications`.
+#[derive(Serialize, Deserialize, Default)]
+pub struct NotificationFilter {
+    #[serde(default)]
+    pub unread_only: bool,
+}
+
+/// Entry of `GET /users/me/watchlist`.
+#[derive(Serialize, Deserialize)]
+pub struct WatchedAuction {
+    pub auction_id: i32,
+    pub vehicle_id: i32,
+    pub vehicle_name: String,
+    pub starting_price: BigDecimal,
+    pub end_time: NaiveDateTime,
+    pub closed: bool,
+    pub highest_bid: Option<BigDecimal>,
+    pub bid_count: i64,
+    pub watcher_count: i64,
+    pub watched_at: NaiveDateTime,
+}
+
+/// Document types accepted as evidence for a pre-platform provenance entry.
+pub const PROVENANCE_DOCUMENT_TYPES: &[&str] = &["title", "registration", "invoice"];
+
+/// One link in the chain of custody of a vehicle.
+///
+/// `kind` is `registration` (listed on the platform), `sale` (settled auction) or `provenance`
+/// (an owner from before the vehicle was listed, backed by `evidence_document_id`).
+#[derive(Serialize, Deserialize)]
+pub struct OwnershipEntry {
+    pub id: i32,
+    pub kind: String,
+    pub from_owner: Option<String>,
+    pub to_owner: Option<String>,
+    pub auction_id: Option<i32>,
+    pub price: Option<BigDecimal>,
+    pub transferred_at: NaiveDateTime,
+    pub notes: Option<String>,
+    pub evidence_document_id: Option<i32>,
+    pub recorded_by: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+pub struct CreateProvenanceEntry {
+    pub from_name: Option<String>,
+    pub to_name: String,
+    pub transferred_on: NaiveDate,
+    pub price: Option<f64>,
+    pub notes: Option<String>,
+    /// Title, registration or invoice uploaded to this vehicle that proves the transfer.
+    pub evidence_document_id: i32,
+}
+
+impl CreateProvenanceEntry {
+    pub fn validate(&self) -> Result<(), ApiError> {
+        Validator::default()
+            .check(
+                !self.to_name.trim().is_empty() && self.to_name.len() <= 100,
+                "to_name",
+                "must be between 1 and 100 characters",
+            )
+            .check(
+                self.from_name.as_deref().is_none_or(|name| name.len() <= 100),
+                "from_name",
+                "must be at most 100 characters",
+            )
+            .check(
+                self.transferred_on <= Utc::now().date_naive(),
+                "transferred_on",
+                "must not be in the future",
+            )
+            .check(self.price.is_none_or(|price| price >= 0.0), "price", "must not be negative")
+            .check(
+                self.notes.as_deref().is_none_or(|notes| notes.len() <= 2000),
+                "notes",
+                "must be at most 2000 characters",
+            )
+            .finish()
+    }
+}
+
+/// Largest upload accepted by `POST /vehicles/import`, in bytes.
+pub const MAX_IMPORT_BYTES: usize = 5 * 1024 * 1024;
+/// Most rows accepted by a single import.
+pub const MAX_IMPORT_ROWS: usize = 1000;
+
+/// Query string for `POST /vehicles/import`.
+#[derive(Serialize, Deserialize, Default)]
+pub struct ImportOptions {
+    /// Validate every row without saving anything.
+    #[serde(default)]
+    pub dry_run: bool,
+    /// `csv` or `jsonl`; taken from the Content-Type header when absent.
+    pub format: Option<String>,
+}
+
+/// One CSV row of an import; the columns match `CreateVehicle` and the export.
+#[derive(Deserialize)]
+pub struct VehicleImportRow {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    pub starting_price: f64,
+    pub make: Option<String>,
+    pub model: Option<String>,
+    pub year: Option<i16>,
+    pub vin: Option<String>,
+    pub mileage: Option<i32>,
+    pub mileage_unit: Option<String>,
+    pub fuel_type: Option<String>,
+    pub transmission: Option<String>,
+    pub body_style: Option<String>,
+    pub colour: Option<String>,
+    pub engine: Option<String>,
+    pub matching_numbers: Option<bool>,
+    pub registration_country: Option<String>,
+    pub condition_grade: Option<i16>,
+    pub category_id: Option<i32>,
+}
+
+impl From<VehicleImportRow> for CreateVehicle {
+    fn from(row: VehicleImportRow) -> Self {
+        CreateVehicle {
+            name: row.name,
+            description: row.description,
+            starting_price: row.starting_price,
+            category_id: row.category_id,
+            spec: VehicleSpec {
+                make: row.make,
+                model: row.model,
+                year: row.year,
+                vin: row.vin,
+                mileage: row.mileage,
+                mileage_unit: row.mileage_unit,
+                fuel_type: row.fuel_type,
+                transmission: row.transmission,
+                body_style: row.body_style,
+                colour: row.colour,
+                engine: row.engine,
+                matching_numbers: row.matching_numbers,
+                registration_country: row.registration_country,
+                condition_grade: row.condition_grade,
+            },
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ImportReport {
+    pub dry_run: bool,
+    pub row_count: usize,
+    /// Created vehicles; empty on a dry run.
+    pub vehicles: Vec<Vehicle>,
+}
+
+/// A node of the category tree.
+#[derive(Serialize, Deserialize, sqlx::FromRow)]
+pub struct Category {
+    pub id: i32,
+    pub parent_id: Option<i32>,
+    pub name: String,
+    pub slug: String,
+    pub position: i32,
+}
+
+/// A category with its subcategories, as returned by `GET /categories`.
+#[derive(Serialize, Deserialize)]
+pub struct CategoryNode {
+    #[serde(flatten)]
+    pub category: Category,
+    /// Listed vehicles in this category or any of its subcategories.
+    pub vehicle_count: i64,
+    pub children: Vec<CategoryNode>,
+}
+
+fn check_category(validator: &mut Validator, name: Option<&str>, slug: Option<&str>) {
+    validator
+        .check(
+            name.is_none_or(|name| !name.trim().is_empty() && name.len() <= 100),
+            "name",
+            "must be between 1 and 100 characters",
+        )
+        .check(
+            slug.is_none_or(|slug| {
+                !slug.is_empty()
+                    && slug.len() <= 100
+                    && slug.bytes().all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'-')
+            }),
+            "slug",
+            "must be 1 to 100 lowercase letters, digits or dashes",
+        );
+}
+
+/// URL-safe identifier derived from a category name, e.g. `Sports cars` becomes `sports-cars`.
+pub fn slugify(name: &str) -> String {
+    name.to_lowercase()
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+#[derive(Deserialize, Serialize, Default)]
+pub struct CreateCategory {
+    pub name: String,
+    /// Derived from the name when absent.
+    pub slug: Option<String>,
+    /// Absent for a top-level category.
+    pub parent_id: Option<i32>,
+    #[serde(default)]
+    pub position: i32,
+}
+
+impl CreateCategory {
+    pub fn validate(&self) -> Result<(), ApiError> {
+        let mut validator = Validator::default();
+        let slug = self.slug.clone().unwrap_or_else(|| slugify(&self.name));
+        check_category(&mut validator, Some(&self.name), Some(&slug));
+        validator.finish()
+    }
+}
+
+/// Partial category update; `parent_id: null` moves the category to the top level.
+#[derive(Deserialize, Serialize, Default)]
+pub struct UpdateCategory {
+    pub name: Option<String>,
+    pub slug: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_present", skip_serializing_if = "Option::is_none")]
+    pub parent_id: Option<Option<i32>>,
+    pub position: Option<i32>,
+}
+
+impl UpdateCategory {
+    pub fn validate(&self) -> Result<(), ApiError> {
+        let mut validator = Validator::default();
+        check_category(&mut validator, self.name.as_deref(), self.slug.as_deref());
+        validator.finish()
+    }
+}
+
+/// Sellers cannot withdraw an auction this close to its end.
+pub const CANCELLATION_CUTOFF_MINUTES: i64 = 60;
+/// Fee for withdrawing an auction with bids without an administrator's approval, in percent of the highest bid.
+pub const CANCELLATION_FEE_PERCENT: i64 = 10;
+
+/// Body of `POST /auctions/{id}/cancel`.
+#[derive(Deserialize, Serialize, Default)]
+pub struct CancelAuction {
+    /// Shown to bidders and kept with the auction.
+    pub reason: String,
+    /// Once there are bids, pay the cancellation fee instead of waiting for approval.
+    #[serde(default)]
+    pub pay_fee: bool,
+}
+
+impl CancelAuction {
+    pub fn validate(&self) -> Result<(), ApiError> {
+        Validator::default()
+            .check(!self.reason.trim().is_empty(), "reason", "must not be empty")
+            .check(self.reason.len() <= 1000, "reason", "must be at most 1000 characters")
+            .finish()
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct AuctionCancellation {
+    pub id: i32,
+    pub auction_id: i32,
+    pub requested_by: String,
+    pub reason: String,
+    pub fee: Option<BigDecimal>,
+    /// `pending` until an administrator decides, then `approved` or `rejected`; `completed` when no approval was needed.
+    /// `expired` when the auction closed before anyone decided.
+    pub status: String,
+    pub created_at: NaiveDateTime,
+    pub decided_at: Option<NaiveDateTime>,
+}
+
+/// Body of `POST /auctions/bids/{id}/retract`.
+#[derive(Deserialize, Serialize, Default)]
+pub struct RetractBid {
+    /// Kept with the retraction for the seller and administrators.
+    pub reason: String,
+}
+
+impl RetractBid {
+    pub fn validate(&self) -> Result<(), ApiError> {
+        Validator::default()
+            .check(!self.reason.trim().is_empty(), "reason", "must not be empty")
+            .check(self.reason.len() <= 1000, "reason", "must be at most 1000 characters")
+            .finish()
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct BidRetraction {
+    pub bid_id: i32,
+    pub auction_id: i32,
+    pub bid_amount: BigDecimal,
+    pub reason: String,
+    pub retracted_at: NaiveDateTime,
+    /// The auction's highest bid once this one is withdrawn.
+    pub highest_bid: Option<BigDecimal>,
+}
+
+/// A withdrawn bid as kept in `bid_retractions`.
+#[derive(Serialize, Deserialize)]
+pub struct RetractedBid {
+    pub bid_id: i32,
+    pub auction_id: i32,
+    pub bid_amount: BigDecimal,
+    pub reason: String,
+    pub placed_at: Option<NaiveDateTime>,
+    pub retracted_at: NaiveDateTime,
+}
+
+/// A bidder's own bid on a sealed-bid auction, decrypted.
+#[derive(Serialize, Deserialize)]
+pub struct SealedBid {
+    pub auction_id: i32,
+    pub bid_amount: BigDecimal,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+/// A bid as the seller of the auction sees it.
+#[derive(Serialize, Deserialize)]
+pub struct AuctionBid {
+    pub id: i32,
+    pub bidder_username: String,
+    pub bid_amount: BigDecimal,
+    pub created_at: Option<NaiveDateTime>,
+    /// Bids this bidder has withdrawn on any auction.
+    pub bidder_retraction_count: i64,
+}
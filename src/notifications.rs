@@ -0,0 +1,174 @@
+use chrono::Utc;
+use sqlx::{PgConnection, PgPool};
+use crate::errors::ApiError;
+
+/// Record the saved searches a newly created auction matches and notify the `immediate` ones.
+///
+/// Runs inside the transaction that creates the auction so a failed insert leaves no alerts behind.
+pub async fn match_saved_searches(conn: &mut PgConnection, auction_id: i32) -> Result<(), ApiError> {
+    sqlx::query!(
+        "INSERT INTO saved_search_matches (saved_search_id, auction_id)
+         SELECT s.id, a.id
+         FROM auctions a
+         INNER JOIN vehicles v ON v.id = a.vehicle_id
+         INNER JOIN saved_searches s ON s.user_id <> a.seller_id
+         INNER JOIN users u ON u.id = s.user_id AND u.deleted_at IS NULL
+         WHERE a.id = $1
+           AND (s.q IS NULL OR v.search_vector @@ websearch_to_tsquery('english', s.q))
+           AND (s.make IS NULL OR lower(v.make) = lower(s.make))
+           AND (s.model IS NULL OR lower(v.model) = lower(s.model))
+           AND (s.year_min IS NULL OR v.year >= s.year_min)
+           AND (s.year_max IS NULL OR v.year <= s.year_max)
+           AND (s.price_min IS NULL OR a.starting_price >= s.price_min)
+           AND (s.price_max IS NULL OR a.starting_price <= s.price_max)
+           AND (s.body_style IS NULL OR v.body_style = s.body_style)
+           AND (s.fuel_type IS NULL OR v.fuel_type = s.fuel_type)
+           AND (s.transmission IS NULL OR v.transmission = s.transmission)
+         ON CONFLICT DO NOTHING",
+        auction_id
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    sqlx::query!(
+        r#"WITH due AS (
+               UPDATE saved_search_matches m SET notified_at = NOW()
+               FROM saved_searches s
+               WHERE s.id = m.saved_search_id AND m.auction_id = $1
+                 AND m.notified_at IS NULL AND s.frequency = 'immediate'
+               RETURNING s.id, s.user_id, s.name
+           )
+           INSERT INTO notifications (user_id, kind, message, payload)
+           SELECT due.user_id, 'saved_search_match',
+                  'New listing matching "' || due.name || '": ' || v.name,
+                  jsonb_build_object('saved_search_id', due.id, 'auction_id', a.id, 'vehicle_id', v.id)
+           FROM due
+           CROSS JOIN auctions a
+           INNER JOIN vehicles v ON v.id = a.vehicle_id
+           WHERE a.id = $1"#,
+        auction_id
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
+
+/// Tell everyone who bid on an auction that the seller withdrew it, and why.
+pub async fn notify_bidders_of_cancellation(
+    conn: &mut PgConnection,
+    auction_id: i32,
+    reason: &str,
+) -> Result<(), ApiError> {
+    sqlx::query!(
+        "INSERT INTO notifications (user_id, kind, message, payload)
+         SELECT DISTINCT b.bidder_id, 'auction_cancelled', v.name || ' was withdrawn by the seller: ' || $2,
+                jsonb_build_object('auction_id', a.id, 'vehicle_id', v.id, 'reason', $2::TEXT)
+         FROM (SELECT auction_id, bidder_id FROM bids UNION SELECT auction_id, bidder_id FROM sealed_bids) b
+         INNER JOIN auctions a ON a.id = b.auction_id
+         INNER JOIN vehicles v ON v.id = a.vehicle_id
+         INNER JOIN users u ON u.id = b.bidder_id AND u.deleted_at IS NULL
+         WHERE b.auction_id = $1",
+        auction_id,
+        reason
+    )
+    .execute(conn)
+    .await?;
+
+    Ok(())
+}
+
+/// Send one digest per `daily` saved search with pending matches, at most once a day.
+///
+/// Returns the number of digest notifications created.
+pub async fn send_daily_digests(pool: &PgPool) -> Result<u64, ApiError> {
+    let result = sqlx::query!(
+        r#"WITH due AS (
+               UPDATE saved_searches s SET last_digest_at = NOW()
+               WHERE s.frequency = 'daily'
+                 AND (s.last_digest_at IS NULL OR s.last_digest_at <= NOW() - INTERVAL '1 day')
+                 AND EXISTS (
+                     SELECT 1 FROM saved_search_matches m WHERE m.saved_search_id = s.id AND m.notified_at IS NULL
+                 )
+               RETURNING s.id, s.user_id, s.name
+           ), sent AS (
+               UPDATE saved_search_matches m SET notified_at = NOW()
+               FROM due
+               WHERE m.saved_search_id = due.id AND m.notified_at IS NULL
+               RETURNING m.saved_search_id, m.auction_id
+           )
+           INSERT INTO notifications (user_id, kind, message, payload)
+           SELECT due.user_id, 'saved_search_digest',
+                  COUNT(*) || ' new listing(s) matching "' || due.name || '"',
+                  jsonb_build_object('saved_search_id', due.id, 'auction_ids', jsonb_agg(sent.auction_id ORDER BY sent.auction_id))
+           FROM due INNER JOIN sent ON sent.saved_search_id = due.id
+           GROUP BY due.id, due.user_id, due.name"#
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+/// Minutes before `end_time` at which watchers are reminded, unless `WATCH_REMINDER_OFFSETS` overrides them.
+pub const DEFAULT_REMINDER_OFFSETS: &[i32] = &[24 * 60, 60];
+
+/// Reminder offsets in minutes from the comma separated `WATCH_REMINDER_OFFSETS`, e.g. `1440,60,10`.
+pub fn reminder_offsets() -> Vec<i32> {
+    let configured: Vec<i32> = std::env::var("WATCH_REMINDER_OFFSETS")
+        .unwrap_or_default()
+        .split(',')
+        .filter_map(|value| value.trim().parse().ok())
+        .filter(|minutes| *minutes > 0)
+        .collect();
+
+    if configured.is_empty() {
+        DEFAULT_REMINDER_OFFSETS.to_vec()
+    } else {
+        configured
+    }
+}
+
+/// Remind watchers of open auctions that entered one of the `offsets` windows.
+///
+/// Only the tightest due window is notified, so someone who starts watching an hour before
+/// the end gets one reminder rather than one per offset. Returns the number of reminders sent.
+pub async fn send_ending_soon_reminders(pool: &PgPool, offsets: &[i32]) -> Result<u64, ApiError> {
+    // End times are naive UTC, so the session time zone must not take part in the comparison
+    let result = sqlx::query!(
+        r#"WITH due AS (
+               SELECT w.user_id, w.auction_id, MIN(o.minutes) as offset_minutes
+               FROM auction_watchers w
+               INNER JOIN auctions a ON a.id = w.auction_id
+               INNER JOIN users u ON u.id = w.user_id AND u.deleted_at IS NULL
+               CROSS JOIN UNNEST($1::INT[]) as o(minutes)
+               WHERE a.closed = FALSE
+                 AND a.end_time > $2
+                 AND a.end_time <= $2 + o.minutes * INTERVAL '1 minute'
+                 AND NOT EXISTS (
+                     SELECT 1 FROM watch_reminders r
+                     WHERE r.user_id = w.user_id AND r.auction_id = w.auction_id AND r.offset_minutes <= o.minutes
+                 )
+               GROUP BY w.user_id, w.auction_id
+           ), sent AS (
+               INSERT INTO watch_reminders (user_id, auction_id, offset_minutes)
+               SELECT user_id, auction_id, offset_minutes FROM due
+               ON CONFLICT DO NOTHING
+               RETURNING user_id, auction_id, offset_minutes
+           )
+           INSERT INTO notifications (user_id, kind, message, payload)
+           SELECT sent.user_id, 'auction_ending_soon',
+                  v.name || ' ends at ' || to_char(a.end_time, 'YYYY-MM-DD HH24:MI') || ' UTC',
+                  jsonb_build_object('auction_id', a.id, 'vehicle_id', v.id, 'end_time', a.end_time,
+                                     'offset_minutes', sent.offset_minutes)
+           FROM sent
+           INNER JOIN auctions a ON a.id = sent.auction_id
+           INNER JOIN vehicles v ON v.id = a.vehicle_id"#,
+        offsets,
+        Utc::now().naive_utc()
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected())
+}
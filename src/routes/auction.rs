@@ -1,169 +1,289 @@
 use sqlx::PgPool;
-use actix_web::{web, Responder, HttpResponse, HttpRequest};
-use crate::models::{CreateAuction, PlaceBid};
-use redis::AsyncCommands;
+use actix_web::{web, HttpResponse, HttpRequest};
+use crate::auction_state::{record_creation, transition, AuctionStatus};
+use crate::errors::{ApiError, ErrorCode};
+use crate::models::{
+    CreateAuction, PlaceBid, AuctionSummary, BidReceipt, AuctionSettlement, AuctionDetail, UpdateAuction,
+    RelistAuction, RelistedAuction, AuctionType, DEFAULT_RELIST_DAYS,
+};
+use crate::notifications::match_saved_searches;
+use crate::routes::history::record_sale;
+use crate::routes::rating::fetch_reputation;
+use crate::sealed::{ensure_configured, open_amount, seal_amount};
+use crate::session::require_user;
 use bigdecimal::BigDecimal;
 use std::str::FromStr;
-use chrono::Utc;
+use chrono::{Duration, NaiveDateTime, Utc};
+use sqlx::PgConnection;
 
-pub async fn create_auction(
-    pool: web::Data<PgPool>,
-    redis_client: web::Data<redis::Client>,
-    req: HttpRequest,
-    form: web::Json<CreateAuction>,
-) -> impl Responder {
-    // Extract the Session-Code from headers
-    let session_code = match req.headers().get("Session-Code") {
-        Some(code) => code.to_str().unwrap_or_default(),
-        None => return HttpResponse::Unauthorized().body("Missing Session-Code header"),
-    };
-
-    // Connect to Redis
-    let mut redis_conn = match redis_client.get_multiplexed_async_connection().await {
-        Ok(conn) => conn,
-        Err(_) => return HttpResponse::InternalServerError().body("Failed to connect to Redis"),
-    };
-
-    // Retrieve the user_name associated with the session_code from Redis
-    let user_name: Option<String> = match redis_conn
-        .get(format!("session:{}", session_code))
-        .await
-    {
-        Ok(username) => username,
-        Err(_) => return HttpResponse::InternalServerError().body("Failed to retrieve user_name"),
-    };
+/// Status and start time of an auction being published: scheduled for a future start, otherwise live now.
+fn publication(start_time: Option<NaiveDateTime>) -> (AuctionStatus, NaiveDateTime) {
+    let now = Utc::now().naive_utc();
+    match start_time {
+        Some(start_time) if start_time > now => (AuctionStatus::Scheduled, start_time),
+        _ => (AuctionStatus::Live, now),
+    }
+}
 
-    // Check if the username exists
-    let username_exists: (bool,) = match sqlx::query_as::<_, (bool,)>(
-        "SELECT EXISTS(SELECT 1 FROM users WHERE username = $1)"
+async fn fetch_summary(conn: &mut PgConnection, auction_id: i32) -> Result<AuctionSummary, ApiError> {
+    let auction = sqlx::query_as!(
+        AuctionSummary,
+        r#"SELECT id, vehicle_id, starting_price, start_time, end_time, status as "status: AuctionStatus", closed,
+                  auction_type as "auction_type: AuctionType"
+           FROM auctions WHERE id = $1"#,
+        auction_id
     )
-    .bind(&user_name)
-    .fetch_one(pool.as_ref())
-    .await
-    {
-        Ok(result) => result,
-        Err(_) => return HttpResponse::InternalServerError().body("Database query error"),
-    };
+    .fetch_one(conn)
+    .await?;
 
-    if !username_exists.0 {
-        // Username not exists
-        return HttpResponse::BadRequest().body("Username not exists");
-    }
+    Ok(auction)
+}
 
+/// Put a vehicle up for auction in the caller's transaction, optionally as the relist of an unsold auction.
+async fn list_vehicle(
+    conn: &mut PgConnection,
+    seller_id: i32,
+    form: &CreateAuction,
+    relisted_from: Option<i32>,
+) -> Result<AuctionSummary, ApiError> {
+    // Without a key no sealed bid could be stored, so do not open an auction that would take them
+    if form.auction_type == AuctionType::Sealed {
+        ensure_configured()?;
+    }
 
     // Verify that the current user is the owner of the vehicle
-    let vehicle_owner: Option<String> = match sqlx::query_scalar::<_, String>(
-        "SELECT owner_username FROM vehicles WHERE id = $1"
+    let vehicle_owner = sqlx::query_scalar!(
+        "SELECT owner_id FROM vehicles WHERE id = $1 AND deleted_at IS NULL",
+        form.vehicle_id
     )
-    .bind(form.vehicle_id)
-    .fetch_optional(pool.as_ref())
-    .await
-    {
-        Ok(owner) => owner,
-        Err(_) => return HttpResponse::InternalServerError().body("Failed to verify vehicle ownership"),
-    };
+    .fetch_optional(&mut *conn)
+    .await?
+    .ok_or_else(|| ApiError::new(ErrorCode::VehicleNotFound, "Vehicle not found"))?;
 
-    if vehicle_owner != Some(user_name).expect("no user name") {
+    if vehicle_owner != seller_id {
         // The user is not the owner of the vehicle
-        return HttpResponse::Forbidden().body("You are not the owner of this vehicle");
+        return Err(ApiError::new(ErrorCode::NotOwner, "You are not the owner of this vehicle"));
     }
 
     // Check if there is an existing auction for the same vehicle
-    let existing_auction: Option<(i32, bool)> = sqlx::query_as(
-        "SELECT id, closed FROM auctions WHERE vehicle_id = $1 AND closed = FALSE"
+    let existing_auction = sqlx::query_scalar!(
+        "SELECT id FROM auctions WHERE vehicle_id = $1 AND closed = FALSE",
+        form.vehicle_id
     )
-    .bind(form.vehicle_id)
-    .fetch_optional(pool.as_ref())
-    .await
-    .expect("Failed to query auctions");
+    .fetch_optional(&mut *conn)
+    .await?;
 
-    if let Some(_) = existing_auction {
+    if existing_auction.is_some() {
         // Auction already exists and is not closed
-        return HttpResponse::BadRequest().body("An auction is already open for this vehicle.");
+        return Err(ApiError::new(ErrorCode::AuctionAlreadyOpen, "An auction is already open for this vehicle."));
     }
 
-    // Proceed to create the auction
-    if let Err(_) = sqlx::query(
-        "INSERT INTO auctions (vehicle_id, starting_price, end_time) VALUES ($1, $2, $3)"
+    let starting_price = BigDecimal::from_str(&form.starting_price.to_string())
+        .map_err(|_| ApiError::new(ErrorCode::InvalidPayload, "Failed to parse starting price"))?;
+    let min_buyer_rating = form
+        .min_buyer_rating
+        .map(|rating| BigDecimal::from_str(&rating.to_string()))
+        .transpose()
+        .map_err(|_| ApiError::new(ErrorCode::InvalidPayload, "Failed to parse minimum buyer rating"))?;
+
+    // Every auction starts as a draft; unless the seller keeps it that way it is published right away,
+    // alerting matching saved searches in the same transaction
+    let auction_id = sqlx::query_scalar!(
+        "INSERT INTO auctions (vehicle_id, starting_price, end_time, seller_id, min_buyer_rating, status, start_time,
+                               relisted_from, auction_type)
+         VALUES ($1, $2, $3, $4, $5, 'draft', $6, $7, $8)
+         RETURNING id",
+        form.vehicle_id,
+        starting_price,
+        form.end_time,
+        seller_id,
+        min_buyer_rating,
+        form.start_time,
+        relisted_from,
+        form.auction_type as AuctionType
     )
-    .bind(form.vehicle_id)
-    .bind(form.starting_price)
-    .bind(form.end_time)
-    .execute(pool.as_ref())
-    .await
-    {
-        return HttpResponse::InternalServerError().body("Failed to create auction");
+    .fetch_one(&mut *conn)
+    .await?;
+    record_creation(&mut *conn, auction_id, AuctionStatus::Draft, seller_id).await?;
+
+    if !form.draft {
+        let (status, start_time) = publication(form.start_time);
+        sqlx::query!("UPDATE auctions SET start_time = $2 WHERE id = $1", auction_id, start_time)
+            .execute(&mut *conn)
+            .await?;
+        transition(&mut *conn, auction_id, status, Some(seller_id), None).await?;
+        match_saved_searches(&mut *conn, auction_id).await?;
     }
 
-    HttpResponse::Ok().body("Auction Created")
+    fetch_summary(conn, auction_id).await
 }
 
-pub async fn place_bid(
+pub async fn create_auction(
     pool: web::Data<PgPool>,
     redis_client: web::Data<redis::Client>,
     req: HttpRequest,
-    form: web::Json<PlaceBid>,
-) -> impl Responder {
-    // Extract the Session-Code from headers
-    let session_code = match req.headers().get("Session-Code") {
-        Some(code) => code.to_str().unwrap_or_default(),
-        None => return HttpResponse::Unauthorized().body("Missing Session-Code header"),
-    };
+    form: web::Json<CreateAuction>,
+) -> Result<HttpResponse, ApiError> {
+    let user = require_user(&req, &pool, &redis_client).await?;
+    form.validate()?;
 
-    // Connect to Redis
-    let mut redis_conn = match redis_client.get_multiplexed_async_connection().await {
-        Ok(conn) => conn,
-        Err(_) => return HttpResponse::InternalServerError().body("Failed to connect to Redis"),
-    };
+    let mut tx = pool.begin().await?;
+    let auction = list_vehicle(&mut tx, user.id, &form, None).await?;
+    tx.commit().await?;
 
-    // Retrieve the username associated with the session_code from Redis
-    let user_name: Option<String> = match redis_conn
-        .get(format!("session:{}", session_code))
-        .await
-    {
-        Ok(username) => username,
-        Err(_) => return HttpResponse::InternalServerError().body("Failed to retrieve username"),
-    };
+    Ok(HttpResponse::Ok().json(auction))
+}
+
+/// Put an auction that ended unsold up again with the same terms, apart from any given price or end time.
+///
+/// A live auction whose end time passed without bids is ended as unsold first.
+pub async fn relist_auction(
+    pool: web::Data<PgPool>,
+    redis_client: web::Data<redis::Client>,
+    req: HttpRequest,
+    path: web::Path<i32>,
+    form: web::Json<RelistAuction>,
+) -> Result<HttpResponse, ApiError> {
+    let user = require_user(&req, &pool, &redis_client).await?;
+    let auction_id = *path;
+
+    let mut tx = pool.begin().await?;
+    let previous = sqlx::query!(
+        r#"SELECT vehicle_id, seller_id, status as "status: AuctionStatus",
+                  starting_price::FLOAT8 as "starting_price!", start_time, end_time, created_at,
+                  min_buyer_rating::FLOAT8 as min_buyer_rating, auction_type as "auction_type: AuctionType",
+                  (EXISTS(SELECT 1 FROM bids b WHERE b.auction_id = a.id)
+                   OR EXISTS(SELECT 1 FROM sealed_bids s WHERE s.auction_id = a.id)) as "has_bids!",
+                  (SELECT r.id FROM auctions r WHERE r.relisted_from = a.id AND r.status <> 'cancelled'
+                   ORDER BY r.id DESC LIMIT 1) as relisted_as
+           FROM auctions a WHERE id = $1 FOR UPDATE"#,
+        auction_id
+    )
+    .fetch_optional(&mut *tx)
+    .await?
+    .filter(|auction| auction.status != AuctionStatus::Draft || auction.seller_id == Some(user.id))
+    .ok_or_else(|| ApiError::new(ErrorCode::AuctionNotFound, "Auction not found"))?;
+
+    if previous.seller_id != Some(user.id) {
+        return Err(ApiError::new(ErrorCode::NotOwner, "You are not the seller of this auction"));
+    }
+    if let Some(relisted_as) = previous.relisted_as {
+        return Err(ApiError::new(
+            ErrorCode::AuctionNotRelistable,
+            format!("The auction has already been relisted as auction {}", relisted_as),
+        ));
+    }
 
-    let user_name = match user_name {
-        Some(name) => name,
-        None => return HttpResponse::Unauthorized().body("Invalid or expired session"),
+    let now = Utc::now().naive_utc();
+    match previous.status {
+        AuctionStatus::EndedUnsold | AuctionStatus::ReserveNotMet => {}
+        AuctionStatus::Live if previous.end_time < now && !previous.has_bids => {
+            transition(&mut tx, auction_id, AuctionStatus::EndedUnsold, None, Some("End time passed without bids"))
+                .await?;
+        }
+        _ => {
+            return Err(ApiError::new(
+                ErrorCode::AuctionNotRelistable,
+                "Only auctions that ended unsold can be relisted",
+            ))
+        }
+    }
+
+    // Run as long as the ended auction did, unless that is unknown or was not a positive length
+    let duration = previous
+        .start_time
+        .or(previous.created_at)
+        .map(|start_time| previous.end_time - start_time)
+        .filter(|duration| *duration > Duration::zero())
+        .unwrap_or_else(|| Duration::days(DEFAULT_RELIST_DAYS));
+    let terms = CreateAuction {
+        vehicle_id: previous.vehicle_id,
+        starting_price: form.starting_price.unwrap_or(previous.starting_price),
+        end_time: form.end_time.unwrap_or(now + duration),
+        min_buyer_rating: previous.min_buyer_rating,
+        start_time: None,
+        draft: false,
+        auction_type: previous.auction_type,
     };
+    terms.validate()?;
 
-    // Fetch the starting price and end time of the auction
-    let auction_details = match sqlx::query!(
-        "SELECT starting_price, end_time FROM auctions WHERE id = $1",
+    let auction = list_vehicle(&mut tx, user.id, &terms, Some(auction_id)).await?;
+    tx.commit().await?;
+
+    Ok(HttpResponse::Ok().json(auction))
+}
+
+pub async fn place_bid(
+    pool: web::Data<PgPool>,
+    redis_client: web::Data<redis::Client>,
+    req: HttpRequest,
+    form: web::Json<PlaceBid>,
+) -> Result<HttpResponse, ApiError> {
+    let user = require_user(&req, &pool, &redis_client).await?;
+    form.validate()?;
+
+    // Bids on one auction are placed one at a time
+    let mut tx = pool.begin().await?;
+    let auction_details = sqlx::query!(
+        r#"SELECT starting_price, start_time, end_time, status as "status: AuctionStatus",
+                  min_buyer_rating::FLOAT8 as min_buyer_rating, auction_type as "auction_type: AuctionType"
+           FROM auctions WHERE id = $1 AND status <> 'draft' FOR UPDATE"#,
         form.auction_id
     )
-    .fetch_optional(pool.as_ref())
-    .await
-    {
-        Ok(Some(auction)) => auction,
-        Ok(None) => return HttpResponse::NotFound().body("Auction not found"),
-        Err(_) => return HttpResponse::InternalServerError().body("Failed to fetch auction details"),
-    };
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| ApiError::new(ErrorCode::AuctionNotFound, "Auction not found"))?;
 
     let starting_price = auction_details.starting_price;
     let end_time = auction_details.end_time;
 
-    // Ensure that the current time is before the auction's end time
     let now = Utc::now().naive_utc();
+    match auction_details.status {
+        AuctionStatus::Live => {}
+        AuctionStatus::Scheduled if auction_details.start_time.is_some_and(|start_time| start_time <= now) => {
+            // The start time has come but the scheduler has not opened the auction yet
+            transition(&mut tx, form.auction_id, AuctionStatus::Live, None, Some("Start time reached")).await?;
+        }
+        AuctionStatus::Scheduled => {
+            return Err(ApiError::new(ErrorCode::AuctionNotStarted, "The auction has not started yet"));
+        }
+        _ => return Err(ApiError::new(ErrorCode::AuctionClosed, "The auction is already closed")),
+    }
+
+    // Ensure that the current time is before the auction's end time
     if now > end_time {
-        return HttpResponse::BadRequest().body("The auction has already ended");
+        return Err(ApiError::new(ErrorCode::AuctionEnded, "The auction has already ended"));
+    }
+
+    // Sellers may restrict bidding to buyers with a good track record; unrated buyers do not qualify
+    if let Some(min_buyer_rating) = auction_details.min_buyer_rating {
+        let reputation = fetch_reputation(&pool, user.id).await?;
+        if reputation.buyer_score.is_none_or(|score| score < min_buyer_rating) {
+            return Err(ApiError::new(
+                ErrorCode::BuyerRatingTooLow,
+                format!("The seller requires a buyer rating of at least {}", min_buyer_rating),
+            ));
+        }
+    }
+
+    let bid_amount = BigDecimal::from_str(&form.bid_amount.to_string())
+        .map_err(|_| ApiError::new(ErrorCode::InvalidPayload, "Failed to parse bid amount"))?;
+
+    if auction_details.auction_type == AuctionType::Sealed {
+        let receipt =
+            place_sealed_bid(&mut tx, form.auction_id, user.id, user.username, &starting_price, bid_amount).await?;
+        tx.commit().await?;
+        return Ok(HttpResponse::Ok().json(receipt));
     }
 
     // Get the current highest bid for the auction
-    let current_highest_bid: BigDecimal = match sqlx::query_scalar!(
+    let current_highest_bid: BigDecimal = sqlx::query_scalar!(
         "SELECT MAX(bid_amount) FROM bids WHERE auction_id = $1",
         form.auction_id
     )
-    .fetch_optional(pool.as_ref())
-    .await
-    {
-        Ok(Some(bid)) => bid.unwrap_or_else(|| BigDecimal::from(0)),
-        Ok(None) => BigDecimal::from(0),
-        Err(_) => return HttpResponse::InternalServerError().body("Failed to fetch current highest bid"),
-    };
+    .fetch_one(&mut *tx)
+    .await?
+    .unwrap_or_else(|| BigDecimal::from(0));
 
     // Determine the minimum valid bid
     let min_required_bid = if current_highest_bid > BigDecimal::from(0) {
@@ -172,146 +292,484 @@ pub async fn place_bid(
         starting_price.clone()
     };
 
-    let bid_amount = match BigDecimal::from_str(&form.bid_amount.to_string()) {
-        Ok(amount) => amount,
-        Err(_) => return HttpResponse::InternalServerError().body("Failed to parse bid amount"),
-    };
-
     // Validate the bid amount
     if bid_amount < starting_price {
-        return HttpResponse::BadRequest().body(format!(
-            "Your bid must be at least the starting price of {}",
-            starting_price
+        return Err(ApiError::new(
+            ErrorCode::BidTooLow,
+            format!("Your bid must be at least the starting price of {}", starting_price),
         ));
     }
 
     if bid_amount < min_required_bid {
-        return HttpResponse::BadRequest().body(format!(
-            "Your bid must be at least 500 higher than the current highest bid of {}",
-            current_highest_bid
+        return Err(ApiError::new(
+            ErrorCode::BidTooLow,
+            format!("Your bid must be at least 500 higher than the current highest bid of {}", current_highest_bid),
         ));
     }
 
-    // Place the bid
-    if let Err(_) = sqlx::query!(
-        "INSERT INTO bids (auction_id, bid_amount, bidder_username) VALUES ($1, $2, $3)",
+    // Place the bid, timed in naive UTC like the retraction window it is checked against
+    let bid = sqlx::query!(
+        "INSERT INTO bids (auction_id, bid_amount, bidder_id, created_at) VALUES ($1, $2, $3, $4)
+         RETURNING id, created_at",
         form.auction_id,
         bid_amount,
-        user_name
+        user.id,
+        Utc::now().naive_utc()
     )
-    .execute(pool.as_ref())
-    .await
-    {
-        return HttpResponse::InternalServerError().body("Failed to place bid");
-    };
-
-    HttpResponse::Ok().body("Bid Placed")
+    .fetch_one(&mut *tx)
+    .await?;
+    tx.commit().await?;
+
+    Ok(HttpResponse::Ok().json(BidReceipt {
+        id: bid.id,
+        auction_id: form.auction_id,
+        bidder_username: user.username,
+        bid_amount,
+        created_at: bid.created_at,
+    }))
 }
 
 
 
-pub async fn close_auction(pool: web::Data<PgPool>, req: HttpRequest, path: web::Path<i32>, redis_client: web::Data<redis::Client>) -> impl Responder {
-    // Extract the Session-Code from headers
-    let session_code = match req.headers().get("Session-Code") {
-        Some(code) => code.to_str().unwrap_or_default(),
-        None => return HttpResponse::Unauthorized().body("Missing Session-Code header"),
-    };
+/// Submit or raise the bidder's one hidden bid on a sealed-bid auction, stored encrypted.
+async fn place_sealed_bid(
+    conn: &mut PgConnection,
+    auction_id: i32,
+    bidder_id: i32,
+    bidder_username: String,
+    starting_price: &BigDecimal,
+    bid_amount: BigDecimal,
+) -> Result<BidReceipt, ApiError> {
+    if bid_amount < *starting_price {
+        return Err(ApiError::new(
+            ErrorCode::BidTooLow,
+            format!("Your bid must be at least the starting price of {}", starting_price),
+        ));
+    }
 
-    // Connect to Redis
-    let mut redis_conn = match redis_client.get_multiplexed_async_connection().await {
-        Ok(conn) => conn,
-        Err(_) => return HttpResponse::InternalServerError().body("Failed to connect to Redis"),
-    };
+    let existing = sqlx::query_scalar!(
+        "SELECT sealed_amount FROM sealed_bids WHERE auction_id = $1 AND bidder_id = $2",
+        auction_id,
+        bidder_id
+    )
+    .fetch_optional(&mut *conn)
+    .await?;
+    if let Some(existing) = existing {
+        let current = open_amount(&existing, auction_id, bidder_id)?;
+        if bid_amount <= current {
+            return Err(ApiError::new(
+                ErrorCode::BidTooLow,
+                format!("A sealed bid can only be raised; your current bid is {}", current),
+            ));
+        }
+    }
 
-    // Retrieve the username associated with the session_code from Redis
-    let user_name: Option<String> = match redis_conn
-        .get(format!("session:{}", session_code))
-        .await
-    {
-        Ok(username) => username,
-        Err(_) => return HttpResponse::InternalServerError().body("Failed to retrieve username"),
-    };
+    let sealed_amount = seal_amount(&bid_amount, auction_id, bidder_id)?;
+    let bid = sqlx::query!(
+        "INSERT INTO sealed_bids (auction_id, bidder_id, sealed_amount) VALUES ($1, $2, $3)
+         ON CONFLICT (auction_id, bidder_id)
+         DO UPDATE SET sealed_amount = EXCLUDED.sealed_amount, updated_at = NOW()
+         RETURNING id, updated_at",
+        auction_id,
+        bidder_id,
+        sealed_amount
+    )
+    .fetch_one(conn)
+    .await?;
 
-    let user_name = match user_name {
-        Some(name) => name,
-        None => return HttpResponse::Unauthorized().body("Invalid or expired session"),
-    };
+    Ok(BidReceipt {
+        id: bid.id,
+        auction_id,
+        bidder_username,
+        bid_amount,
+        created_at: Some(bid.updated_at),
+    })
+}
 
-    // Fetch the vehicle owner from the auctions table
-    let vehicle_owner = match sqlx::query!(
-        "SELECT v.owner_username FROM vehicles v INNER JOIN auctions a ON v.id = a.vehicle_id WHERE a.id = $1",
-        *path
+/// Decrypt every sealed bid on the auction into `bids`, dated when it was last raised.
+async fn reveal_sealed_bids(conn: &mut PgConnection, auction_id: i32) -> Result<(), ApiError> {
+    let sealed_bids = sqlx::query!(
+        "DELETE FROM sealed_bids WHERE auction_id = $1 RETURNING bidder_id, sealed_amount, updated_at",
+        auction_id
     )
-    .fetch_one(pool.as_ref())
-    .await
-    {
-        Ok(record) => record.owner_username,
-        Err(_) => return HttpResponse::InternalServerError().body("Failed to fetch vehicle owner"),
-    };
+    .fetch_all(&mut *conn)
+    .await?;
+
+    for sealed_bid in sealed_bids {
+        let bid_amount = open_amount(&sealed_bid.sealed_amount, auction_id, sealed_bid.bidder_id)?;
+        sqlx::query!(
+            "INSERT INTO bids (auction_id, bid_amount, bidder_id, created_at) VALUES ($1, $2, $3, $4)",
+            auction_id,
+            bid_amount,
+            sealed_bid.bidder_id,
+            sealed_bid.updated_at
+        )
+        .execute(&mut *conn)
+        .await?;
+    }
+
+    Ok(())
+}
+
+pub async fn close_auction(
+    pool: web::Data<PgPool>,
+    req: HttpRequest,
+    path: web::Path<i32>,
+    redis_client: web::Data<redis::Client>,
+) -> Result<HttpResponse, ApiError> {
+    let user = require_user(&req, &pool, &redis_client).await?;
+    let auction_id = *path;
+
+    let mut tx = pool.begin().await?;
+
+    // Fetch the auction together with the vehicle owner, holding off new bids until it is settled
+    let auction = sqlx::query!(
+        r#"SELECT a.vehicle_id, a.closed, a.end_time, a.auction_type as "auction_type: AuctionType", v.owner_id
+           FROM auctions a INNER JOIN vehicles v ON v.id = a.vehicle_id WHERE a.id = $1 FOR UPDATE OF a"#,
+        auction_id
+    )
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| ApiError::new(ErrorCode::AuctionNotFound, "Auction not found"))?;
 
     // Ensure the current user is the owner of the vehicle
-    if vehicle_owner != user_name {
-        return HttpResponse::Forbidden().body("You are not the owner of the vehicle");
+    if auction.owner_id != user.id {
+        return Err(ApiError::new(ErrorCode::NotOwner, "You are not the owner of the vehicle"));
     }
 
     // Ensure the auction is open (not closed)
-    let auction_closed = match sqlx::query!(
-        "SELECT closed FROM auctions WHERE id = $1",
-        *path
-    )
-    .fetch_optional(pool.as_ref())
-    .await
-    {
-        Ok(Some(record)) => record.closed,  // Extract the `closed` field from the record
-        Ok(None) => return HttpResponse::NotFound().body("Auction not found"),
-        Err(_) => return HttpResponse::InternalServerError().body("Failed to fetch auction status"),
-    };
+    if auction.closed {
+        return Err(ApiError::new(ErrorCode::AuctionClosed, "The auction is already closed"));
+    }
 
-    if auction_closed.expect("Auction Closed missing") {
-        return HttpResponse::BadRequest().body("The auction is already closed");
+    // Sealed bids stay hidden until the end, then are all revealed in this transaction
+    if auction.auction_type == AuctionType::Sealed {
+        if Utc::now().naive_utc() < auction.end_time {
+            return Err(ApiError::new(
+                ErrorCode::AuctionNotEnded,
+                "A sealed-bid auction can only be settled after its end time",
+            ));
+        }
+        reveal_sealed_bids(&mut tx, auction_id).await?;
     }
 
-    // Find the highest bid for the auction
-    let highest_bidder: Option<String> = match sqlx::query!(
-        "SELECT bidder_username FROM bids WHERE auction_id = $1 ORDER BY bid_amount DESC LIMIT 1",
-        *path
+    // Find the highest bid for the auction, the earliest one on a tie
+    let highest_bid = sqlx::query!(
+        "SELECT b.bidder_id, u.username as bidder_username, b.bid_amount
+         FROM bids b INNER JOIN users u ON u.id = b.bidder_id
+         WHERE b.auction_id = $1 ORDER BY b.bid_amount DESC, b.created_at, b.id LIMIT 1",
+        auction_id
     )
-    .fetch_optional(pool.as_ref())
-    .await
-    {
-        Ok(Some(record)) => Some(record.bidder_username),
-        Ok(None) => None,
-        Err(_) => return HttpResponse::InternalServerError().body("Failed to fetch highest bidder"),
+    .fetch_optional(&mut *tx)
+    .await?;
+
+    // Without bids an auction can only be closed once it has run its course, and then it ended unsold
+    let Some(highest_bid) = highest_bid else {
+        if Utc::now().naive_utc() < auction.end_time {
+            return Err(ApiError::new(ErrorCode::NoBids, "No bids have been placed for this auction"));
+        }
+        transition(&mut tx, auction_id, AuctionStatus::EndedUnsold, Some(user.id), Some("End time passed without bids"))
+            .await?;
+        tx.commit().await?;
+
+        return Ok(HttpResponse::Ok().json(AuctionSettlement {
+            auction_id,
+            vehicle_id: auction.vehicle_id,
+            status: AuctionStatus::EndedUnsold,
+            winner_username: None,
+            winning_bid: None,
+        }));
     };
 
-    // If no bids, the auction cannot be closed
-    let highest_bidder = match highest_bidder {
-        Some(bidder) => bidder,
-        None => return HttpResponse::BadRequest().body("No bids have been placed for this auction"),
-    };
+    transition(&mut tx, auction_id, AuctionStatus::Sold, Some(user.id), None).await?;
 
     // Update the vehicle owner to the highest bidder
-    if let Err(_) = sqlx::query!(
-        "UPDATE vehicles SET owner_username = $1 WHERE id = (SELECT vehicle_id FROM auctions WHERE id = $2)",
-        highest_bidder,
-        *path
+    sqlx::query!(
+        "UPDATE vehicles SET owner_id = $1 WHERE id = $2",
+        highest_bid.bidder_id,
+        auction.vehicle_id
     )
-    .execute(pool.as_ref())
-    .await
-    {
-        return HttpResponse::InternalServerError().body("Failed to update vehicle owner");
+    .execute(&mut *tx)
+    .await?;
+
+    record_sale(
+        &mut tx,
+        auction.vehicle_id,
+        auction_id,
+        auction.owner_id,
+        highest_bid.bidder_id,
+        &highest_bid.bid_amount,
+    )
+    .await?;
+
+    // Record the settlement
+    sqlx::query!(
+        "UPDATE auctions SET winner_id = $2 WHERE id = $1",
+        auction_id,
+        highest_bid.bidder_id
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(HttpResponse::Ok().json(AuctionSettlement {
+        auction_id,
+        vehicle_id: auction.vehicle_id,
+        status: AuctionStatus::Sold,
+        winner_username: Some(highest_bid.bidder_username),
+        winning_bid: Some(highest_bid.bid_amount),
+    }))
+}
+
+/// Auction detail including drafts, with the seller's id for access checks.
+async fn fetch_auction_detail(pool: &PgPool, auction_id: i32) -> Result<(Option<i32>, AuctionDetail), ApiError> {
+    let auction = sqlx::query!(
+        r#"SELECT a.id, a.vehicle_id, v.name as vehicle_name, a.seller_id, s.username as "seller_username?",
+                  a.starting_price, a.start_time, a.end_time, a.status as "status: AuctionStatus",
+                  a.closed, a.auction_type as "auction_type: AuctionType", a.min_buyer_rating, a.relisted_from,
+                  (SELECT MAX(b.bid_amount) FROM bids b WHERE b.auction_id = a.id) as highest_bid,
+                  (SELECT COUNT(*) FROM bids b WHERE b.auction_id = a.id) as "bid_count!",
+                  (SELECT COUNT(*) FROM auction_watchers w WHERE w.auction_id = a.id) as "watcher_count!"
+           FROM auctions a INNER JOIN vehicles v ON v.id = a.vehicle_id
+           LEFT JOIN users s ON s.id = a.seller_id
+           WHERE a.id = $1"#,
+        auction_id
+    )
+    .fetch_optional(pool)
+    .await?
+    .ok_or_else(|| ApiError::new(ErrorCode::AuctionNotFound, "Auction not found"))?;
+
+    let seller_reputation = match auction.seller_id {
+        Some(seller_id) => Some(fetch_reputation(pool, seller_id).await?),
+        None => None,
+    };
+
+    // Walk the chain both ways; drafts and discarded drafts were never seen by buyers
+    let relist_chain = sqlx::query_as!(
+        RelistedAuction,
+        r#"WITH RECURSIVE earlier AS (
+               SELECT id, relisted_from FROM auctions WHERE id = $1
+               UNION ALL
+               SELECT a.id, a.relisted_from FROM auctions a INNER JOIN earlier e ON a.id = e.relisted_from
+           ), later AS (
+               SELECT id FROM auctions WHERE id = $1
+               UNION ALL
+               SELECT a.id FROM auctions a INNER JOIN later l ON a.relisted_from = l.id
+           )
+           SELECT a.id, a.starting_price, a.start_time, a.end_time, a.status as "status: AuctionStatus",
+                  (SELECT MAX(b.bid_amount) FROM bids b WHERE b.auction_id = a.id) as highest_bid,
+                  (SELECT COUNT(*) FROM bids b WHERE b.auction_id = a.id) as "bid_count!"
+           FROM auctions a
+           WHERE (a.id IN (SELECT id FROM earlier) OR a.id IN (SELECT id FROM later))
+             AND EXISTS(SELECT 1 FROM auction_transitions t
+                        WHERE t.auction_id = a.id AND t.to_status IN ('scheduled', 'live'))
+           ORDER BY a.id"#,
+        auction_id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok((
+        auction.seller_id,
+        AuctionDetail {
+            id: auction.id,
+            vehicle_id: auction.vehicle_id,
+            vehicle_name: auction.vehicle_name,
+            seller_username: auction.seller_username,
+            starting_price: auction.starting_price,
+            start_time: auction.start_time,
+            end_time: auction.end_time,
+            status: auction.status,
+            closed: auction.closed,
+            auction_type: auction.auction_type,
+            highest_bid: auction.highest_bid,
+            bid_count: auction.bid_count,
+            watcher_count: auction.watcher_count,
+            min_buyer_rating: auction.min_buyer_rating,
+            seller_reputation,
+            relisted_from: auction.relisted_from,
+            relist_chain,
+        },
+    ))
+}
+
+pub async fn get_auction(
+    pool: web::Data<PgPool>,
+    path: web::Path<i32>,
+) -> Result<HttpResponse, ApiError> {
+    let (_, auction) = fetch_auction_detail(&pool, *path).await?;
+    if auction.status == AuctionStatus::Draft {
+        return Err(ApiError::new(ErrorCode::AuctionNotFound, "Auction not found"));
     }
 
-    // Close the auction
-    if let Err(_) = sqlx::query!(
-        "UPDATE auctions SET closed = TRUE WHERE id = $1",
-        *path
+    Ok(HttpResponse::Ok().json(auction))
+}
+
+/// The auction as bidders will see it, available to its seller while it is still a draft.
+pub async fn preview_auction(
+    pool: web::Data<PgPool>,
+    redis_client: web::Data<redis::Client>,
+    req: HttpRequest,
+    path: web::Path<i32>,
+) -> Result<HttpResponse, ApiError> {
+    let user = require_user(&req, &pool, &redis_client).await?;
+    let (seller_id, auction) = fetch_auction_detail(&pool, *path).await?;
+    if seller_id != Some(user.id) {
+        return Err(ApiError::new(ErrorCode::NotOwner, "You are not the seller of this auction"));
+    }
+
+    Ok(HttpResponse::Ok().json(auction))
+}
+
+struct DraftAuction {
+    starting_price: f64,
+    start_time: Option<NaiveDateTime>,
+    end_time: NaiveDateTime,
+    min_buyer_rating: Option<f64>,
+}
+
+/// Lock one of the user's draft auctions for changes.
+async fn lock_draft(conn: &mut PgConnection, auction_id: i32, user_id: i32) -> Result<DraftAuction, ApiError> {
+    let auction = sqlx::query!(
+        r#"SELECT seller_id, status as "status: AuctionStatus", starting_price::FLOAT8 as "starting_price!",
+                  start_time, end_time,
+                  min_buyer_rating::FLOAT8 as min_buyer_rating
+           FROM auctions WHERE id = $1 FOR UPDATE"#,
+        auction_id
+    )
+    .fetch_optional(conn)
+    .await?
+    .filter(|auction| auction.status != AuctionStatus::Draft || auction.seller_id == Some(user_id))
+    .ok_or_else(|| ApiError::new(ErrorCode::AuctionNotFound, "Auction not found"))?;
+
+    if auction.seller_id != Some(user_id) {
+        return Err(ApiError::new(ErrorCode::NotOwner, "You are not the seller of this auction"));
+    }
+    if auction.status != AuctionStatus::Draft {
+        return Err(ApiError::new(ErrorCode::AuctionNotDraft, "The auction has already been published"));
+    }
+
+    Ok(DraftAuction {
+        starting_price: auction.starting_price,
+        start_time: auction.start_time,
+        end_time: auction.end_time,
+        min_buyer_rating: auction.min_buyer_rating,
+    })
+}
+
+/// Change the terms of a draft auction.
+pub async fn update_auction(
+    pool: web::Data<PgPool>,
+    redis_client: web::Data<redis::Client>,
+    req: HttpRequest,
+    path: web::Path<i32>,
+    form: web::Json<UpdateAuction>,
+) -> Result<HttpResponse, ApiError> {
+    let user = require_user(&req, &pool, &redis_client).await?;
+    let auction_id = *path;
+
+    let mut tx = pool.begin().await?;
+    let draft = lock_draft(&mut tx, auction_id, user.id).await?;
+    form.validate_merged(draft.starting_price, draft.start_time, draft.end_time, draft.min_buyer_rating)?;
+
+    let auction = sqlx::query_as!(
+        AuctionSummary,
+        r#"UPDATE auctions SET
+               starting_price = COALESCE($2::FLOAT8::NUMERIC, starting_price),
+               start_time = COALESCE($3, start_time),
+               end_time = COALESCE($4, end_time),
+               min_buyer_rating = COALESCE($5::FLOAT8::NUMERIC, min_buyer_rating)
+           WHERE id = $1
+           RETURNING id, vehicle_id, starting_price, start_time, end_time, status as "status: AuctionStatus", closed,
+                     auction_type as "auction_type: AuctionType""#,
+        auction_id,
+        form.starting_price,
+        form.start_time,
+        form.end_time,
+        form.min_buyer_rating
     )
-    .execute(pool.as_ref())
-    .await
-    {
-        return HttpResponse::InternalServerError().body("Failed to close auction");
+    .fetch_one(&mut *tx)
+    .await?;
+    tx.commit().await?;
+
+    Ok(HttpResponse::Ok().json(auction))
+}
+
+/// Publish a draft: scheduled when its start time is in the future, otherwise live at once.
+pub async fn publish_auction(
+    pool: web::Data<PgPool>,
+    redis_client: web::Data<redis::Client>,
+    req: HttpRequest,
+    path: web::Path<i32>,
+) -> Result<HttpResponse, ApiError> {
+    let user = require_user(&req, &pool, &redis_client).await?;
+    let auction_id = *path;
+
+    let mut tx = pool.begin().await?;
+    let draft = lock_draft(&mut tx, auction_id, user.id).await?;
+    // The end time may have passed while the draft was waiting
+    UpdateAuction::default().validate_merged(
+        draft.starting_price,
+        draft.start_time,
+        draft.end_time,
+        draft.min_buyer_rating,
+    )?;
+
+    let (status, start_time) = publication(draft.start_time);
+    sqlx::query!("UPDATE auctions SET start_time = $2 WHERE id = $1", auction_id, start_time)
+        .execute(&mut *tx)
+        .await?;
+    transition(&mut tx, auction_id, status, Some(user.id), None).await?;
+
+    match_saved_searches(&mut tx, auction_id).await?;
+    let auction = fetch_summary(&mut tx, auction_id).await?;
+    tx.commit().await?;
+
+    Ok(HttpResponse::Ok().json(auction))
+}
+
+/// Discard a draft auction, freeing the vehicle for another one; it stays in the log as cancelled.
+pub async fn delete_draft_auction(
+    pool: web::Data<PgPool>,
+    redis_client: web::Data<redis::Client>,
+    req: HttpRequest,
+    path: web::Path<i32>,
+) -> Result<HttpResponse, ApiError> {
+    let user = require_user(&req, &pool, &redis_client).await?;
+    let auction_id = *path;
+
+    let mut tx = pool.begin().await?;
+    lock_draft(&mut tx, auction_id, user.id).await?;
+    transition(&mut tx, auction_id, AuctionStatus::Cancelled, Some(user.id), Some("Draft discarded")).await?;
+    tx.commit().await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Open every scheduled auction whose start time has come. Returns the number opened.
+pub async fn start_scheduled_auctions(pool: &PgPool) -> Result<u64, ApiError> {
+    // Start times are naive UTC, so the session time zone must not take part in the comparison
+    let due = sqlx::query_scalar!(
+        "SELECT id FROM auctions WHERE status = 'scheduled' AND start_time <= $1 ORDER BY start_time, id",
+        Utc::now().naive_utc()
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let mut started = 0;
+    for auction_id in due {
+        let mut tx = pool.begin().await?;
+        match transition(&mut tx, auction_id, AuctionStatus::Live, None, Some("Start time reached")).await {
+            Ok(_) => {
+                tx.commit().await?;
+                started += 1;
+            }
+            // Cancelled or opened by a bid since it was listed
+            Err(err) if err.code == ErrorCode::IllegalAuctionTransition => {}
+            Err(err) => return Err(err),
+        }
     }
 
-    HttpResponse::Ok().body("Auction Closed and Vehicle Ownership Transferred")
-}
\ No newline at end of file
+    Ok(started)
+}
@@ -0,0 +1,151 @@
+use sqlx::PgPool;
+use actix_web::{web, HttpResponse, HttpRequest};
+use chrono::{Duration, Utc};
+use crate::auction_state::AuctionStatus;
+use crate::errors::{ApiError, ErrorCode};
+use crate::models::{AuctionBid, BidRetraction, RetractBid};
+use crate::session::require_user;
+
+/// Minutes after placing a bid during which it may be withdrawn, unless `BID_RETRACTION_WINDOW_MINUTES` overrides it.
+pub const DEFAULT_RETRACTION_WINDOW_MINUTES: i64 = 60;
+/// Hours before the end of an auction from which no bid may be withdrawn, unless `BID_RETRACTION_CUTOFF_HOURS`
+/// overrides it.
+pub const DEFAULT_RETRACTION_CUTOFF_HOURS: i64 = 12;
+
+/// When a bid may still be withdrawn.
+pub struct RetractionPolicy {
+    pub window: Duration,
+    pub cutoff: Duration,
+}
+
+/// The retraction policy configured in the environment; invalid or missing values fall back to the defaults.
+pub fn retraction_policy() -> RetractionPolicy {
+    let configured = |name: &str, default: i64| {
+        std::env::var(name)
+            .ok()
+            .and_then(|value| value.trim().parse().ok())
+            .filter(|value: &i64| *value >= 0)
+            .unwrap_or(default)
+    };
+
+    RetractionPolicy {
+        window: Duration::minutes(configured("BID_RETRACTION_WINDOW_MINUTES", DEFAULT_RETRACTION_WINDOW_MINUTES)),
+        cutoff: Duration::hours(configured("BID_RETRACTION_CUTOFF_HOURS", DEFAULT_RETRACTION_CUTOFF_HOURS)),
+    }
+}
+
+/// Withdraw one of the caller's bids, e.g. after a typo in the amount.
+///
+/// The bid moves to `bid_retractions`, so the auction's highest bid falls back to the best remaining one.
+pub async fn retract_bid(
+    pool: web::Data<PgPool>,
+    redis_client: web::Data<redis::Client>,
+    req: HttpRequest,
+    path: web::Path<i32>,
+    form: web::Json<RetractBid>,
+) -> Result<HttpResponse, ApiError> {
+    let user = require_user(&req, &pool, &redis_client).await?;
+    form.validate()?;
+    let bid_id = *path;
+    let policy = retraction_policy();
+
+    let bid = sqlx::query!("SELECT auction_id, bidder_id, bid_amount, created_at FROM bids WHERE id = $1", bid_id)
+        .fetch_optional(pool.as_ref())
+        .await?
+        .ok_or_else(|| ApiError::new(ErrorCode::BidNotFound, "Bid not found"))?;
+    if bid.bidder_id != user.id {
+        return Err(ApiError::new(ErrorCode::NotOwner, "You did not place this bid"));
+    }
+
+    // Bids on one auction are placed and withdrawn one at a time
+    let mut tx = pool.begin().await?;
+    let auction = sqlx::query!(
+        r#"SELECT status as "status: AuctionStatus", end_time FROM auctions WHERE id = $1 FOR UPDATE"#,
+        bid.auction_id
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+
+    let now = Utc::now().naive_utc();
+    if auction.status != AuctionStatus::Live || now > auction.end_time {
+        return Err(ApiError::new(ErrorCode::AuctionClosed, "Bids can only be withdrawn while the auction is open"));
+    }
+    if bid.created_at.is_none_or(|placed_at| now - placed_at > policy.window) {
+        return Err(ApiError::new(
+            ErrorCode::RetractionNotAllowed,
+            format!("Bids can only be withdrawn within {} minutes of placing them", policy.window.num_minutes()),
+        ));
+    }
+    if auction.end_time - now < policy.cutoff {
+        return Err(ApiError::new(
+            ErrorCode::RetractionNotAllowed,
+            format!("Bids cannot be withdrawn in the last {} hours of an auction", policy.cutoff.num_hours()),
+        ));
+    }
+
+    // A concurrent retraction of the same bid got there first
+    let removed = sqlx::query!("DELETE FROM bids WHERE id = $1", bid_id).execute(&mut *tx).await?;
+    if removed.rows_affected() == 0 {
+        return Err(ApiError::new(ErrorCode::BidNotFound, "Bid not found"));
+    }
+
+    let retracted_at = sqlx::query_scalar!(
+        "INSERT INTO bid_retractions (bid_id, auction_id, bidder_id, bid_amount, placed_at, reason)
+         VALUES ($1, $2, $3, $4, $5, $6) RETURNING retracted_at",
+        bid_id,
+        bid.auction_id,
+        user.id,
+        bid.bid_amount,
+        bid.created_at,
+        form.reason.trim()
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+
+    let highest_bid = sqlx::query_scalar!("SELECT MAX(bid_amount) FROM bids WHERE auction_id = $1", bid.auction_id)
+        .fetch_one(&mut *tx)
+        .await?;
+    tx.commit().await?;
+
+    Ok(HttpResponse::Ok().json(BidRetraction {
+        bid_id,
+        auction_id: bid.auction_id,
+        bid_amount: bid.bid_amount,
+        reason: form.reason.trim().to_string(),
+        retracted_at,
+        highest_bid,
+    }))
+}
+
+/// Bids on an auction, highest first, with how often each bidder has withdrawn bids. Only for the seller.
+pub async fn list_auction_bids(
+    pool: web::Data<PgPool>,
+    redis_client: web::Data<redis::Client>,
+    req: HttpRequest,
+    path: web::Path<i32>,
+) -> Result<HttpResponse, ApiError> {
+    let user = require_user(&req, &pool, &redis_client).await?;
+    let auction_id = *path;
+
+    let seller_id = sqlx::query_scalar!("SELECT seller_id FROM auctions WHERE id = $1", auction_id)
+        .fetch_optional(pool.as_ref())
+        .await?
+        .ok_or_else(|| ApiError::new(ErrorCode::AuctionNotFound, "Auction not found"))?;
+    if seller_id != Some(user.id) {
+        return Err(ApiError::new(ErrorCode::NotOwner, "You are not the seller of this auction"));
+    }
+
+    let bids = sqlx::query_as!(
+        AuctionBid,
+        r#"SELECT b.id, u.username as bidder_username, b.bid_amount, b.created_at,
+                  (SELECT COUNT(*) FROM bid_retractions r WHERE r.bidder_id = b.bidder_id) as "bidder_retraction_count!"
+           FROM bids b INNER JOIN users u ON u.id = b.bidder_id
+           WHERE b.auction_id = $1
+           ORDER BY b.bid_amount DESC, b.id"#,
+        auction_id
+    )
+    .fetch_all(pool.as_ref())
+    .await?;
+
+    Ok(HttpResponse::Ok().json(bids))
+}
@@ -0,0 +1,216 @@
+use sqlx::{PgConnection, PgExecutor, PgPool};
+use actix_web::{web, HttpResponse, HttpRequest};
+use bigdecimal::BigDecimal;
+use chrono::{Duration, NaiveDateTime, Utc};
+use crate::auction_state::{transition, AuctionStatus};
+use crate::errors::{ApiError, ErrorCode};
+use crate::models::{AuctionCancellation, CancelAuction, CANCELLATION_CUTOFF_MINUTES, CANCELLATION_FEE_PERCENT};
+use crate::notifications::notify_bidders_of_cancellation;
+use crate::session::{require_admin, require_user};
+
+async fn fetch_cancellation<'e>(
+    executor: impl PgExecutor<'e>,
+    cancellation_id: i32,
+) -> Result<AuctionCancellation, ApiError> {
+    sqlx::query_as!(
+        AuctionCancellation,
+        "SELECT c.id, c.auction_id, u.username as requested_by, c.reason, c.fee, c.status, c.created_at, c.decided_at
+         FROM auction_cancellations c INNER JOIN users u ON u.id = c.requested_by
+         WHERE c.id = $1",
+        cancellation_id
+    )
+    .fetch_optional(executor)
+    .await?
+    .ok_or_else(|| ApiError::new(ErrorCode::CancellationNotFound, "Cancellation not found"))
+}
+
+/// Bidders plan around the last hour, so nothing published is withdrawn inside it.
+fn check_cutoff(status: AuctionStatus, end_time: NaiveDateTime) -> Result<(), ApiError> {
+    let cutoff = end_time - Duration::minutes(CANCELLATION_CUTOFF_MINUTES);
+    if status != AuctionStatus::Draft && Utc::now().naive_utc() >= cutoff {
+        return Err(ApiError::new(
+            ErrorCode::CancellationWindowClosed,
+            format!("Auctions cannot be cancelled in their final {} minutes", CANCELLATION_CUTOFF_MINUTES),
+        ));
+    }
+    Ok(())
+}
+
+async fn cancel(conn: &mut PgConnection, auction_id: i32, actor_id: i32, reason: &str) -> Result<(), ApiError> {
+    transition(&mut *conn, auction_id, AuctionStatus::Cancelled, Some(actor_id), Some(reason)).await?;
+    notify_bidders_of_cancellation(conn, auction_id, reason).await
+}
+
+/// Withdraw an auction as its seller.
+///
+/// Free until the first bid. Afterwards the seller either pays a fee or the request waits for an
+/// administrator; either way never inside the final hour.
+pub async fn cancel_auction(
+    pool: web::Data<PgPool>,
+    redis_client: web::Data<redis::Client>,
+    req: HttpRequest,
+    path: web::Path<i32>,
+    form: web::Json<CancelAuction>,
+) -> Result<HttpResponse, ApiError> {
+    let user = require_user(&req, &pool, &redis_client).await?;
+    form.validate()?;
+    let auction_id = *path;
+    let reason = form.reason.trim();
+
+    let mut tx = pool.begin().await?;
+    let auction = sqlx::query!(
+        r#"SELECT seller_id, status as "status: AuctionStatus", end_time,
+                  (SELECT MAX(b.bid_amount) FROM bids b WHERE b.auction_id = a.id) as highest_bid,
+                  EXISTS(SELECT 1 FROM sealed_bids s WHERE s.auction_id = a.id) as "has_sealed_bids!",
+                  EXISTS(SELECT 1 FROM auction_cancellations c WHERE c.auction_id = a.id AND c.status = 'pending')
+                      as "pending!"
+           FROM auctions a WHERE id = $1 FOR UPDATE"#,
+        auction_id
+    )
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| ApiError::new(ErrorCode::AuctionNotFound, "Auction not found"))?;
+
+    if auction.seller_id != Some(user.id) {
+        return Err(ApiError::new(ErrorCode::NotOwner, "You are not the seller of this auction"));
+    }
+    if auction.status.is_closed() {
+        return Err(ApiError::new(ErrorCode::AuctionClosed, "The auction is already closed"));
+    }
+    if auction.pending {
+        return Err(ApiError::new(ErrorCode::CancellationPending, "A cancellation is already awaiting approval"));
+    }
+    check_cutoff(auction.status, auction.end_time)?;
+
+    let fee = auction
+        .highest_bid
+        .as_ref()
+        .filter(|_| form.pay_fee)
+        .map(|highest_bid| (highest_bid * BigDecimal::from(CANCELLATION_FEE_PERCENT) / BigDecimal::from(100)).round(2));
+    // Sealed amounts stay hidden, so there is nothing to base a fee on and an administrator decides
+    let needs_approval = (auction.highest_bid.is_some() || auction.has_sealed_bids) && fee.is_none();
+
+    let cancellation_id = sqlx::query_scalar!(
+        "INSERT INTO auction_cancellations (auction_id, requested_by, reason, fee, status)
+         VALUES ($1, $2, $3, $4, $5) RETURNING id",
+        auction_id,
+        user.id,
+        reason,
+        fee,
+        if needs_approval { "pending" } else { "completed" }
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+
+    if !needs_approval {
+        cancel(&mut tx, auction_id, user.id, reason).await?;
+    }
+
+    let cancellation = fetch_cancellation(&mut *tx, cancellation_id).await?;
+    tx.commit().await?;
+
+    Ok(HttpResponse::Ok().json(cancellation))
+}
+
+/// Cancellation requests awaiting an administrator, oldest first.
+pub async fn list_pending_cancellations(
+    pool: web::Data<PgPool>,
+    redis_client: web::Data<redis::Client>,
+    req: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    require_admin(&req, &pool, &redis_client).await?;
+
+    let cancellations = sqlx::query_as!(
+        AuctionCancellation,
+        "SELECT c.id, c.auction_id, u.username as requested_by, c.reason, c.fee, c.status, c.created_at, c.decided_at
+         FROM auction_cancellations c INNER JOIN users u ON u.id = c.requested_by
+         WHERE c.status = 'pending'
+         ORDER BY c.created_at, c.id"
+    )
+    .fetch_all(pool.as_ref())
+    .await?;
+
+    Ok(HttpResponse::Ok().json(cancellations))
+}
+
+async fn decide(
+    pool: &PgPool,
+    req: &HttpRequest,
+    redis_client: &redis::Client,
+    cancellation_id: i32,
+    approve: bool,
+) -> Result<HttpResponse, ApiError> {
+    let admin = require_admin(req, pool, redis_client).await?;
+
+    let mut tx = pool.begin().await?;
+    let request = sqlx::query!(
+        "SELECT auction_id, requested_by, reason FROM auction_cancellations
+         WHERE id = $1 AND status = 'pending' FOR UPDATE",
+        cancellation_id
+    )
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| ApiError::new(ErrorCode::CancellationNotFound, "No pending cancellation with this id"))?;
+
+    if approve {
+        let auction = sqlx::query!(
+            r#"SELECT status as "status: AuctionStatus", end_time FROM auctions WHERE id = $1 FOR UPDATE"#,
+            request.auction_id
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+        if auction.status != AuctionStatus::Live {
+            return Err(ApiError::new(ErrorCode::AuctionClosed, "The auction is no longer live"));
+        }
+        check_cutoff(auction.status, auction.end_time)?;
+        cancel(&mut tx, request.auction_id, request.requested_by, &request.reason).await?;
+    }
+
+    sqlx::query!(
+        "UPDATE auction_cancellations SET status = $2, decided_by = $3, decided_at = NOW() WHERE id = $1",
+        cancellation_id,
+        if approve { "approved" } else { "rejected" },
+        admin.id
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query!(
+        "INSERT INTO notifications (user_id, kind, message, payload)
+         SELECT $2, 'auction_cancellation_decided',
+                'Your request to cancel the auction of ' || v.name || CASE WHEN $3 THEN ' was approved' ELSE ' was rejected' END,
+                jsonb_build_object('auction_id', a.id, 'cancellation_id', $1::INT, 'approved', $3::BOOLEAN)
+         FROM auctions a INNER JOIN vehicles v ON v.id = a.vehicle_id WHERE a.id = $4",
+        cancellation_id,
+        request.requested_by,
+        approve,
+        request.auction_id
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    let cancellation = fetch_cancellation(&mut *tx, cancellation_id).await?;
+    tx.commit().await?;
+
+    Ok(HttpResponse::Ok().json(cancellation))
+}
+
+/// Approve a pending cancellation; the auction is cancelled as if the seller had paid.
+pub async fn approve_cancellation(
+    pool: web::Data<PgPool>,
+    redis_client: web::Data<redis::Client>,
+    req: HttpRequest,
+    path: web::Path<i32>,
+) -> Result<HttpResponse, ApiError> {
+    decide(&pool, &req, &redis_client, *path, true).await
+}
+
+/// Reject a pending cancellation; the auction carries on.
+pub async fn reject_cancellation(
+    pool: web::Data<PgPool>,
+    redis_client: web::Data<redis::Client>,
+    req: HttpRequest,
+    path: web::Path<i32>,
+) -> Result<HttpResponse, ApiError> {
+    decide(&pool, &req, &redis_client, *path, false).await
+}
@@ -0,0 +1,252 @@
+use sqlx::{PgExecutor, PgPool, Postgres, QueryBuilder};
+use actix_web::{web, HttpResponse, HttpRequest};
+use std::collections::HashMap;
+use crate::errors::{ApiError, ErrorCode, Validator};
+use crate::models::{slugify, Category, CategoryNode, CreateCategory, FacetCount, UpdateCategory};
+use crate::routes::vehicle::NOT_SOLD;
+use crate::session::require_admin;
+
+/// Restrict `vehicles v` to a category and its subcategories, by slug.
+pub fn push_category_filter(query: &mut QueryBuilder<'_, Postgres>, slug: String) {
+    query
+        .push(
+            " AND v.category_id IN (SELECT cc.category_id FROM category_closure cc
+              INNER JOIN categories c ON c.id = cc.ancestor_id WHERE c.slug = ",
+        )
+        .push_bind(slug)
+        .push(")");
+}
+
+/// Vehicles per category slug, each counted in its own category and every ancestor.
+///
+/// `push_vehicles` appends the `FROM vehicles v WHERE ...` clause selecting the vehicles to count.
+pub async fn category_counts<'e, F>(executor: impl PgExecutor<'e>, push_vehicles: F) -> Result<Vec<FacetCount>, ApiError>
+where
+    F: for<'q> FnOnce(&mut QueryBuilder<'q, Postgres>),
+{
+    let mut query = QueryBuilder::new(
+        "SELECT c.slug as value, COUNT(*) as count FROM category_closure cc
+         INNER JOIN categories c ON c.id = cc.ancestor_id
+         INNER JOIN (SELECT v.category_id",
+    );
+    push_vehicles(&mut query);
+    query.push(") m ON m.category_id = cc.category_id GROUP BY c.slug ORDER BY 2 DESC, 1");
+
+    Ok(query.build_query_as().fetch_all(executor).await?)
+}
+
+/// Reject a `category_id` that names no category.
+pub async fn ensure_category_exists<'e>(
+    executor: impl PgExecutor<'e>,
+    category_id: Option<i32>,
+) -> Result<(), ApiError> {
+    let Some(category_id) = category_id else {
+        return Ok(());
+    };
+
+    let exists = sqlx::query_scalar!(
+        r#"SELECT EXISTS(SELECT 1 FROM categories WHERE id = $1) as "exists!""#,
+        category_id
+    )
+    .fetch_one(executor)
+    .await?;
+
+    Validator::default().check(exists, "category_id", "does not exist").finish()
+}
+
+async fn ensure_slug_free<'e>(executor: impl PgExecutor<'e>, slug: &str, except_id: Option<i32>) -> Result<(), ApiError> {
+    let taken = sqlx::query_scalar!(
+        r#"SELECT EXISTS(SELECT 1 FROM categories WHERE slug = $1 AND ($2::INT IS NULL OR id <> $2)) as "taken!""#,
+        slug,
+        except_id
+    )
+    .fetch_one(executor)
+    .await?;
+
+    Validator::default().check(!taken, "slug", "is already in use").finish()
+}
+
+fn build_tree(
+    parent_id: Option<i32>,
+    children: &mut HashMap<Option<i32>, Vec<Category>>,
+    counts: &HashMap<String, i64>,
+) -> Vec<CategoryNode> {
+    children
+        .remove(&parent_id)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|category| CategoryNode {
+            vehicle_count: counts.get(&category.slug).copied().unwrap_or(0),
+            children: build_tree(Some(category.id), children, counts),
+            category,
+        })
+        .collect()
+}
+
+/// The whole category tree with the number of listed vehicles under each node.
+pub async fn list_categories(pool: web::Data<PgPool>) -> Result<HttpResponse, ApiError> {
+    let categories = sqlx::query_as!(
+        Category,
+        "SELECT id, parent_id, name, slug, position FROM categories ORDER BY position, name, id"
+    )
+    .fetch_all(pool.as_ref())
+    .await?;
+
+    // Same vehicles as an unfiltered listing
+    let counts = category_counts(pool.as_ref(), |query| {
+        query.push(" FROM vehicles v WHERE v.deleted_at IS NULL AND ").push(NOT_SOLD);
+    })
+    .await?;
+    let counts: HashMap<String, i64> = counts.into_iter().map(|facet| (facet.value, facet.count)).collect();
+
+    let mut children: HashMap<Option<i32>, Vec<Category>> = HashMap::new();
+    for category in categories {
+        children.entry(category.parent_id).or_default().push(category);
+    }
+
+    Ok(HttpResponse::Ok().json(build_tree(None, &mut children, &counts)))
+}
+
+pub async fn create_category(
+    pool: web::Data<PgPool>,
+    redis_client: web::Data<redis::Client>,
+    req: HttpRequest,
+    form: web::Json<CreateCategory>,
+) -> Result<HttpResponse, ApiError> {
+    require_admin(&req, &pool, &redis_client).await?;
+    form.validate()?;
+    let slug = form.slug.clone().unwrap_or_else(|| slugify(&form.name));
+
+    let mut tx = pool.begin().await?;
+    if let Some(parent_id) = form.parent_id {
+        let parent_exists = sqlx::query_scalar!(
+            r#"SELECT EXISTS(SELECT 1 FROM categories WHERE id = $1) as "exists!""#,
+            parent_id
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+        Validator::default().check(parent_exists, "parent_id", "does not exist").finish()?;
+    }
+    ensure_slug_free(&mut *tx, &slug, None).await?;
+
+    let category = sqlx::query_as!(
+        Category,
+        "INSERT INTO categories (parent_id, name, slug, position) VALUES ($1, $2, $3, $4)
+         RETURNING id, parent_id, name, slug, position",
+        form.parent_id,
+        form.name.trim(),
+        slug,
+        form.position
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+    tx.commit().await?;
+
+    Ok(HttpResponse::Ok().json(category))
+}
+
+/// Rename, reorder or move a category; a category cannot move below itself.
+pub async fn update_category(
+    pool: web::Data<PgPool>,
+    redis_client: web::Data<redis::Client>,
+    req: HttpRequest,
+    path: web::Path<i32>,
+    form: web::Json<UpdateCategory>,
+) -> Result<HttpResponse, ApiError> {
+    require_admin(&req, &pool, &redis_client).await?;
+    form.validate()?;
+    let category_id = *path;
+
+    let mut tx = pool.begin().await?;
+    // Concurrent moves could otherwise combine into a cycle
+    sqlx::query!("LOCK TABLE categories IN SHARE ROW EXCLUSIVE MODE")
+        .execute(&mut *tx)
+        .await?;
+
+    sqlx::query_scalar!("SELECT id FROM categories WHERE id = $1", category_id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| ApiError::new(ErrorCode::CategoryNotFound, "Category not found"))?;
+
+    if let Some(Some(parent_id)) = form.parent_id {
+        let parent = sqlx::query!(
+            r#"SELECT EXISTS(SELECT 1 FROM category_closure WHERE category_id = $1 AND ancestor_id = $2)
+                      as "inside_subtree!"
+               FROM categories WHERE id = $1"#,
+            parent_id,
+            category_id
+        )
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        Validator::default()
+            .check(parent.is_some(), "parent_id", "does not exist")
+            .check(
+                parent.is_none_or(|parent| !parent.inside_subtree),
+                "parent_id",
+                "must not be the category itself or one of its subcategories",
+            )
+            .finish()?;
+    }
+    if let Some(slug) = form.slug.as_deref() {
+        ensure_slug_free(&mut *tx, slug, Some(category_id)).await?;
+    }
+
+    let category = sqlx::query_as!(
+        Category,
+        "UPDATE categories SET
+            name = COALESCE($2, name),
+            slug = COALESCE($3, slug),
+            position = COALESCE($4, position),
+            parent_id = CASE WHEN $5 THEN $6 ELSE parent_id END
+         WHERE id = $1
+         RETURNING id, parent_id, name, slug, position",
+        category_id,
+        form.name.as_deref().map(str::trim),
+        form.slug,
+        form.position,
+        form.parent_id.is_some(),
+        form.parent_id.flatten()
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+    tx.commit().await?;
+
+    Ok(HttpResponse::Ok().json(category))
+}
+
+/// Remove an empty category: no subcategories and no vehicles, archived ones included.
+pub async fn delete_category(
+    pool: web::Data<PgPool>,
+    redis_client: web::Data<redis::Client>,
+    req: HttpRequest,
+    path: web::Path<i32>,
+) -> Result<HttpResponse, ApiError> {
+    require_admin(&req, &pool, &redis_client).await?;
+    let category_id = *path;
+
+    let mut tx = pool.begin().await?;
+    let usage = sqlx::query!(
+        r#"SELECT EXISTS(SELECT 1 FROM categories WHERE parent_id = c.id) as "has_children!",
+                  EXISTS(SELECT 1 FROM vehicles WHERE category_id = c.id) as "has_vehicles!"
+           FROM categories c WHERE c.id = $1 FOR UPDATE"#,
+        category_id
+    )
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| ApiError::new(ErrorCode::CategoryNotFound, "Category not found"))?;
+
+    if usage.has_children || usage.has_vehicles {
+        return Err(ApiError::new(
+            ErrorCode::CategoryInUse,
+            "Move its subcategories and vehicles elsewhere before deleting this category",
+        ));
+    }
+
+    sqlx::query!("DELETE FROM categories WHERE id = $1", category_id)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
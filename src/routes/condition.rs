@@ -0,0 +1,189 @@
+use sqlx::PgPool;
+use actix_web::{web, HttpResponse, HttpRequest};
+use crate::errors::{ApiError, ErrorCode, Validator};
+use crate::models::{ConditionItem, ConditionReport, CreateConditionReport};
+use crate::routes::image::{fetch_images, require_vehicle_owner};
+use crate::session::require_user;
+
+async fn fetch_report(pool: &PgPool, report_id: i32) -> Result<ConditionReport, ApiError> {
+    let report = sqlx::query!(
+        "SELECT r.id, r.vehicle_id, u.username as author_username, r.inspection_date, r.rust_rating, r.overall_notes,
+                r.third_party_inspected, r.inspector_name, r.inspection_document_id, r.created_at
+         FROM condition_reports r INNER JOIN users u ON u.id = r.author_id
+         WHERE r.id = $1",
+        report_id
+    )
+    .fetch_one(pool)
+    .await?;
+
+    let items = sqlx::query!(
+        "SELECT section, item, rating, notes FROM condition_report_items WHERE report_id = $1 ORDER BY id",
+        report_id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let photo_ids = sqlx::query_scalar!(
+        "SELECT image_id FROM condition_report_photos WHERE report_id = $1",
+        report_id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let (mut panels, mut mechanical) = (Vec::new(), Vec::new());
+    for row in items {
+        let entry = ConditionItem { item: row.item, rating: row.rating, notes: row.notes };
+        if row.section == "panel" {
+            panels.push(entry);
+        } else {
+            mechanical.push(entry);
+        }
+    }
+
+    let photos = fetch_images(pool, report.vehicle_id)
+        .await?
+        .into_iter()
+        .filter(|image| photo_ids.contains(&image.id))
+        .collect();
+
+    Ok(ConditionReport {
+        id: report.id,
+        vehicle_id: report.vehicle_id,
+        author_username: report.author_username,
+        inspection_date: report.inspection_date,
+        rust_rating: report.rust_rating,
+        overall_notes: report.overall_notes,
+        third_party_inspected: report.third_party_inspected,
+        inspector_name: report.inspector_name,
+        inspection_document_id: report.inspection_document_id,
+        panels,
+        mechanical,
+        photos,
+        created_at: report.created_at,
+    })
+}
+
+pub async fn create_condition_report(
+    pool: web::Data<PgPool>,
+    redis_client: web::Data<redis::Client>,
+    req: HttpRequest,
+    path: web::Path<i32>,
+    form: web::Json<CreateConditionReport>,
+) -> Result<HttpResponse, ApiError> {
+    let user = require_user(&req, &pool, &redis_client).await?;
+    form.validate()?;
+    let vehicle_id = *path;
+
+    let mut tx = pool.begin().await?;
+    require_vehicle_owner(&mut *tx, vehicle_id, user.id).await?;
+
+    // Photos and the inspection document must belong to this vehicle
+    let known_photos = sqlx::query_scalar!(
+        r#"SELECT COUNT(*) as "count!" FROM vehicle_images WHERE vehicle_id = $1 AND id = ANY($2)"#,
+        vehicle_id,
+        &form.photo_ids
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+
+    let document_ok = match form.inspection_document_id {
+        Some(document_id) => sqlx::query_scalar!(
+            r#"SELECT EXISTS(
+                SELECT 1 FROM vehicle_documents WHERE vehicle_id = $1 AND id = $2 AND doc_type = 'inspection_report'
+            ) as "exists!""#,
+            vehicle_id,
+            document_id
+        )
+        .fetch_one(&mut *tx)
+        .await?,
+        None => true,
+    };
+
+    let mut photo_ids = form.photo_ids.clone();
+    photo_ids.sort_unstable();
+    photo_ids.dedup();
+    Validator::default()
+        .check(known_photos as usize == photo_ids.len(), "photo_ids", "must reference images of this vehicle")
+        .check(document_ok, "inspection_document_id", "must reference an inspection report of this vehicle")
+        .finish()?;
+
+    let report_id = sqlx::query_scalar!(
+        "INSERT INTO condition_reports (vehicle_id, author_id, inspection_date, rust_rating, overall_notes,
+                third_party_inspected, inspector_name, inspection_document_id)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+         RETURNING id",
+        vehicle_id,
+        user.id,
+        form.inspection_date,
+        form.rust_rating,
+        form.overall_notes,
+        form.third_party_inspected,
+        form.inspector_name,
+        form.inspection_document_id
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+
+    let sections = form
+        .panels
+        .iter()
+        .map(|entry| ("panel", entry))
+        .chain(form.mechanical.iter().map(|entry| ("mechanical", entry)));
+    let (mut section, mut item, mut rating, mut notes) = (Vec::new(), Vec::new(), Vec::new(), Vec::new());
+    for (name, entry) in sections {
+        section.push(name.to_string());
+        item.push(entry.item.clone());
+        rating.push(entry.rating.clone());
+        notes.push(entry.notes.clone());
+    }
+
+    sqlx::query!(
+        "INSERT INTO condition_report_items (report_id, section, item, rating, notes)
+         SELECT $1, * FROM UNNEST($2::TEXT[], $3::TEXT[], $4::TEXT[], $5::TEXT[])",
+        report_id,
+        &section,
+        &item,
+        &rating,
+        &notes as &[Option<String>]
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query!(
+        "INSERT INTO condition_report_photos (report_id, image_id) SELECT $1, UNNEST($2::INT[])",
+        report_id,
+        &photo_ids
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    let report = fetch_report(&pool, report_id).await?;
+    Ok(HttpResponse::Ok().json(report))
+}
+
+/// Condition reports of a vehicle, newest first.
+pub async fn list_condition_reports(
+    pool: web::Data<PgPool>,
+    path: web::Path<i32>,
+) -> Result<HttpResponse, ApiError> {
+    let vehicle_id = *path;
+    sqlx::query_scalar!("SELECT id FROM vehicles WHERE id = $1 AND deleted_at IS NULL", vehicle_id)
+        .fetch_optional(pool.as_ref())
+        .await?
+        .ok_or_else(|| ApiError::new(ErrorCode::VehicleNotFound, "Vehicle not found"))?;
+
+    let report_ids = sqlx::query_scalar!(
+        "SELECT id FROM condition_reports WHERE vehicle_id = $1 ORDER BY inspection_date DESC, id DESC",
+        vehicle_id
+    )
+    .fetch_all(pool.as_ref())
+    .await?;
+
+    let mut reports = Vec::with_capacity(report_ids.len());
+    for report_id in report_ids {
+        reports.push(fetch_report(&pool, report_id).await?);
+    }
+    Ok(HttpResponse::Ok().json(reports))
+}
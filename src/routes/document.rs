@@ -0,0 +1,237 @@
+use sqlx::PgPool;
+use actix_multipart::Multipart;
+use actix_web::{web, HttpResponse, HttpRequest};
+use bytes::Bytes;
+use futures_util::TryStreamExt;
+use crate::errors::{ApiError, ErrorCode, Validator};
+use crate::models::{VehicleDocument, DOCUMENT_TYPES};
+use crate::routes::image::{remove_files, require_vehicle_owner};
+use crate::session::require_user;
+use crate::storage::Storage;
+
+/// Largest document accepted, in bytes.
+pub const MAX_DOCUMENT_BYTES: usize = 20 * 1024 * 1024;
+/// Storage key prefix for documents; kept apart from the publicly served images.
+pub const DOCUMENT_KEY_PREFIX: &str = "documents/";
+const MAX_TEXT_FIELD_BYTES: usize = 1024;
+
+#[derive(Default)]
+struct DocumentUpload {
+    file: Option<Vec<u8>>,
+    doc_type: Option<String>,
+    title: Option<String>,
+}
+
+async fn read_document_upload(mut payload: Multipart) -> Result<DocumentUpload, ApiError> {
+    let invalid_payload = |_| ApiError::new(ErrorCode::InvalidPayload, "Malformed multipart body");
+    let mut upload = DocumentUpload::default();
+
+    while let Some(mut field) = payload.try_next().await.map_err(invalid_payload)? {
+        let name = field.name().unwrap_or_default().to_string();
+        let limit = if name == "file" { MAX_DOCUMENT_BYTES } else { MAX_TEXT_FIELD_BYTES };
+
+        let mut data = Vec::new();
+        while let Some(chunk) = field.try_next().await.map_err(invalid_payload)? {
+            if data.len() + chunk.len() > limit {
+                Validator::default().check(false, &name, "is too large").finish()?;
+            }
+            data.extend_from_slice(&chunk);
+        }
+
+        match name.as_str() {
+            "file" => upload.file = Some(data),
+            "doc_type" => upload.doc_type = String::from_utf8(data).ok(),
+            "title" => upload.title = String::from_utf8(data).ok(),
+            _ => {}
+        }
+    }
+
+    Ok(upload)
+}
+
+/// Detect the document type from its content rather than trusting the client.
+fn sniff_content_type(data: &[u8]) -> Option<&'static str> {
+    if data.starts_with(b"%PDF-") {
+        return Some("application/pdf");
+    }
+    match image::guess_format(data).ok()? {
+        image::ImageFormat::Jpeg => Some("image/jpeg"),
+        image::ImageFormat::Png => Some("image/png"),
+        image::ImageFormat::WebP => Some("image/webp"),
+        _ => None,
+    }
+}
+
+async fn fetch_documents(pool: &PgPool, vehicle_id: i32) -> Result<Vec<VehicleDocument>, ApiError> {
+    let documents = sqlx::query_as!(
+        VehicleDocument,
+        r#"SELECT d.id, d.doc_type, d.title, d.content_type, d.byte_size, u.username as uploaded_by,
+                  '/vehicles/' || d.vehicle_id || '/documents/' || d.id || '/file' as "url!", d.created_at
+           FROM vehicle_documents d INNER JOIN users u ON u.id = d.uploaded_by
+           WHERE d.vehicle_id = $1 ORDER BY d.created_at, d.id"#,
+        vehicle_id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(documents)
+}
+
+pub async fn upload_vehicle_document(
+    pool: web::Data<PgPool>,
+    redis_client: web::Data<redis::Client>,
+    storage: web::Data<dyn Storage>,
+    req: HttpRequest,
+    path: web::Path<i32>,
+    payload: Multipart,
+) -> Result<HttpResponse, ApiError> {
+    let user = require_user(&req, &pool, &redis_client).await?;
+    let vehicle_id = *path;
+    require_vehicle_owner(pool.as_ref(), vehicle_id, user.id).await?;
+
+    let upload = read_document_upload(payload).await?;
+    let content_type = upload.file.as_deref().and_then(sniff_content_type);
+    let doc_type = upload.doc_type.unwrap_or_default();
+    let title = upload.title.filter(|title| !title.trim().is_empty()).unwrap_or_else(|| doc_type.clone());
+    Validator::default()
+        .check(upload.file.is_some(), "file", "is required")
+        .check(
+            upload.file.is_none() || content_type.is_some(),
+            "file",
+            "must be a PDF, JPEG, PNG or WebP file",
+        )
+        .check(DOCUMENT_TYPES.contains(&doc_type.as_str()), "doc_type", "is not a supported document type")
+        .check(title.len() <= 255, "title", "must be at most 255 characters")
+        .finish()?;
+
+    let (Some(data), Some(content_type)) = (upload.file, content_type) else {
+        return Err(ApiError::new(ErrorCode::InternalError, "Document upload was not validated"));
+    };
+    let byte_size = data.len() as i32;
+    let key = format!("{}{}/{}", DOCUMENT_KEY_PREFIX, vehicle_id, uuid::Uuid::new_v4());
+    storage.put(&key, content_type, Bytes::from(data)).await?;
+
+    let inserted = sqlx::query_scalar!(
+        "INSERT INTO vehicle_documents (vehicle_id, doc_type, title, storage_key, content_type, byte_size, uploaded_by)
+         VALUES ($1, $2, $3, $4, $5, $6, $7)
+         RETURNING id",
+        vehicle_id,
+        doc_type,
+        title,
+        key,
+        content_type,
+        byte_size,
+        user.id
+    )
+    .fetch_one(pool.as_ref())
+    .await;
+
+    let document_id = match inserted {
+        Ok(document_id) => document_id,
+        Err(err) => {
+            remove_files(storage.as_ref(), &[key]).await;
+            return Err(err.into());
+        }
+    };
+
+    let document = fetch_documents(&pool, vehicle_id)
+        .await?
+        .into_iter()
+        .find(|document| document.id == document_id)
+        .ok_or_else(|| ApiError::new(ErrorCode::DocumentNotFound, "Document not found"))?;
+    Ok(HttpResponse::Ok().json(document))
+}
+
+pub async fn list_vehicle_documents(
+    pool: web::Data<PgPool>,
+    redis_client: web::Data<redis::Client>,
+    req: HttpRequest,
+    path: web::Path<i32>,
+) -> Result<HttpResponse, ApiError> {
+    require_user(&req, &pool, &redis_client).await?;
+    let vehicle_id = *path;
+    sqlx::query_scalar!("SELECT id FROM vehicles WHERE id = $1 AND deleted_at IS NULL", vehicle_id)
+        .fetch_optional(pool.as_ref())
+        .await?
+        .ok_or_else(|| ApiError::new(ErrorCode::VehicleNotFound, "Vehicle not found"))?;
+
+    let documents = fetch_documents(&pool, vehicle_id).await?;
+    Ok(HttpResponse::Ok().json(documents))
+}
+
+pub async fn download_vehicle_document(
+    pool: web::Data<PgPool>,
+    redis_client: web::Data<redis::Client>,
+    storage: web::Data<dyn Storage>,
+    req: HttpRequest,
+    path: web::Path<(i32, i32)>,
+) -> Result<HttpResponse, ApiError> {
+    require_user(&req, &pool, &redis_client).await?;
+    let (vehicle_id, document_id) = path.into_inner();
+    sqlx::query_scalar!("SELECT id FROM vehicles WHERE id = $1 AND deleted_at IS NULL", vehicle_id)
+        .fetch_optional(pool.as_ref())
+        .await?
+        .ok_or_else(|| ApiError::new(ErrorCode::VehicleNotFound, "Vehicle not found"))?;
+
+    let document = sqlx::query!(
+        "SELECT storage_key, content_type FROM vehicle_documents WHERE vehicle_id = $1 AND id = $2",
+        vehicle_id,
+        document_id
+    )
+    .fetch_optional(pool.as_ref())
+    .await?
+    .ok_or_else(|| ApiError::new(ErrorCode::DocumentNotFound, "Document not found"))?;
+
+    let data = storage
+        .get(&document.storage_key)
+        .await?
+        .ok_or_else(|| ApiError::new(ErrorCode::DocumentNotFound, "Document not found"))?;
+
+    let extension = document.content_type.rsplit('/').next().unwrap_or("bin");
+    Ok(HttpResponse::Ok()
+        .content_type(document.content_type.as_str())
+        .insert_header((
+            "Content-Disposition",
+            format!("attachment; filename=\"document-{}.{}\"", document_id, extension),
+        ))
+        .insert_header(("Cache-Control", "private, no-store"))
+        .body(data))
+}
+
+pub async fn delete_vehicle_document(
+    pool: web::Data<PgPool>,
+    redis_client: web::Data<redis::Client>,
+    storage: web::Data<dyn Storage>,
+    req: HttpRequest,
+    path: web::Path<(i32, i32)>,
+) -> Result<HttpResponse, ApiError> {
+    let user = require_user(&req, &pool, &redis_client).await?;
+    let (vehicle_id, document_id) = path.into_inner();
+
+    let mut tx = pool.begin().await?;
+    require_vehicle_owner(&mut *tx, vehicle_id, user.id).await?;
+
+    // Provenance entries are append-only, so their evidence has to stay
+    let is_evidence = sqlx::query_scalar!(
+        r#"SELECT EXISTS(SELECT 1 FROM vehicle_ownership WHERE evidence_document_id = $1) as "exists!""#,
+        document_id
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+    if is_evidence {
+        return Err(ApiError::new(ErrorCode::DocumentInUse, "The document backs a provenance entry"));
+    }
+
+    let storage_key = sqlx::query_scalar!(
+        "DELETE FROM vehicle_documents WHERE vehicle_id = $1 AND id = $2 RETURNING storage_key",
+        vehicle_id,
+        document_id
+    )
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| ApiError::new(ErrorCode::DocumentNotFound, "Document not found"))?;
+    tx.commit().await?;
+
+    remove_files(storage.as_ref(), &[storage_key]).await;
+    Ok(HttpResponse::NoContent().finish())
+}
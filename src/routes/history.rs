@@ -0,0 +1,157 @@
+use sqlx::{PgConnection, PgPool};
+use actix_web::{web, HttpResponse, HttpRequest};
+use bigdecimal::BigDecimal;
+use std::str::FromStr;
+use crate::errors::{ApiError, ErrorCode, Validator};
+use crate::models::{CreateProvenanceEntry, OwnershipEntry, PROVENANCE_DOCUMENT_TYPES};
+use crate::routes::image::require_vehicle_owner;
+use crate::session::require_user;
+
+/// First entry of every vehicle's history: the user who listed it.
+pub async fn record_registration(conn: &mut PgConnection, vehicle_id: i32, owner_id: i32) -> Result<(), ApiError> {
+    sqlx::query!(
+        "INSERT INTO vehicle_ownership (vehicle_id, kind, to_user_id, transferred_at, recorded_by)
+         VALUES ($1, 'registration', $2, NOW(), $2)",
+        vehicle_id,
+        owner_id
+    )
+    .execute(conn)
+    .await?;
+
+    Ok(())
+}
+
+/// Transfer from the seller to the winner of a settled auction.
+pub async fn record_sale(
+    conn: &mut PgConnection,
+    vehicle_id: i32,
+    auction_id: i32,
+    from_user_id: i32,
+    to_user_id: i32,
+    price: &BigDecimal,
+) -> Result<(), ApiError> {
+    sqlx::query!(
+        "INSERT INTO vehicle_ownership (vehicle_id, kind, from_user_id, to_user_id, auction_id, price, transferred_at,
+                recorded_by)
+         VALUES ($1, 'sale', $3, $4, $2, $5, NOW(), $3)",
+        vehicle_id,
+        auction_id,
+        from_user_id,
+        to_user_id,
+        price
+    )
+    .execute(conn)
+    .await?;
+
+    Ok(())
+}
+
+async fn fetch_history(pool: &PgPool, vehicle_id: i32) -> Result<Vec<OwnershipEntry>, ApiError> {
+    let entries = sqlx::query_as!(
+        OwnershipEntry,
+        "SELECT o.id, o.kind, COALESCE(fu.username, o.from_name) as from_owner,
+                COALESCE(tu.username, o.to_name) as to_owner, o.auction_id, o.price, o.transferred_at, o.notes,
+                o.evidence_document_id, ru.username as \"recorded_by?\"
+         FROM vehicle_ownership o
+         LEFT JOIN users fu ON fu.id = o.from_user_id
+         LEFT JOIN users tu ON tu.id = o.to_user_id
+         LEFT JOIN users ru ON ru.id = o.recorded_by
+         WHERE o.vehicle_id = $1
+         ORDER BY o.transferred_at, o.id",
+        vehicle_id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(entries)
+}
+
+/// Chain of custody of a vehicle, oldest first.
+pub async fn get_vehicle_history(
+    pool: web::Data<PgPool>,
+    path: web::Path<i32>,
+) -> Result<HttpResponse, ApiError> {
+    let vehicle_id = *path;
+    sqlx::query_scalar!("SELECT id FROM vehicles WHERE id = $1 AND deleted_at IS NULL", vehicle_id)
+        .fetch_optional(pool.as_ref())
+        .await?
+        .ok_or_else(|| ApiError::new(ErrorCode::VehicleNotFound, "Vehicle not found"))?;
+
+    let history = fetch_history(&pool, vehicle_id).await?;
+    Ok(HttpResponse::Ok().json(history))
+}
+
+/// Add an owner from before the vehicle was listed, backed by one of its documents.
+pub async fn add_provenance_entry(
+    pool: web::Data<PgPool>,
+    redis_client: web::Data<redis::Client>,
+    req: HttpRequest,
+    path: web::Path<i32>,
+    form: web::Json<CreateProvenanceEntry>,
+) -> Result<HttpResponse, ApiError> {
+    let user = require_user(&req, &pool, &redis_client).await?;
+    form.validate()?;
+    let vehicle_id = *path;
+
+    let price = form
+        .price
+        .map(|price| BigDecimal::from_str(&price.to_string()))
+        .transpose()
+        .map_err(|_| ApiError::new(ErrorCode::InvalidPayload, "Failed to parse price"))?;
+
+    let mut tx = pool.begin().await?;
+    require_vehicle_owner(&mut *tx, vehicle_id, user.id).await?;
+
+    let evidence_ok = sqlx::query_scalar!(
+        r#"SELECT EXISTS(
+            SELECT 1 FROM vehicle_documents WHERE vehicle_id = $1 AND id = $2 AND doc_type = ANY($3)
+        ) as "exists!""#,
+        vehicle_id,
+        form.evidence_document_id,
+        PROVENANCE_DOCUMENT_TYPES as &[&str]
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+
+    // Provenance covers the time before the vehicle was listed here
+    let listed_on = sqlx::query_scalar!(
+        "SELECT MIN(transferred_at)::DATE FROM vehicle_ownership WHERE vehicle_id = $1 AND kind <> 'provenance'",
+        vehicle_id
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+
+    Validator::default()
+        .check(
+            evidence_ok,
+            "evidence_document_id",
+            "must reference a title, registration or invoice of this vehicle",
+        )
+        .check(
+            listed_on.is_none_or(|listed_on| form.transferred_on < listed_on),
+            "transferred_on",
+            "must be before the vehicle was listed",
+        )
+        .finish()?;
+
+    sqlx::query!(
+        "INSERT INTO vehicle_ownership (vehicle_id, kind, from_name, to_name, price, transferred_at, notes,
+                evidence_document_id, recorded_by)
+         VALUES ($1, 'provenance', $2, $3, $4, $5::DATE, $6, $7, $8)",
+        vehicle_id,
+        form.from_name.as_deref().map(str::trim).filter(|name| !name.is_empty()),
+        form.to_name.trim(),
+        price,
+        form.transferred_on,
+        form.notes,
+        form.evidence_document_id,
+        user.id
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    let history = fetch_history(&pool, vehicle_id).await?;
+    Ok(HttpResponse::Ok().json(history))
+}
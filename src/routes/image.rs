@@ -0,0 +1,291 @@
+use sqlx::PgPool;
+use actix_multipart::Multipart;
+use actix_web::{web, HttpResponse, HttpRequest};
+use bytes::Bytes;
+use futures_util::TryStreamExt;
+use crate::errors::{ApiError, ErrorCode, FieldError};
+use crate::images::{self, DERIVATIVE_CONTENT_TYPE, MAX_IMAGE_BYTES};
+use crate::models::{ReorderImages, VehicleImage};
+use crate::session::require_user;
+use crate::storage::Storage;
+
+/// URL prefix under which stored media is served.
+pub const MEDIA_PREFIX: &str = "/media/";
+/// Storage key prefix for image derivatives, the only keys served publicly.
+pub const IMAGE_KEY_PREFIX: &str = "vehicles/";
+
+pub async fn fetch_images(pool: &PgPool, vehicle_id: i32) -> Result<Vec<VehicleImage>, ApiError> {
+    let images = sqlx::query_as!(
+        VehicleImage,
+        r#"SELECT id, position, is_primary, $2 || web_key as "url!", $2 || thumbnail_key as "thumbnail_url!",
+                  width, height, created_at
+           FROM vehicle_images WHERE vehicle_id = $1 ORDER BY position, id"#,
+        vehicle_id,
+        MEDIA_PREFIX
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(images)
+}
+
+pub async fn require_vehicle_owner<'e>(
+    executor: impl sqlx::PgExecutor<'e>,
+    vehicle_id: i32,
+    user_id: i32,
+) -> Result<(), ApiError> {
+    let owner_id = sqlx::query_scalar!(
+        "SELECT owner_id FROM vehicles WHERE id = $1 AND deleted_at IS NULL FOR UPDATE",
+        vehicle_id
+    )
+    .fetch_optional(executor)
+    .await?
+    .ok_or_else(|| ApiError::new(ErrorCode::VehicleNotFound, "Vehicle not found"))?;
+
+    if owner_id != user_id {
+        return Err(ApiError::new(ErrorCode::NotOwner, "You are not the owner of this vehicle"));
+    }
+    Ok(())
+}
+
+/// Read the `image` field of a multipart upload, enforcing the size limit while streaming.
+async fn read_image_field(mut payload: Multipart) -> Result<Vec<u8>, ApiError> {
+    let invalid_payload = |_| ApiError::new(ErrorCode::InvalidPayload, "Malformed multipart body");
+
+    while let Some(mut field) = payload.try_next().await.map_err(invalid_payload)? {
+        if field.name() != Some("image") {
+            continue;
+        }
+
+        let mut data = Vec::new();
+        while let Some(chunk) = field.try_next().await.map_err(invalid_payload)? {
+            if data.len() + chunk.len() > MAX_IMAGE_BYTES {
+                return Err(images::invalid_image("must be at most 10 MB"));
+            }
+            data.extend_from_slice(&chunk);
+        }
+        return Ok(data);
+    }
+
+    Err(images::invalid_image("is required"))
+}
+
+/// Best effort removal of stored files whose database rows are gone.
+pub async fn remove_files(storage: &dyn Storage, keys: &[String]) {
+    for key in keys {
+        if let Err(err) = storage.delete(key).await {
+            eprintln!("Failed to remove stored file {}: {}", key, err);
+        }
+    }
+}
+
+pub async fn upload_vehicle_image(
+    pool: web::Data<PgPool>,
+    redis_client: web::Data<redis::Client>,
+    storage: web::Data<dyn Storage>,
+    req: HttpRequest,
+    path: web::Path<i32>,
+    payload: Multipart,
+) -> Result<HttpResponse, ApiError> {
+    let user = require_user(&req, &pool, &redis_client).await?;
+    let vehicle_id = *path;
+    require_vehicle_owner(pool.as_ref(), vehicle_id, user.id).await?;
+
+    let data = read_image_field(payload).await?;
+    let processed = web::block(move || images::process(&data))
+        .await
+        .map_err(|_| ApiError::new(ErrorCode::InternalError, "Image processing failed"))??;
+
+    let stem = format!("{}{}/{}", IMAGE_KEY_PREFIX, vehicle_id, uuid::Uuid::new_v4());
+    let keys = [format!("{}-web.jpg", stem), format!("{}-thumb.jpg", stem)];
+    storage.put(&keys[0], DERIVATIVE_CONTENT_TYPE, Bytes::from(processed.web)).await?;
+    storage.put(&keys[1], DERIVATIVE_CONTENT_TYPE, Bytes::from(processed.thumbnail)).await?;
+
+    // Append to the end of the gallery; the first image becomes the primary one
+    let insert = async {
+        let mut tx = pool.begin().await?;
+        require_vehicle_owner(&mut *tx, vehicle_id, user.id).await?;
+        let image_id = sqlx::query_scalar!(
+            r#"INSERT INTO vehicle_images (vehicle_id, position, is_primary, web_key, thumbnail_key, content_type, width, height)
+               SELECT $1, COALESCE(MAX(position) + 1, 0), NOT COALESCE(BOOL_OR(is_primary), FALSE), $2, $3, $4, $5, $6
+               FROM vehicle_images WHERE vehicle_id = $1
+               RETURNING id"#,
+            vehicle_id,
+            keys[0],
+            keys[1],
+            DERIVATIVE_CONTENT_TYPE,
+            processed.width as i32,
+            processed.height as i32
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+        tx.commit().await?;
+        Ok::<i32, ApiError>(image_id)
+    };
+
+    let image_id = match insert.await {
+        Ok(image_id) => image_id,
+        Err(err) => {
+            remove_files(storage.as_ref(), &keys).await;
+            return Err(err);
+        }
+    };
+
+    let image = fetch_images(&pool, vehicle_id)
+        .await?
+        .into_iter()
+        .find(|image| image.id == image_id)
+        .ok_or_else(|| ApiError::new(ErrorCode::ImageNotFound, "Image not found"))?;
+    Ok(HttpResponse::Ok().json(image))
+}
+
+pub async fn list_vehicle_images(
+    pool: web::Data<PgPool>,
+    path: web::Path<i32>,
+) -> Result<HttpResponse, ApiError> {
+    let vehicle_id = *path;
+    sqlx::query_scalar!("SELECT id FROM vehicles WHERE id = $1 AND deleted_at IS NULL", vehicle_id)
+        .fetch_optional(pool.as_ref())
+        .await?
+        .ok_or_else(|| ApiError::new(ErrorCode::VehicleNotFound, "Vehicle not found"))?;
+
+    let images = fetch_images(&pool, vehicle_id).await?;
+    Ok(HttpResponse::Ok().json(images))
+}
+
+pub async fn reorder_vehicle_images(
+    pool: web::Data<PgPool>,
+    redis_client: web::Data<redis::Client>,
+    req: HttpRequest,
+    path: web::Path<i32>,
+    form: web::Json<ReorderImages>,
+) -> Result<HttpResponse, ApiError> {
+    let user = require_user(&req, &pool, &redis_client).await?;
+    let vehicle_id = *path;
+
+    let mut tx = pool.begin().await?;
+    require_vehicle_owner(&mut *tx, vehicle_id, user.id).await?;
+
+    let current = sqlx::query_scalar!(
+        "SELECT id FROM vehicle_images WHERE vehicle_id = $1 ORDER BY id",
+        vehicle_id
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+
+    let mut requested = form.image_ids.clone();
+    requested.sort_unstable();
+    if requested != current {
+        return Err(ApiError::validation(vec![FieldError {
+            field: "image_ids".to_string(),
+            message: "must list every image of the vehicle exactly once".to_string(),
+        }]));
+    }
+
+    sqlx::query!(
+        "UPDATE vehicle_images v SET position = o.ord - 1
+         FROM UNNEST($2::INT[]) WITH ORDINALITY AS o(id, ord)
+         WHERE v.id = o.id AND v.vehicle_id = $1",
+        vehicle_id,
+        &form.image_ids
+    )
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+
+    let images = fetch_images(&pool, vehicle_id).await?;
+    Ok(HttpResponse::Ok().json(images))
+}
+
+pub async fn set_primary_image(
+    pool: web::Data<PgPool>,
+    redis_client: web::Data<redis::Client>,
+    req: HttpRequest,
+    path: web::Path<(i32, i32)>,
+) -> Result<HttpResponse, ApiError> {
+    let user = require_user(&req, &pool, &redis_client).await?;
+    let (vehicle_id, image_id) = path.into_inner();
+
+    let mut tx = pool.begin().await?;
+    require_vehicle_owner(&mut *tx, vehicle_id, user.id).await?;
+
+    // Clear the old flag first; the one-primary index is checked per statement row
+    sqlx::query!(
+        "UPDATE vehicle_images SET is_primary = FALSE WHERE vehicle_id = $1 AND is_primary AND id <> $2",
+        vehicle_id,
+        image_id
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    let updated = sqlx::query!(
+        "UPDATE vehicle_images SET is_primary = TRUE WHERE vehicle_id = $1 AND id = $2",
+        vehicle_id,
+        image_id
+    )
+    .execute(&mut *tx)
+    .await?;
+    if updated.rows_affected() == 0 {
+        return Err(ApiError::new(ErrorCode::ImageNotFound, "Image not found"));
+    }
+    tx.commit().await?;
+
+    let images = fetch_images(&pool, vehicle_id).await?;
+    Ok(HttpResponse::Ok().json(images))
+}
+
+pub async fn delete_vehicle_image(
+    pool: web::Data<PgPool>,
+    redis_client: web::Data<redis::Client>,
+    storage: web::Data<dyn Storage>,
+    req: HttpRequest,
+    path: web::Path<(i32, i32)>,
+) -> Result<HttpResponse, ApiError> {
+    let user = require_user(&req, &pool, &redis_client).await?;
+    let (vehicle_id, image_id) = path.into_inner();
+
+    let mut tx = pool.begin().await?;
+    require_vehicle_owner(&mut *tx, vehicle_id, user.id).await?;
+
+    let deleted = sqlx::query!(
+        "DELETE FROM vehicle_images WHERE vehicle_id = $1 AND id = $2
+         RETURNING web_key, thumbnail_key, is_primary",
+        vehicle_id,
+        image_id
+    )
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| ApiError::new(ErrorCode::ImageNotFound, "Image not found"))?;
+
+    // Promote the next image in order so a gallery always has a primary image
+    if deleted.is_primary {
+        sqlx::query!(
+            "UPDATE vehicle_images SET is_primary = TRUE
+             WHERE id = (SELECT id FROM vehicle_images WHERE vehicle_id = $1 ORDER BY position, id LIMIT 1)",
+            vehicle_id
+        )
+        .execute(&mut *tx)
+        .await?;
+    }
+    tx.commit().await?;
+
+    remove_files(storage.as_ref(), &[deleted.web_key, deleted.thumbnail_key]).await;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Serve a stored derivative; keys are unique per upload so responses cache forever.
+pub async fn serve_media(
+    storage: web::Data<dyn Storage>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    let not_found = || ApiError::new(ErrorCode::ImageNotFound, "Image not found");
+    if !path.starts_with(IMAGE_KEY_PREFIX) {
+        return Err(not_found());
+    }
+    let data = storage.get(&path).await?.ok_or_else(not_found)?;
+
+    Ok(HttpResponse::Ok()
+        .content_type(DERIVATIVE_CONTENT_TYPE)
+        .insert_header(("Cache-Control", "public, max-age=31536000, immutable"))
+        .body(data))
+}
@@ -0,0 +1,207 @@
+use sqlx::PgPool;
+use actix_web::{http::header, web, HttpResponse, HttpRequest};
+use bytes::Bytes;
+use futures_util::{stream, StreamExt, TryStreamExt};
+use crate::errors::{ApiError, ErrorCode, FieldError, Validator};
+use crate::models::{
+    CreateVehicle, ImportOptions, ImportReport, Vehicle, VehicleImportRow, MAX_IMPORT_BYTES, MAX_IMPORT_ROWS,
+};
+use crate::routes::vehicle::insert_vehicle;
+use crate::session::require_user;
+
+/// Export columns, in the order of `Vehicle`; the same file can be imported again.
+const EXPORT_COLUMNS: &[&str] = &[
+    "id", "name", "description", "starting_price", "make", "model", "year", "vin", "mileage", "mileage_unit",
+    "fuel_type", "transmission", "body_style", "colour", "engine", "matching_numbers", "registration_country",
+    "condition_grade", "category_id",
+];
+
+#[derive(Clone, Copy, PartialEq)]
+enum ImportFormat {
+    Csv,
+    JsonLines,
+}
+
+fn import_format(req: &HttpRequest, options: &ImportOptions) -> Result<ImportFormat, ApiError> {
+    let content_type = req
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.split(';').next())
+        .map(|value| value.trim().to_ascii_lowercase());
+
+    let format = match options.format.as_deref().or(content_type.as_deref()) {
+        Some("csv" | "text/csv") => Some(ImportFormat::Csv),
+        Some("jsonl" | "application/jsonl" | "application/x-ndjson" | "application/x-jsonlines") => {
+            Some(ImportFormat::JsonLines)
+        }
+        _ => None,
+    };
+
+    format.ok_or_else(|| {
+        ApiError::validation(vec![FieldError {
+            field: "format".to_string(),
+            message: "must be csv or jsonl, via ?format= or the Content-Type header".to_string(),
+        }])
+    })
+}
+
+async fn read_upload(mut payload: web::Payload) -> Result<Vec<u8>, ApiError> {
+    let mut data = Vec::new();
+    while let Some(chunk) = payload
+        .try_next()
+        .await
+        .map_err(|_| ApiError::new(ErrorCode::InvalidPayload, "Malformed request body"))?
+    {
+        if data.len() + chunk.len() > MAX_IMPORT_BYTES {
+            Validator::default().check(false, "file", "is too large").finish()?;
+        }
+        data.extend_from_slice(&chunk);
+    }
+    Ok(data)
+}
+
+/// Parse every row, keeping parse failures as messages so all of them can be reported at once.
+fn parse_rows(data: &[u8], format: ImportFormat) -> Vec<Result<CreateVehicle, String>> {
+    match format {
+        ImportFormat::Csv => csv::ReaderBuilder::new()
+            .trim(csv::Trim::All)
+            .from_reader(data)
+            .deserialize::<VehicleImportRow>()
+            .map(|row| row.map(CreateVehicle::from).map_err(|err| err.to_string()))
+            .collect(),
+        ImportFormat::JsonLines => String::from_utf8_lossy(data)
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| serde_json::from_str::<CreateVehicle>(line).map_err(|err| err.to_string()))
+            .collect(),
+    }
+}
+
+/// Create many vehicles from a CSV or JSON Lines upload, all or nothing.
+///
+/// Row errors are reported as `rows[<index>].<field>`, counting data rows from 0.
+pub async fn import_vehicles(
+    pool: web::Data<PgPool>,
+    redis_client: web::Data<redis::Client>,
+    req: HttpRequest,
+    options: web::Query<ImportOptions>,
+    payload: web::Payload,
+) -> Result<HttpResponse, ApiError> {
+    let user = require_user(&req, &pool, &redis_client).await?;
+    let format = import_format(&req, &options)?;
+    let rows = parse_rows(&read_upload(payload).await?, format);
+    Validator::default()
+        .check(!rows.is_empty(), "file", "contains no rows")
+        .check(rows.len() <= MAX_IMPORT_ROWS, "file", "has too many rows")
+        .finish()?;
+
+    // Rows are inserted as they validate so later rows see earlier VINs; any error rolls everything back
+    let mut tx = pool.begin().await?;
+    let mut errors = Vec::new();
+    let mut vehicles = Vec::with_capacity(rows.len());
+    for (index, row) in rows.into_iter().enumerate() {
+        let form = match row {
+            Ok(form) => form,
+            Err(message) => {
+                errors.push(FieldError { field: format!("rows[{}]", index), message });
+                continue;
+            }
+        };
+
+        let row_errors = |err: ApiError| {
+            err.details.into_iter().map(move |detail| FieldError {
+                field: format!("rows[{}].{}", index, detail.field),
+                message: detail.message,
+            })
+        };
+        if let Err(err) = form.validate() {
+            errors.extend(row_errors(err));
+            continue;
+        }
+
+        match insert_vehicle(&mut tx, user.id, &form).await {
+            Ok(vehicle) => vehicles.push(vehicle),
+            Err(err) if err.code == ErrorCode::DuplicateVin => errors.push(FieldError {
+                field: format!("rows[{}].vin", index),
+                message: "is already listed or repeats an earlier row".to_string(),
+            }),
+            Err(err) if err.code == ErrorCode::ValidationFailed => errors.extend(row_errors(err)),
+            Err(err) => return Err(err),
+        }
+    }
+
+    if !errors.is_empty() {
+        return Err(ApiError::validation(errors));
+    }
+
+    let row_count = vehicles.len();
+    if options.dry_run {
+        tx.rollback().await?;
+        vehicles.clear();
+    } else {
+        tx.commit().await?;
+    }
+
+    Ok(HttpResponse::Ok().json(ImportReport { dry_run: options.dry_run, row_count, vehicles }))
+}
+
+fn csv_chunk<T: serde::Serialize>(record: T) -> Result<Bytes, std::io::Error> {
+    let mut writer = csv::WriterBuilder::new().has_headers(false).from_writer(Vec::new());
+    writer.serialize(record).map_err(std::io::Error::other)?;
+    writer.into_inner().map(Bytes::from).map_err(|err| std::io::Error::other(err.to_string()))
+}
+
+/// Stream the signed-in user's inventory as CSV, one row per vehicle.
+pub async fn export_vehicles(
+    pool: web::Data<PgPool>,
+    redis_client: web::Data<redis::Client>,
+    req: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    let user = require_user(&req, &pool, &redis_client).await?;
+    let pool = pool.get_ref().clone();
+
+    // Rows are written as the database produces them, without holding the inventory in memory
+    let (sender, receiver) = tokio::sync::mpsc::channel::<Result<Bytes, std::io::Error>>(16);
+    actix_web::rt::spawn(async move {
+        if sender.send(csv_chunk(EXPORT_COLUMNS)).await.is_err() {
+            return;
+        }
+
+        let mut rows = sqlx::query_as!(
+            Vehicle,
+            "SELECT id, name, description, starting_price, make, model, year, vin, mileage, mileage_unit,
+                    fuel_type, transmission, body_style, colour, engine, matching_numbers, registration_country,
+                    condition_grade, category_id
+             FROM vehicles WHERE owner_id = $1 AND deleted_at IS NULL ORDER BY id",
+            user.id
+        )
+        .fetch(&pool);
+
+        while let Some(row) = rows.next().await {
+            let chunk = row
+                .map_err(|err| {
+                    eprintln!("Database error: {:?}", err);
+                    std::io::Error::other("Database query error")
+                })
+                .and_then(csv_chunk);
+            let failed = chunk.is_err();
+            // Stop once the client has gone away or the export broke
+            if sender.send(chunk).await.is_err() || failed {
+                return;
+            }
+        }
+    });
+
+    let body = stream::unfold(receiver, |mut receiver| async move {
+        receiver.recv().await.map(|chunk| (chunk, receiver))
+    });
+
+    Ok(HttpResponse::Ok()
+        .content_type("text/csv; charset=utf-8")
+        .insert_header((
+            "Content-Disposition",
+            format!("attachment; filename=\"{}-vehicles.csv\"", user.username),
+        ))
+        .streaming(body))
+}
@@ -1,3 +1,16 @@
 pub mod user;
 pub mod vehicle;
-pub mod auction;
\ No newline at end of file
+pub mod auction;
+pub mod rating;
+pub mod image;
+pub mod document;
+pub mod condition;
+pub mod search;
+pub mod saved_search;
+pub mod notification;
+pub mod watchlist;
+pub mod history;
+pub mod inventory;
+pub mod category;
+pub mod cancellation;
+pub mod bid;
@@ -0,0 +1,63 @@
+use sqlx::PgPool;
+use actix_web::{web, HttpResponse, HttpRequest};
+use crate::errors::{ApiError, ErrorCode};
+use crate::models::{Notification, NotificationFilter};
+use crate::session::require_user;
+
+/// Notifications shown to a user, newest first.
+pub const NOTIFICATION_PAGE_SIZE: i64 = 100;
+
+/// The user's notifications, newest first; `limit` of `None` returns all of them.
+pub async fn fetch_notifications(
+    pool: &PgPool,
+    user_id: i32,
+    unread_only: bool,
+    limit: Option<i64>,
+) -> Result<Vec<Notification>, ApiError> {
+    let notifications = sqlx::query_as!(
+        Notification,
+        "SELECT id, kind, message, payload, created_at, read_at
+         FROM notifications
+         WHERE user_id = $1 AND (NOT $2 OR read_at IS NULL)
+         ORDER BY created_at DESC, id DESC
+         LIMIT $3",
+        user_id,
+        unread_only,
+        limit
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(notifications)
+}
+
+pub async fn list_notifications(
+    pool: web::Data<PgPool>,
+    redis_client: web::Data<redis::Client>,
+    req: HttpRequest,
+    filter: web::Query<NotificationFilter>,
+) -> Result<HttpResponse, ApiError> {
+    let user = require_user(&req, &pool, &redis_client).await?;
+    let notifications = fetch_notifications(&pool, user.id, filter.unread_only, Some(NOTIFICATION_PAGE_SIZE)).await?;
+    Ok(HttpResponse::Ok().json(notifications))
+}
+
+pub async fn mark_notification_read(
+    pool: web::Data<PgPool>,
+    redis_client: web::Data<redis::Client>,
+    req: HttpRequest,
+    path: web::Path<i32>,
+) -> Result<HttpResponse, ApiError> {
+    let user = require_user(&req, &pool, &redis_client).await?;
+
+    sqlx::query_scalar!(
+        "UPDATE notifications SET read_at = COALESCE(read_at, NOW()) WHERE user_id = $1 AND id = $2 RETURNING id",
+        user.id,
+        *path
+    )
+    .fetch_optional(pool.as_ref())
+    .await?
+    .ok_or_else(|| ApiError::new(ErrorCode::NotificationNotFound, "Notification not found"))?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
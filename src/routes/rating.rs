@@ -0,0 +1,92 @@
+use sqlx::PgPool;
+use actix_web::{web, HttpResponse, HttpRequest};
+use crate::errors::{ApiError, ErrorCode};
+use crate::models::{RateAuction, Rating, Reputation};
+use crate::session::require_user;
+use chrono::{Duration, Utc};
+
+/// How long after settlement the buyer and seller may rate each other.
+pub const RATING_WINDOW_DAYS: i64 = 30;
+
+/// Aggregate all ratings a user has received, overall and per role.
+pub async fn fetch_reputation(pool: &PgPool, user_id: i32) -> Result<Reputation, ApiError> {
+    let reputation = sqlx::query_as!(
+        Reputation,
+        r#"SELECT
+            AVG(score)::FLOAT8 as score,
+            COUNT(*) as "rating_count!",
+            (AVG(score) FILTER (WHERE ratee_role = 'seller'))::FLOAT8 as seller_score,
+            (AVG(score) FILTER (WHERE ratee_role = 'buyer'))::FLOAT8 as buyer_score
+           FROM ratings WHERE ratee_id = $1"#,
+        user_id
+    )
+    .fetch_one(pool)
+    .await?;
+
+    Ok(reputation)
+}
+
+pub async fn rate_auction(
+    pool: web::Data<PgPool>,
+    redis_client: web::Data<redis::Client>,
+    req: HttpRequest,
+    path: web::Path<i32>,
+    form: web::Json<RateAuction>,
+) -> Result<HttpResponse, ApiError> {
+    let user = require_user(&req, &pool, &redis_client).await?;
+    form.validate()?;
+    let auction_id = *path;
+
+    let auction = sqlx::query!(
+        "SELECT seller_id, winner_id, closed_at FROM auctions WHERE id = $1 AND closed = TRUE",
+        auction_id
+    )
+    .fetch_optional(pool.as_ref())
+    .await?
+    .ok_or_else(|| ApiError::new(ErrorCode::AuctionNotFound, "Settled auction not found"))?;
+
+    // The seller rates the buyer and the buyer rates the seller
+    let (ratee_id, ratee_role) = match (auction.seller_id, auction.winner_id) {
+        (Some(seller), Some(winner)) if seller == user.id => (winner, "buyer"),
+        (Some(seller), Some(winner)) if winner == user.id => (seller, "seller"),
+        _ => return Err(ApiError::new(ErrorCode::NotAuctionParty, "Only the buyer and seller can rate this auction")),
+    };
+
+    let window_open = auction
+        .closed_at
+        .is_some_and(|closed_at| Utc::now().naive_utc() <= closed_at + Duration::days(RATING_WINDOW_DAYS));
+    if !window_open {
+        return Err(ApiError::new(ErrorCode::RatingWindowClosed, "The rating window for this auction has closed"));
+    }
+
+    let rating_id = sqlx::query_scalar!(
+        "INSERT INTO ratings (auction_id, rater_id, ratee_id, ratee_role, score, comment)
+         VALUES ($1, $2, $3, $4, $5, $6)
+         ON CONFLICT (auction_id, rater_id) DO NOTHING
+         RETURNING id",
+        auction_id,
+        user.id,
+        ratee_id,
+        ratee_role,
+        form.score,
+        form.comment
+    )
+    .fetch_optional(pool.as_ref())
+    .await?
+    .ok_or_else(|| ApiError::new(ErrorCode::AlreadyRated, "You have already rated this auction"))?;
+
+    let rating = sqlx::query_as!(
+        Rating,
+        "SELECT r.id, r.auction_id, rater.username as rater_username, ratee.username as ratee_username,
+                r.ratee_role, r.score, r.comment, r.created_at
+         FROM ratings r
+         INNER JOIN users rater ON rater.id = r.rater_id
+         INNER JOIN users ratee ON ratee.id = r.ratee_id
+         WHERE r.id = $1",
+        rating_id
+    )
+    .fetch_one(pool.as_ref())
+    .await?;
+
+    Ok(HttpResponse::Ok().json(rating))
+}
@@ -0,0 +1,197 @@
+use sqlx::PgPool;
+use actix_web::{web, HttpResponse, HttpRequest};
+use crate::errors::{ApiError, ErrorCode};
+use crate::models::{CreateSavedSearch, SavedSearch, SearchCriteria, UpdateSavedSearch, MAX_SAVED_SEARCHES};
+use crate::session::require_user;
+
+/// Saved searches of a user, oldest first; `search_id` narrows it to one.
+pub async fn fetch_saved_searches(
+    pool: &PgPool,
+    user_id: i32,
+    search_id: Option<i32>,
+) -> Result<Vec<SavedSearch>, ApiError> {
+    let rows = sqlx::query!(
+        "SELECT id, name, frequency, q, make, model, year_min, year_max, price_min::FLOAT8 as price_min,
+                price_max::FLOAT8 as price_max, body_style, fuel_type, transmission, created_at
+         FROM saved_searches
+         WHERE user_id = $1 AND ($2::INT IS NULL OR id = $2)
+         ORDER BY created_at, id",
+        user_id,
+        search_id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| SavedSearch {
+            id: row.id,
+            name: row.name,
+            frequency: row.frequency,
+            criteria: SearchCriteria {
+                q: row.q,
+                make: row.make,
+                model: row.model,
+                year_min: row.year_min,
+                year_max: row.year_max,
+                price_min: row.price_min,
+                price_max: row.price_max,
+                body_style: row.body_style,
+                fuel_type: row.fuel_type,
+                transmission: row.transmission,
+            },
+            created_at: row.created_at,
+        })
+        .collect())
+}
+
+async fn fetch_saved_search(pool: &PgPool, user_id: i32, search_id: i32) -> Result<SavedSearch, ApiError> {
+    fetch_saved_searches(pool, user_id, Some(search_id))
+        .await?
+        .pop()
+        .ok_or_else(|| ApiError::new(ErrorCode::SavedSearchNotFound, "Saved search not found"))
+}
+
+/// Blank text criteria would match everything; store them as absent.
+fn normalize(criteria: &SearchCriteria) -> SearchCriteria {
+    let text = |value: &Option<String>| {
+        value.as_deref().map(str::trim).filter(|value| !value.is_empty()).map(str::to_string)
+    };
+    SearchCriteria {
+        q: text(&criteria.q),
+        make: text(&criteria.make),
+        model: text(&criteria.model),
+        ..criteria.clone()
+    }
+}
+
+pub async fn create_saved_search(
+    pool: web::Data<PgPool>,
+    redis_client: web::Data<redis::Client>,
+    req: HttpRequest,
+    form: web::Json<CreateSavedSearch>,
+) -> Result<HttpResponse, ApiError> {
+    let user = require_user(&req, &pool, &redis_client).await?;
+    form.validate()?;
+    let criteria = normalize(&form.criteria);
+
+    let mut tx = pool.begin().await?;
+    sqlx::query!("SELECT id FROM users WHERE id = $1 FOR UPDATE", user.id)
+        .fetch_one(&mut *tx)
+        .await?;
+
+    let count = sqlx::query_scalar!(
+        r#"SELECT COUNT(*) as "count!" FROM saved_searches WHERE user_id = $1"#,
+        user.id
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+
+    if count >= MAX_SAVED_SEARCHES {
+        return Err(ApiError::new(
+            ErrorCode::SavedSearchLimitReached,
+            format!("You can keep at most {} saved searches", MAX_SAVED_SEARCHES),
+        ));
+    }
+
+    let search_id = sqlx::query_scalar!(
+        "INSERT INTO saved_searches (user_id, name, frequency, q, make, model, year_min, year_max, price_min, price_max,
+                body_style, fuel_type, transmission)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9::FLOAT8::NUMERIC, $10::FLOAT8::NUMERIC, $11, $12, $13)
+         RETURNING id",
+        user.id,
+        form.name.trim(),
+        form.frequency,
+        criteria.q,
+        criteria.make,
+        criteria.model,
+        criteria.year_min,
+        criteria.year_max,
+        criteria.price_min,
+        criteria.price_max,
+        criteria.body_style,
+        criteria.fuel_type,
+        criteria.transmission
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    let search = fetch_saved_search(&pool, user.id, search_id).await?;
+    Ok(HttpResponse::Ok().json(search))
+}
+
+pub async fn list_saved_searches(
+    pool: web::Data<PgPool>,
+    redis_client: web::Data<redis::Client>,
+    req: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    let user = require_user(&req, &pool, &redis_client).await?;
+    let searches = fetch_saved_searches(&pool, user.id, None).await?;
+    Ok(HttpResponse::Ok().json(searches))
+}
+
+pub async fn update_saved_search(
+    pool: web::Data<PgPool>,
+    redis_client: web::Data<redis::Client>,
+    req: HttpRequest,
+    path: web::Path<i32>,
+    form: web::Json<UpdateSavedSearch>,
+) -> Result<HttpResponse, ApiError> {
+    let user = require_user(&req, &pool, &redis_client).await?;
+    form.validate()?;
+    let search_id = *path;
+
+    let current = fetch_saved_search(&pool, user.id, search_id).await?;
+    let name = form.name.as_deref().map(str::trim).unwrap_or(&current.name);
+    let frequency = form.frequency.as_deref().unwrap_or(&current.frequency);
+    let criteria = form.criteria.as_ref().map(normalize).unwrap_or(current.criteria);
+
+    sqlx::query!(
+        "UPDATE saved_searches
+         SET name = $3, frequency = $4, q = $5, make = $6, model = $7, year_min = $8, year_max = $9,
+             price_min = $10::FLOAT8::NUMERIC, price_max = $11::FLOAT8::NUMERIC, body_style = $12, fuel_type = $13,
+             transmission = $14
+         WHERE user_id = $1 AND id = $2",
+        user.id,
+        search_id,
+        name,
+        frequency,
+        criteria.q,
+        criteria.make,
+        criteria.model,
+        criteria.year_min,
+        criteria.year_max,
+        criteria.price_min,
+        criteria.price_max,
+        criteria.body_style,
+        criteria.fuel_type,
+        criteria.transmission
+    )
+    .execute(pool.as_ref())
+    .await?;
+
+    let search = fetch_saved_search(&pool, user.id, search_id).await?;
+    Ok(HttpResponse::Ok().json(search))
+}
+
+pub async fn delete_saved_search(
+    pool: web::Data<PgPool>,
+    redis_client: web::Data<redis::Client>,
+    req: HttpRequest,
+    path: web::Path<i32>,
+) -> Result<HttpResponse, ApiError> {
+    let user = require_user(&req, &pool, &redis_client).await?;
+
+    sqlx::query_scalar!(
+        "DELETE FROM saved_searches WHERE user_id = $1 AND id = $2 RETURNING id",
+        user.id,
+        *path
+    )
+    .fetch_optional(pool.as_ref())
+    .await?
+    .ok_or_else(|| ApiError::new(ErrorCode::SavedSearchNotFound, "Saved search not found"))?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
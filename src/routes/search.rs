@@ -0,0 +1,160 @@
+use sqlx::{PgPool, Postgres, QueryBuilder};
+use actix_web::{web, HttpResponse};
+use crate::errors::ApiError;
+use crate::models::{FacetCount, SearchFacets, SearchHit, SearchQuery, SearchResults, Vehicle, DEFAULT_PAGE_SIZE};
+use crate::routes::category::{category_counts, push_category_filter};
+use crate::routes::vehicle::{NOT_SOLD, VEHICLE_COLUMNS};
+
+/// Minimum `word_similarity` for the fuzzy fallback.
+const FUZZY_THRESHOLD: f64 = 0.3;
+
+/// User text is HTML-escaped before `ts_headline` adds `<mark>` tags.
+const ESCAPED_DESCRIPTION: &str = "replace(replace(replace(v.description, '&', '&amp;'), '<', '&lt;'), '>', '&gt;')";
+const ESCAPED_NAME: &str = "replace(replace(replace(v.name, '&', '&amp;'), '<', '&lt;'), '>', '&gt;')";
+
+const PRICE_BUCKET: &str = "CASE
+    WHEN v.starting_price < 10000 THEN 'under_10k'
+    WHEN v.starting_price < 25000 THEN '10k_25k'
+    WHEN v.starting_price < 50000 THEN '25k_50k'
+    WHEN v.starting_price < 100000 THEN '50k_100k'
+    WHEN v.starting_price < 250000 THEN '100k_250k'
+    ELSE '250k_plus' END";
+
+#[derive(sqlx::FromRow)]
+struct SearchRow {
+    #[sqlx(flatten)]
+    vehicle: Vehicle,
+    rank: f64,
+    name_highlight: String,
+    description_highlight: String,
+}
+
+#[derive(sqlx::FromRow)]
+struct FacetRow {
+    value: String,
+    count: i64,
+}
+
+/// Matching vehicles: full-text by default, trigram word similarity when `fuzzy`.
+fn push_matches(query: &mut QueryBuilder<'_, Postgres>, search: &SearchQuery, fuzzy: bool) {
+    query.push(" FROM vehicles v WHERE v.deleted_at IS NULL AND ").push(NOT_SOLD);
+    if fuzzy {
+        query.push(" AND ").push_bind(search.q.to_lowercase()).push(" <% v.search_text");
+    } else {
+        query
+            .push(" AND v.search_vector @@ websearch_to_tsquery('english', ")
+            .push_bind(search.q.clone())
+            .push(")");
+    }
+
+    if let Some(make) = search.make.clone() {
+        query.push(" AND lower(v.make) = lower(").push_bind(make).push(")");
+    }
+    if let Some(decade) = search.decade {
+        query.push(" AND v.year BETWEEN ").push_bind(decade).push(" AND ").push_bind(decade + 9);
+    }
+    if let Some(price_min) = search.price_min {
+        query.push(" AND v.starting_price >= ").push_bind(price_min).push("::NUMERIC");
+    }
+    if let Some(price_max) = search.price_max {
+        query.push(" AND v.starting_price <= ").push_bind(price_max).push("::NUMERIC");
+    }
+    if let Some(category) = search.category.clone() {
+        push_category_filter(query, category);
+    }
+}
+
+async fn facet(
+    tx: &mut sqlx::PgConnection,
+    search: &SearchQuery,
+    fuzzy: bool,
+    expression: &str,
+) -> Result<Vec<FacetCount>, ApiError> {
+    let mut query = QueryBuilder::new(format!("SELECT {} as value, COUNT(*) as count", expression));
+    push_matches(&mut query, search, fuzzy);
+    query.push(format!(" AND {} IS NOT NULL GROUP BY 1 ORDER BY 2 DESC, 1", expression));
+
+    let rows: Vec<FacetRow> = query.build_query_as().fetch_all(tx).await?;
+    Ok(rows.into_iter().map(|row| FacetCount { value: row.value, count: row.count }).collect())
+}
+
+pub async fn search_vehicles(
+    pool: web::Data<PgPool>,
+    search: web::Query<SearchQuery>,
+) -> Result<HttpResponse, ApiError> {
+    search.validate()?;
+    let limit = search.limit.unwrap_or(DEFAULT_PAGE_SIZE);
+    let offset = search.offset.unwrap_or(0);
+
+    let mut tx = pool.begin().await?;
+    sqlx::query(&format!("SET LOCAL pg_trgm.word_similarity_threshold = {}", FUZZY_THRESHOLD))
+        .execute(&mut *tx)
+        .await?;
+
+    let count = |fuzzy: bool| {
+        let mut query = QueryBuilder::new("SELECT COUNT(*)");
+        push_matches(&mut query, &search, fuzzy);
+        query
+    };
+
+    // Fall back to typo-tolerant matching only when nothing matches exactly
+    let mut fuzzy = false;
+    let mut total_count: i64 = count(false).build_query_scalar().fetch_one(&mut *tx).await?;
+    if total_count == 0 {
+        fuzzy = true;
+        total_count = count(true).build_query_scalar().fetch_one(&mut *tx).await?;
+    }
+
+    // Highlight any query word, not only full matches
+    let highlight_query = "replace(plainto_tsquery('english', ";
+    let mut query = QueryBuilder::new(format!("SELECT {}, ", VEHICLE_COLUMNS));
+    if fuzzy {
+        query.push("word_similarity(").push_bind(search.q.to_lowercase()).push(", v.search_text)::FLOAT8");
+    } else {
+        query
+            .push("ts_rank_cd(v.search_vector, websearch_to_tsquery('english', ")
+            .push_bind(search.q.clone())
+            .push("))::FLOAT8");
+    }
+    query
+        .push(format!(" as rank, ts_headline('english', {}, {}", ESCAPED_NAME, highlight_query))
+        .push_bind(search.q.clone())
+        .push(")::TEXT, '&', '|')::tsquery, 'HighlightAll=true, StartSel=<mark>, StopSel=</mark>') as name_highlight")
+        .push(format!(", ts_headline('english', {}, {}", ESCAPED_DESCRIPTION, highlight_query))
+        .push_bind(search.q.clone())
+        .push(
+            ")::TEXT, '&', '|')::tsquery, 'MaxFragments=2, MaxWords=30, MinWords=10, StartSel=<mark>, StopSel=</mark>')
+             as description_highlight",
+        );
+    push_matches(&mut query, &search, fuzzy);
+    query
+        .push(" ORDER BY rank DESC, v.id DESC LIMIT ")
+        .push_bind(limit)
+        .push(" OFFSET ")
+        .push_bind(offset);
+
+    let rows: Vec<SearchRow> = query.build_query_as().fetch_all(&mut *tx).await?;
+
+    let facets = SearchFacets {
+        makes: facet(&mut tx, &search, fuzzy, "v.make").await?,
+        decades: facet(&mut tx, &search, fuzzy, "((v.year / 10) * 10)::TEXT || 's'").await?,
+        price_buckets: facet(&mut tx, &search, fuzzy, PRICE_BUCKET).await?,
+        categories: category_counts(&mut *tx, |query| push_matches(query, &search, fuzzy)).await?,
+    };
+    tx.commit().await?;
+
+    Ok(HttpResponse::Ok().json(SearchResults {
+        items: rows
+            .into_iter()
+            .map(|row| SearchHit {
+                vehicle: row.vehicle,
+                rank: row.rank,
+                name_highlight: row.name_highlight,
+                description_highlight: row.description_highlight,
+            })
+            .collect(),
+        total_count,
+        fuzzy,
+        facets,
+    }))
+}
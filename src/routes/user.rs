@@ -1,99 +1,463 @@
 use sqlx::PgPool;
-use actix_web::{web, Responder, HttpResponse};
-use crate::models::{UserRegister, UserLogin};
+use actix_web::{web, HttpResponse, HttpRequest};
+use crate::auction_state::AuctionStatus;
+use crate::errors::{ApiError, ErrorCode};
+use crate::models::{
+    UserRegister, UserLogin, UserSummary, LoginResponse, UserProfile, UpdateProfile, PublicProfile,
+    UserExport, ChangeUsername, Vehicle, AuctionSummary, AuctionType, BidReceipt, RetractedBid, SealedBid, Rating,
+    DELETED_USER_PREFIX,
+};
+use crate::routes::image::remove_files;
+use crate::routes::notification::fetch_notifications;
+use crate::routes::rating::fetch_reputation;
+use crate::routes::saved_search::fetch_saved_searches;
+use crate::routes::watchlist::fetch_watchlist;
+use crate::sealed::open_amount;
+use crate::session::{create_session, require_user, revoke_sessions, SESSION_TTL_SECONDS};
+use crate::storage::Storage;
 use argon2::{Argon2, PasswordHash, PasswordVerifier, password_hash::SaltString, PasswordHasher};
-use uuid::Uuid; // To generate unique session codes
-use redis::AsyncCommands;
+use chrono::{Duration, Utc};
 
 
 pub async fn user_register(
     pool: web::Data<PgPool>,
     form: web::Json<UserRegister>,
-) -> impl Responder {
+) -> Result<HttpResponse, ApiError> {
+    form.validate()?;
+
     // Check if the username already exists
-    let username_exists: (bool,) = match sqlx::query_as::<_, (bool,)>(
+    let username_exists: (bool,) = sqlx::query_as::<_, (bool,)>(
         "SELECT EXISTS(SELECT 1 FROM users WHERE username = $1)"
     )
     .bind(&form.username)
     .fetch_one(pool.as_ref())
-    .await
-    {
-        Ok(result) => result,
-        Err(_) => return HttpResponse::InternalServerError().body("Database query error"),
-    };
+    .await?;
 
     if username_exists.0 {
         // Username already exists
-        return HttpResponse::BadRequest().body("Username already taken");
+        return Err(ApiError::new(ErrorCode::UsernameTaken, "Username already taken"));
     }
 
     // Hash the password using Argon2
     let salt = SaltString::generate(&mut rand::thread_rng());
     let hashed_password = Argon2::default()
         .hash_password(form.password.as_bytes(), &salt)
-        .unwrap()
+        .map_err(|_| ApiError::new(ErrorCode::InternalError, "Failed to hash password"))?
         .to_string();
 
     // Insert the new user into the database
-    if let Err(_) = sqlx::query("INSERT INTO users (username, password) VALUES ($1, $2)")
-        .bind(&form.username)
-        .bind(hashed_password)
-        .execute(pool.as_ref())
-        .await
-    {
-        return HttpResponse::InternalServerError().body("Failed to register user");
-    }
+    let user = sqlx::query_as!(
+        UserSummary,
+        "INSERT INTO users (username, password) VALUES ($1, $2) RETURNING id, username, created_at",
+        form.username,
+        hashed_password
+    )
+    .fetch_one(pool.as_ref())
+    .await?;
 
-    HttpResponse::Ok().body("User Registered")
+    Ok(HttpResponse::Ok().json(user))
 }
 
 pub async fn user_login(
     pool: web::Data<PgPool>,
     redis_client: web::Data<redis::Client>,
     form: web::Json<UserLogin>,
-) -> impl Responder {
-    let user = sqlx::query!("SELECT password FROM users WHERE username = $1", form.username)
-        .fetch_one(pool.as_ref())
-        .await;
-
-    match user {
-        Ok(record) => {
-            match PasswordHash::new(&record.password) {
-                Ok(parsed_hash) => {
-                    if Argon2::default().verify_password(form.password.as_bytes(), &parsed_hash).is_ok() {
-                        // Generate a unique session code
-                        let session_code = Uuid::new_v4().to_string();
-
-                        // Save the session code in Redis with a time-to-live (TTL)
-                        let mut redis_conn = redis_client.get_multiplexed_async_connection().await.expect("Failed to connect to Redis");
-                        let _: () = redis_conn
-                            .set_ex(format!("session:{}", session_code), &form.username, 3600) // TTL = 3600 seconds (1 hour)
-                            .await
-                            .expect("Failed to save session in Redis");
-
-                        // Return the session code to the user
-                        HttpResponse::Ok().json(format!("Login successful. Session code: {}", session_code))
-                    } else {
-                        HttpResponse::Unauthorized().body("Invalid credentials")
-                    }
-                }
-                Err(_) => {
-                    eprintln!("Failed to parse password hash for user: {}", form.username);
-                    HttpResponse::Unauthorized().body("Invalid password hash")
-                }
-            }
-        }
-        Err(sqlx::Error::RowNotFound) => {
-            eprintln!("User not found: {}", form.username);
-            HttpResponse::Unauthorized().body("Invalid credentials")
-        }
-        Err(err) => {
-            eprintln!("Unexpected database error: {:?}", err);
-            HttpResponse::InternalServerError().body("Database query error")
-        }
+) -> Result<HttpResponse, ApiError> {
+    let record = sqlx::query!(
+        "SELECT id, username, password, created_at FROM users WHERE username = $1 AND deleted_at IS NULL",
+        form.username
+    )
+    .fetch_optional(pool.as_ref())
+    .await?
+    .ok_or_else(|| {
+        eprintln!("User not found: {}", form.username);
+        ApiError::new(ErrorCode::InvalidCredentials, "Invalid credentials")
+    })?;
+
+    let parsed_hash = PasswordHash::new(&record.password).map_err(|_| {
+        eprintln!("Failed to parse password hash for user: {}", form.username);
+        ApiError::new(ErrorCode::InvalidCredentials, "Invalid password hash")
+    })?;
+
+    if Argon2::default().verify_password(form.password.as_bytes(), &parsed_hash).is_err() {
+        return Err(ApiError::new(ErrorCode::InvalidCredentials, "Invalid credentials"));
+    }
+
+    // Generate a unique session code and save it in Redis with a time-to-live (TTL)
+    let session_code = create_session(&redis_client, record.id).await?;
+
+    Ok(HttpResponse::Ok().json(LoginResponse {
+        session_code,
+        expires_at: Utc::now() + Duration::seconds(SESSION_TTL_SECONDS as i64),
+        user: UserSummary {
+            id: record.id,
+            username: record.username,
+            created_at: record.created_at,
+        },
+    }))
+}
+
+async fn fetch_profile(pool: &PgPool, username: &str) -> Result<UserProfile, ApiError> {
+    sqlx::query_as!(
+        UserProfile,
+        "SELECT id, username, display_name, location, bio, avatar_url, is_dealer, created_at
+         FROM users WHERE username = $1 AND deleted_at IS NULL",
+        username
+    )
+    .fetch_optional(pool)
+    .await?
+    .ok_or_else(|| ApiError::new(ErrorCode::UserNotFound, "User not found"))
+}
+
+pub async fn get_my_profile(
+    pool: web::Data<PgPool>,
+    redis_client: web::Data<redis::Client>,
+    req: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    let user = require_user(&req, &pool, &redis_client).await?;
+    let profile = fetch_profile(&pool, &user.username).await?;
+
+    Ok(HttpResponse::Ok().json(profile))
+}
+
+pub async fn update_my_profile(
+    pool: web::Data<PgPool>,
+    redis_client: web::Data<redis::Client>,
+    req: HttpRequest,
+    form: web::Json<UpdateProfile>,
+) -> Result<HttpResponse, ApiError> {
+    let user = require_user(&req, &pool, &redis_client).await?;
+    form.validate()?;
+
+    // Absent fields keep their value, empty strings clear the column
+    let profile = sqlx::query_as!(
+        UserProfile,
+        "UPDATE users SET
+            display_name = CASE WHEN $2::TEXT IS NULL THEN display_name ELSE NULLIF($2, '') END,
+            location = CASE WHEN $3::TEXT IS NULL THEN location ELSE NULLIF($3, '') END,
+            bio = CASE WHEN $4::TEXT IS NULL THEN bio ELSE NULLIF($4, '') END,
+            avatar_url = CASE WHEN $5::TEXT IS NULL THEN avatar_url ELSE NULLIF($5, '') END,
+            is_dealer = COALESCE($6, is_dealer)
+         WHERE id = $1
+         RETURNING id, username, display_name, location, bio, avatar_url, is_dealer, created_at",
+        user.id,
+        form.display_name,
+        form.location,
+        form.bio,
+        form.avatar_url,
+        form.is_dealer
+    )
+    .fetch_one(pool.as_ref())
+    .await?;
+
+    Ok(HttpResponse::Ok().json(profile))
+}
+
+pub async fn get_public_profile(
+    pool: web::Data<PgPool>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    let profile = fetch_profile(&pool, &path).await?;
+
+    let vehicles = sqlx::query_as!(
+        Vehicle,
+        "SELECT id, name, description, starting_price, make, model, year, vin, mileage, mileage_unit,
+                fuel_type, transmission, body_style, colour, engine, matching_numbers, registration_country,
+                condition_grade, category_id
+         FROM vehicles WHERE owner_id = $1 AND deleted_at IS NULL ORDER BY id",
+        profile.id
+    )
+    .fetch_all(pool.as_ref())
+    .await?;
+
+    // End times are naive UTC, so the session time zone must not take part in the comparison
+    let active_auctions = sqlx::query_as!(
+        AuctionSummary,
+        r#"SELECT a.id, a.vehicle_id, a.starting_price, a.start_time, a.end_time,
+                  a.status as "status: AuctionStatus", a.closed, a.auction_type as "auction_type: AuctionType"
+           FROM auctions a INNER JOIN vehicles v ON v.id = a.vehicle_id
+           WHERE v.owner_id = $1 AND a.closed = FALSE AND a.status <> 'draft' AND a.end_time > $2
+           ORDER BY a.end_time"#,
+        profile.id,
+        Utc::now().naive_utc()
+    )
+    .fetch_all(pool.as_ref())
+    .await?;
+
+    let sales_count = sqlx::query_scalar!(
+        r#"SELECT COUNT(*) as "count!" FROM auctions WHERE seller_id = $1 AND status = 'sold'"#,
+        profile.id
+    )
+    .fetch_one(pool.as_ref())
+    .await?;
+
+    let reputation = fetch_reputation(&pool, profile.id).await?;
+
+    Ok(HttpResponse::Ok().json(PublicProfile {
+        profile,
+        vehicles,
+        active_auctions,
+        sales_count,
+        reputation,
+    }))
+}
+
+pub async fn export_my_data(
+    pool: web::Data<PgPool>,
+    redis_client: web::Data<redis::Client>,
+    req: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    let user = require_user(&req, &pool, &redis_client).await?;
+    let profile = fetch_profile(&pool, &user.username).await?;
+
+    let vehicles = sqlx::query_as!(
+        Vehicle,
+        "SELECT id, name, description, starting_price, make, model, year, vin, mileage, mileage_unit,
+                fuel_type, transmission, body_style, colour, engine, matching_numbers, registration_country,
+                condition_grade, category_id
+         FROM vehicles WHERE owner_id = $1 ORDER BY id",
+        user.id
+    )
+    .fetch_all(pool.as_ref())
+    .await?;
+
+    let auctions = sqlx::query_as!(
+        AuctionSummary,
+        r#"SELECT id, vehicle_id, starting_price, start_time, end_time, status as "status: AuctionStatus", closed,
+                  auction_type as "auction_type: AuctionType"
+           FROM auctions WHERE seller_id = $1 ORDER BY id"#,
+        user.id
+    )
+    .fetch_all(pool.as_ref())
+    .await?;
+
+    let bids = sqlx::query_as!(
+        BidReceipt,
+        "SELECT b.id, b.auction_id, u.username as bidder_username, b.bid_amount, b.created_at
+         FROM bids b INNER JOIN users u ON u.id = b.bidder_id
+         WHERE b.bidder_id = $1 ORDER BY b.id",
+        user.id
+    )
+    .fetch_all(pool.as_ref())
+    .await?;
+
+    // Retracted bids leave `bids`, but they were still placed by this user
+    let retracted_bids = sqlx::query_as!(
+        RetractedBid,
+        "SELECT bid_id, auction_id, bid_amount, reason, placed_at, retracted_at
+         FROM bid_retractions WHERE bidder_id = $1 ORDER BY bid_id",
+        user.id
+    )
+    .fetch_all(pool.as_ref())
+    .await?;
+
+    let sealed_bids = sqlx::query!(
+        "SELECT auction_id, sealed_amount, created_at, updated_at FROM sealed_bids
+         WHERE bidder_id = $1 ORDER BY auction_id",
+        user.id
+    )
+    .fetch_all(pool.as_ref())
+    .await?
+    .into_iter()
+    .map(|row| {
+        Ok(SealedBid {
+            auction_id: row.auction_id,
+            bid_amount: open_amount(&row.sealed_amount, row.auction_id, user.id)?,
+            created_at: row.created_at,
+            updated_at: row.updated_at,
+        })
+    })
+    .collect::<Result<Vec<_>, ApiError>>()?;
+
+    let ratings_given = sqlx::query_as!(
+        Rating,
+        "SELECT r.id, r.auction_id, rater.username as rater_username, ratee.username as ratee_username,
+                r.ratee_role, r.score, r.comment, r.created_at
+         FROM ratings r
+         INNER JOIN users rater ON rater.id = r.rater_id
+         INNER JOIN users ratee ON ratee.id = r.ratee_id
+         WHERE r.rater_id = $1 ORDER BY r.id",
+        user.id
+    )
+    .fetch_all(pool.as_ref())
+    .await?;
+
+    let ratings_received = sqlx::query_as!(
+        Rating,
+        "SELECT r.id, r.auction_id, rater.username as rater_username, ratee.username as ratee_username,
+                r.ratee_role, r.score, r.comment, r.created_at
+         FROM ratings r
+         INNER JOIN users rater ON rater.id = r.rater_id
+         INNER JOIN users ratee ON ratee.id = r.ratee_id
+         WHERE r.ratee_id = $1 ORDER BY r.id",
+        user.id
+    )
+    .fetch_all(pool.as_ref())
+    .await?;
+
+    let saved_searches = fetch_saved_searches(&pool, user.id, None).await?;
+    let notifications = fetch_notifications(&pool, user.id, false, None).await?;
+    let watchlist = fetch_watchlist(&pool, user.id).await?;
+
+    Ok(HttpResponse::Ok()
+        .insert_header((
+            "Content-Disposition",
+            format!("attachment; filename=\"{}-export.json\"", user.username),
+        ))
+        .json(UserExport {
+            exported_at: Utc::now(),
+            profile,
+            vehicles,
+            auctions,
+            bids,
+            retracted_bids,
+            sealed_bids,
+            ratings_given,
+            ratings_received,
+            saved_searches,
+            notifications,
+            watchlist,
+        }))
+}
+
+/// Anonymize the account instead of deleting it, so other users keep their bid and sale history.
+pub async fn delete_my_account(
+    pool: web::Data<PgPool>,
+    redis_client: web::Data<redis::Client>,
+    storage: web::Data<dyn Storage>,
+    req: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    let user = require_user(&req, &pool, &redis_client).await?;
+
+    let mut tx = pool.begin().await?;
+
+    sqlx::query!("SELECT id FROM users WHERE id = $1 FOR UPDATE", user.id)
+        .fetch_one(&mut *tx)
+        .await?;
+
+    let has_open_auctions = sqlx::query_scalar!(
+        r#"SELECT EXISTS(
+            SELECT 1 FROM auctions a INNER JOIN vehicles v ON v.id = a.vehicle_id
+            WHERE a.closed = FALSE AND (a.seller_id = $1 OR v.owner_id = $1)
+        ) as "exists!""#,
+        user.id
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+
+    if has_open_auctions {
+        return Err(ApiError::new(
+            ErrorCode::AccountDeletionBlocked,
+            "Close your open auctions before deleting your account",
+        ));
+    }
+
+    let is_leading_bidder = sqlx::query_scalar!(
+        r#"SELECT EXISTS(
+            SELECT 1 FROM auctions a
+            WHERE a.closed = FALSE
+              AND (SELECT b.bidder_id FROM bids b WHERE b.auction_id = a.id
+                   ORDER BY b.bid_amount DESC LIMIT 1) = $1
+        ) OR EXISTS(
+            -- A sealed bid might be the winning one
+            SELECT 1 FROM sealed_bids s INNER JOIN auctions a ON a.id = s.auction_id
+            WHERE a.closed = FALSE AND s.bidder_id = $1
+        ) as "exists!""#,
+        user.id
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+
+    if is_leading_bidder {
+        return Err(ApiError::new(
+            ErrorCode::AccountDeletionBlocked,
+            "You are the leading bidder or hold a sealed bid on an open auction",
+        ));
     }
+
+    // Vehicles that never went to auction carry no shared history and can go, photos and paperwork included.
+    // Their rows cascade with the vehicle; the stored files are collected first and removed once committed.
+    let stored_keys = sqlx::query_scalar!(
+        r#"WITH removed AS (
+            SELECT v.id FROM vehicles v WHERE v.owner_id = $1
+            AND NOT EXISTS (SELECT 1 FROM auctions a WHERE a.vehicle_id = v.id)
+        )
+        SELECT web_key as "key!" FROM vehicle_images WHERE vehicle_id IN (SELECT id FROM removed)
+        UNION ALL SELECT thumbnail_key FROM vehicle_images WHERE vehicle_id IN (SELECT id FROM removed)
+        UNION ALL SELECT storage_key FROM vehicle_documents WHERE vehicle_id IN (SELECT id FROM removed)"#,
+        user.id
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+    sqlx::query!(
+        "DELETE FROM vehicles v WHERE v.owner_id = $1
+         AND NOT EXISTS (SELECT 1 FROM auctions a WHERE a.vehicle_id = v.id)",
+        user.id
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    // Alerts and watchlists are personal and mean nothing once the account is gone
+    sqlx::query!("DELETE FROM saved_searches WHERE user_id = $1", user.id)
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query!("DELETE FROM notifications WHERE user_id = $1", user.id)
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query!("DELETE FROM auction_watchers WHERE user_id = $1", user.id)
+        .execute(&mut *tx)
+        .await?;
+
+    // Bids, auctions and ratings keep pointing at the row, which no longer identifies anyone
+    let anonymized = format!("{}{}", DELETED_USER_PREFIX, user.id);
+    sqlx::query!(
+        "UPDATE users SET username = $2, password = '', display_name = NULL, location = NULL,
+             bio = NULL, avatar_url = NULL, deleted_at = NOW()
+         WHERE id = $1",
+        user.id,
+        anonymized
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    remove_files(storage.as_ref(), &stored_keys).await;
+    revoke_sessions(&redis_client, user.id).await?;
+
+    Ok(HttpResponse::NoContent().finish())
 }
 
+pub async fn change_username(
+    pool: web::Data<PgPool>,
+    redis_client: web::Data<redis::Client>,
+    req: HttpRequest,
+    form: web::Json<ChangeUsername>,
+) -> Result<HttpResponse, ApiError> {
+    let user = require_user(&req, &pool, &redis_client).await?;
+    form.validate()?;
 
+    // Everything references users by id, so renaming is a single row update
+    let updated = sqlx::query_as!(
+        UserSummary,
+        "UPDATE users SET username = $2 WHERE id = $1
+         AND NOT EXISTS (SELECT 1 FROM users WHERE username = $2)
+         RETURNING id, username, created_at",
+        user.id,
+        form.username
+    )
+    .fetch_optional(pool.as_ref())
+    .await
+    .map_err(username_conflict)?
+    .ok_or_else(|| ApiError::new(ErrorCode::UsernameTaken, "Username already taken"))?;
 
+    Ok(HttpResponse::Ok().json(updated))
+}
 
+/// Report a rename that lost a race for the name like the `NOT EXISTS` check would have.
+fn username_conflict(err: sqlx::Error) -> ApiError {
+    match err.as_database_error().and_then(|db_err| db_err.constraint()) {
+        Some("users_username_key") => ApiError::new(ErrorCode::UsernameTaken, "Username already taken"),
+        _ => err.into(),
+    }
+}
@@ -1,7 +1,20 @@
-use sqlx::PgPool;
-use actix_web::{web, Responder, HttpResponse, HttpRequest};
-use crate::models::{CreateVehicle, Vehicle};
-use redis::AsyncCommands;
+use sqlx::{PgConnection, PgPool};
+use actix_web::{web, HttpResponse, HttpRequest};
+use crate::auction_state::{transition, AuctionStatus};
+use crate::errors::{ApiError, ErrorCode, FieldError, Validator};
+use crate::models::{
+    CreateVehicle, Vehicle, VehicleFilter, VehiclePage, UpdateVehicle, VehicleDetail, VehicleEdit, DEFAULT_PAGE_SIZE,
+};
+use crate::session::{require_admin, require_user};
+use crate::vin;
+use crate::routes::category::{category_counts, ensure_category_exists, push_category_filter};
+use crate::routes::history::record_registration;
+use crate::routes::image::{fetch_images, require_vehicle_owner};
+use bigdecimal::BigDecimal;
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use sqlx::{Postgres, QueryBuilder};
+use std::str::FromStr;
 
 
 pub async fn create_vehicle(
@@ -9,146 +22,588 @@ pub async fn create_vehicle(
     redis_client: web::Data<redis::Client>,
     req: HttpRequest,
     form: web::Json<CreateVehicle>,
-) -> impl Responder {
-    // Extract the Session-Code from headers
-    let session_code = match req.headers().get("Session-Code") {
-        Some(code) => code.to_str().unwrap_or_default(),
-        None => return HttpResponse::Unauthorized().body("Missing Session-Code header"),
-    };
+) -> Result<HttpResponse, ApiError> {
+    // Check if the session is valid and the user still exists
+    let user = require_user(&req, &pool, &redis_client).await?;
+    form.validate()?;
 
-    // Connect to Redis
-    let mut redis_conn = match redis_client.get_multiplexed_async_connection().await {
-        Ok(conn) => conn,
-        Err(_) => return HttpResponse::InternalServerError().body("Failed to connect to Redis"),
-    };
+    let mut tx = pool.begin().await?;
+    let vehicle = insert_vehicle(&mut tx, user.id, &form).await?;
+    tx.commit().await?;
 
-     // Check if the session is valid
-    // Retrieve the username associated with the session_code from Redis
-    let user_name: Option<String> = match redis_conn
-        .get(format!("session:{}", session_code))
-        .await
-    {
-        Ok(owner_username) => owner_username,
-        Err(_) => return HttpResponse::InternalServerError().body("Failed to retrieve username"),
-    };
+    Ok(HttpResponse::Ok().json(vehicle))
+}
+
+/// Insert an already validated vehicle for `owner_id` and start its ownership history.
+pub async fn insert_vehicle(conn: &mut PgConnection, owner_id: i32, form: &CreateVehicle) -> Result<Vehicle, ApiError> {
+    let starting_price = BigDecimal::from_str(&form.starting_price.to_string())
+        .map_err(|_| ApiError::new(ErrorCode::InvalidPayload, "Failed to parse starting price"))?;
 
-     // Check if the username exists
-     let username_exists: (bool,) = match sqlx::query_as::<_, (bool,)>(
-        "SELECT EXISTS(SELECT 1 FROM users WHERE username = $1)"
+    let mut spec = form.spec.clone();
+    spec.fill_from_vin();
+    ensure_vin_unique(&mut *conn, spec.vin.as_deref(), None).await?;
+    ensure_category_exists(&mut *conn, form.category_id).await?;
+
+    let vehicle = sqlx::query_as!(
+        Vehicle,
+        "INSERT INTO vehicles (name, description, starting_price, owner_id, make, model, year, vin, mileage,
+                mileage_unit, fuel_type, transmission, body_style, colour, engine, matching_numbers,
+                registration_country, condition_grade, category_id)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18, $19)
+         RETURNING id, name, description, starting_price, make, model, year, vin, mileage, mileage_unit,
+                   fuel_type, transmission, body_style, colour, engine, matching_numbers, registration_country,
+                   condition_grade, category_id",
+        form.name,
+        form.description,
+        starting_price,
+        owner_id,
+        spec.make,
+        spec.model,
+        spec.year,
+        spec.vin,
+        spec.mileage,
+        spec.mileage_unit,
+        spec.fuel_type,
+        spec.transmission,
+        spec.body_style,
+        spec.colour,
+        spec.engine,
+        spec.matching_numbers,
+        spec.registration_country,
+        spec.condition_grade,
+        form.category_id
     )
-    .bind(&user_name)
-    .fetch_one(pool.as_ref())
+    .fetch_one(&mut *conn)
     .await
-    {
-        Ok(result) => result,
-        Err(_) => return HttpResponse::InternalServerError().body("Database query error"),
-    };
+    .map_err(vin_conflict)?;
 
-    if !username_exists.0 {
-        // Username not exists
-        return HttpResponse::BadRequest().body("Username not exists");
-    }
-
-    
-
-    match user_name {
-        Some(_) => {
-            // Session is valid, proceed to create the vehicle
-            if let Err(_) = sqlx::query("INSERT INTO vehicles (name, description, starting_price, owner_username) VALUES ($1, $2, $3, $4)"
-                )
-                .bind(&form.name)
-                .bind(&form.description)
-                .bind(form.starting_price)
-                .bind(&user_name)
-                .execute(pool.as_ref())
-                .await
-            {
-                return HttpResponse::InternalServerError().body("Failed to create vehicle");
-            }
-
-            HttpResponse::Ok().body("Vehicle Created")
-        }
-        None => {
-            // Session is invalid or expired
-            HttpResponse::Unauthorized().body("Invalid or expired session")
-        }
+    record_registration(conn, vehicle.id, owner_id).await?;
+    Ok(vehicle)
+}
+
+/// Position after the last row of a page, tied to the sort it was produced for.
+#[derive(Serialize, Deserialize)]
+struct ListCursor {
+    sort: String,
+    value: String,
+    id: i32,
+}
+
+impl ListCursor {
+    fn encode(&self) -> String {
+        hex::encode(serde_json::to_vec(self).unwrap_or_default())
+    }
+
+    /// Decode a cursor issued for `sort`, parsing its value into the type of the sort column.
+    fn decode(cursor: &str, sort: &str) -> Result<(CursorValue, i32), ApiError> {
+        let (column, _) = sort_key(sort);
+        hex::decode(cursor)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice::<ListCursor>(&bytes).ok())
+            .filter(|decoded| decoded.sort == sort)
+            .and_then(|decoded| {
+                let value = match column {
+                    "v.starting_price" => CursorValue::Price(BigDecimal::from_str(&decoded.value).ok()?),
+                    _ => CursorValue::CreatedAt(
+                        NaiveDateTime::parse_from_str(&decoded.value, "%Y-%m-%dT%H:%M:%S%.f").ok()?,
+                    ),
+                };
+                Some((value, decoded.id))
+            })
+            .ok_or_else(|| {
+                ApiError::validation(vec![FieldError {
+                    field: "cursor".to_string(),
+                    message: "is not valid for this sort".to_string(),
+                }])
+            })
     }
 }
 
-pub async fn list_vehicles(pool: web::Data<PgPool>) -> impl Responder {
-    let result = sqlx::query_as!(
-        Vehicle, 
-        "SELECT id, name, description, starting_price FROM vehicles"
-    )
-    .fetch_all(pool.get_ref())
-    .await;
-
-    match result {
-        Ok(vehicles) => HttpResponse::Ok().json(vehicles),
-        Err(err) => {
-            eprintln!("Error fetching vehicles: {:?}", err);
-            HttpResponse::InternalServerError().body("Failed to fetch vehicles")
-        }
+/// Sort column value of the last row of the previous page.
+enum CursorValue {
+    CreatedAt(NaiveDateTime),
+    Price(BigDecimal),
+}
+
+#[derive(sqlx::FromRow)]
+struct VehicleRow {
+    #[sqlx(flatten)]
+    vehicle: Vehicle,
+    created_at: NaiveDateTime,
+}
+
+/// Condition on `vehicles v` excluding vehicles whose latest auction sold them.
+pub const NOT_SOLD: &str = "NOT COALESCE((SELECT a.winner_id IS NOT NULL FROM auctions a
+                              WHERE a.vehicle_id = v.id ORDER BY a.id DESC LIMIT 1), FALSE)";
+
+/// Columns of `Vehicle` selected from `vehicles v`.
+pub const VEHICLE_COLUMNS: &str = "v.id, v.name, v.description, v.starting_price, v.make, v.model, v.year, v.vin,
+    v.mileage, v.mileage_unit, v.fuel_type, v.transmission, v.body_style, v.colour, v.engine, v.matching_numbers,
+    v.registration_country, v.condition_grade, v.category_id";
+
+/// Sort column and whether it sorts descending.
+fn sort_key(sort: &str) -> (&'static str, bool) {
+    match sort {
+        "oldest" => ("v.created_at", false),
+        "price_asc" => ("v.starting_price", false),
+        "price_desc" => ("v.starting_price", true),
+        _ => ("v.created_at", true),
     }
 }
 
+fn push_filters(query: &mut QueryBuilder<'_, Postgres>, filter: &VehicleFilter) {
+    query.push(" FROM vehicles v WHERE v.deleted_at IS NULL");
+    if let Some(make) = filter.make.clone() {
+        query.push(" AND lower(v.make) = lower(").push_bind(make).push(")");
+    }
+    if let Some(model) = filter.model.clone() {
+        query.push(" AND lower(v.model) = lower(").push_bind(model).push(")");
+    }
+    if let Some(year_min) = filter.year_min {
+        query.push(" AND v.year >= ").push_bind(year_min);
+    }
+    if let Some(year_max) = filter.year_max {
+        query.push(" AND v.year <= ").push_bind(year_max);
+    }
+    if let Some(fuel_type) = filter.fuel_type.clone() {
+        query.push(" AND v.fuel_type = ").push_bind(fuel_type);
+    }
+    if let Some(transmission) = filter.transmission.clone() {
+        query.push(" AND v.transmission = ").push_bind(transmission);
+    }
+    if let Some(body_style) = filter.body_style.clone() {
+        query.push(" AND v.body_style = ").push_bind(body_style);
+    }
+    if let Some(matching_numbers) = filter.matching_numbers {
+        query.push(" AND v.matching_numbers = ").push_bind(matching_numbers);
+    }
+    if let Some(country) = filter.registration_country.clone() {
+        query.push(" AND v.registration_country = upper(").push_bind(country).push(")");
+    }
+    if let Some(grade) = filter.condition_grade_max {
+        query.push(" AND v.condition_grade <= ").push_bind(grade);
+    }
+    if let Some(price_min) = filter.price_min {
+        query.push(" AND v.starting_price >= ").push_bind(price_min).push("::NUMERIC");
+    }
+    if let Some(price_max) = filter.price_max {
+        query.push(" AND v.starting_price <= ").push_bind(price_max).push("::NUMERIC");
+    }
+    if let Some(owner) = filter.owner.clone() {
+        query.push(" AND v.owner_id = (SELECT id FROM users WHERE username = ").push_bind(owner).push(")");
+    }
+    if let Some(category) = filter.category.clone() {
+        push_category_filter(query, category);
+    }
+    if let Some(has_open_auction) = filter.has_open_auction {
+        query
+            .push(if has_open_auction { " AND " } else { " AND NOT " })
+            .push(
+                "EXISTS (SELECT 1 FROM auctions a
+                 WHERE a.vehicle_id = v.id AND a.closed = FALSE AND a.status <> 'draft')",
+            );
+    }
+    if !filter.include_sold {
+        query.push(" AND ").push(NOT_SOLD);
+    }
+    if let Some(q) = filter.q.as_deref().map(str::trim).filter(|q| !q.is_empty()) {
+        let pattern = format!("%{}%", q.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_"));
+        query
+            .push(" AND concat_ws(' ', v.name, v.description, v.make, v.model) ILIKE ")
+            .push_bind(pattern);
+    }
+}
+
+pub async fn list_vehicles(
+    pool: web::Data<PgPool>,
+    filter: web::Query<VehicleFilter>,
+) -> Result<HttpResponse, ApiError> {
+    filter.validate()?;
+    let sort = filter.sort.as_deref().unwrap_or("newest");
+    let limit = filter.limit.unwrap_or(DEFAULT_PAGE_SIZE);
+    let cursor = filter.cursor.as_deref().map(|cursor| ListCursor::decode(cursor, sort)).transpose()?;
+
+    let mut count_query = QueryBuilder::new("SELECT COUNT(*)");
+    push_filters(&mut count_query, &filter);
+    let total_count: i64 = count_query.build_query_scalar().fetch_one(pool.get_ref()).await?;
+    let category_counts = category_counts(pool.get_ref(), |query| push_filters(query, &filter)).await?;
+
+    let (column, descending) = sort_key(sort);
+    let mut query = QueryBuilder::new(format!("SELECT {}, v.created_at", VEHICLE_COLUMNS));
+    push_filters(&mut query, &filter);
+    if let Some((value, id)) = cursor {
+        query.push(format!(" AND ({}, v.id) {} (", column, if descending { "<" } else { ">" }));
+        match value {
+            CursorValue::CreatedAt(created_at) => query.push_bind(created_at),
+            CursorValue::Price(price) => query.push_bind(price),
+        };
+        query.push(", ").push_bind(id).push(")");
+    }
+    let direction = if descending { "DESC" } else { "ASC" };
+    query
+        .push(format!(" ORDER BY {} {}, v.id {} LIMIT ", column, direction, direction))
+        .push_bind(limit + 1);
+
+    let mut rows: Vec<VehicleRow> = query.build_query_as().fetch_all(pool.get_ref()).await?;
+
+    // The extra row only tells us whether another page exists
+    let next_cursor = if rows.len() as i64 > limit {
+        rows.truncate(limit as usize);
+        rows.last().map(|row| {
+            let value = match column {
+                "v.starting_price" => row.vehicle.starting_price.to_string(),
+                _ => row.created_at.format("%Y-%m-%dT%H:%M:%S%.6f").to_string(),
+            };
+            ListCursor { sort: sort.to_string(), value, id: row.vehicle.id }.encode()
+        })
+    } else {
+        None
+    };
+
+    Ok(HttpResponse::Ok().json(VehiclePage {
+        items: rows.into_iter().map(|row| row.vehicle).collect(),
+        total_count,
+        next_cursor,
+        category_counts,
+    }))
+}
+
 pub async fn delete_vehicle(
     pool: web::Data<PgPool>,
     redis_client: web::Data<redis::Client>,
     req: HttpRequest,
     path: web::Path<i32>,
-) -> impl Responder {
-    // Extract the Session-Code from headers
-    let session_code = match req.headers().get("Session-Code") {
-        Some(code) => code.to_str().unwrap_or_default(),
-        None => return HttpResponse::Unauthorized().body("Missing Session-Code header"),
-    };
+) -> Result<HttpResponse, ApiError> {
+    let user = require_user(&req, &pool, &redis_client).await?;
+    let vehicle_id = *path;
 
-    // Connect to Redis
-    let mut redis_conn = match redis_client.get_multiplexed_async_connection().await {
-        Ok(conn) => conn,
-        Err(_) => return HttpResponse::InternalServerError().body("Failed to connect to Redis"),
-    };
+    let mut tx = pool.begin().await?;
+    require_vehicle_owner(&mut *tx, vehicle_id, user.id).await?;
 
-    // Retrieve the username associated with the session_code from Redis
-    let user_name: Option<String> = match redis_conn
-        .get(format!("session:{}", session_code))
-        .await
-    {
-        Ok(owner_username) => owner_username,
-        Err(_) => return HttpResponse::InternalServerError().body("Failed to retrieve username"),
-    };
+    let open_auction = sqlx::query!(
+        r#"SELECT a.id,
+                  (EXISTS(SELECT 1 FROM bids b WHERE b.auction_id = a.id)
+                   OR EXISTS(SELECT 1 FROM sealed_bids s WHERE s.auction_id = a.id)) as "has_bids!"
+           FROM auctions a WHERE a.vehicle_id = $1 AND a.closed = FALSE"#,
+        vehicle_id
+    )
+    .fetch_optional(&mut *tx)
+    .await?;
 
-    let user_name = match user_name {
-        Some(name) => name,
-        None => return HttpResponse::Unauthorized().body("Invalid or expired session"),
-    };
+    if let Some(auction) = open_auction {
+        if auction.has_bids {
+            return Err(ApiError::new(
+                ErrorCode::VehicleHasLiveBids,
+                "The vehicle is in an open auction with bids and cannot be deleted",
+            ));
+        }
 
-    // Check ownership
+        // Nobody bid yet, so the auction simply ends with the listing
+        transition(&mut tx, auction.id, AuctionStatus::Cancelled, Some(user.id), Some("Vehicle deleted")).await?;
+    }
+
+    // Archive rather than delete: auctions, bids, images and documents stay for audit and restore
+    sqlx::query!(
+        "UPDATE vehicles SET deleted_at = NOW(), deleted_by = $2 WHERE id = $1",
+        vehicle_id,
+        user.id
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Bring back a soft-deleted vehicle; administrators only.
+pub async fn restore_vehicle(
+    pool: web::Data<PgPool>,
+    redis_client: web::Data<redis::Client>,
+    req: HttpRequest,
+    path: web::Path<i32>,
+) -> Result<HttpResponse, ApiError> {
+    require_admin(&req, &pool, &redis_client).await?;
     let vehicle_id = *path;
-    let owner_username: Option<String> = match sqlx::query_scalar!(
-        "SELECT owner_username FROM vehicles WHERE id = $1",
+
+    let mut tx = pool.begin().await?;
+    let vin = sqlx::query_scalar!(
+        "SELECT vin FROM vehicles WHERE id = $1 AND deleted_at IS NOT NULL FOR UPDATE",
         vehicle_id
     )
-    .fetch_optional(pool.as_ref())
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| ApiError::new(ErrorCode::VehicleNotFound, "No deleted vehicle with this id"))?;
+
+    // The VIN may have been listed again in the meantime
+    ensure_vin_unique(&mut *tx, vin.as_deref(), Some(vehicle_id)).await?;
+
+    sqlx::query!(
+        "UPDATE vehicles SET deleted_at = NULL, deleted_by = NULL WHERE id = $1",
+        vehicle_id
+    )
+    .execute(&mut *tx)
     .await
-    {
-        Ok(Some(username)) => Some(username),
-        Ok(None) => return HttpResponse::NotFound().body("Vehicle not found"),
-        Err(_) => return HttpResponse::InternalServerError().body("Database query error"),
+    .map_err(vin_conflict)?;
+
+    let vehicle = fetch_vehicle(&mut *tx, vehicle_id).await?;
+    tx.commit().await?;
+
+    Ok(HttpResponse::Ok().json(vehicle))
+}
+
+/// Unique index on the VINs of listed vehicles.
+const VIN_INDEX: &str = "idx_vehicles_vin_listed";
+
+/// Report a write that lost a race for a VIN like [`ensure_vin_unique`] would have.
+fn vin_conflict(err: sqlx::Error) -> ApiError {
+    match err.as_database_error().and_then(|db_err| db_err.constraint()) {
+        Some(VIN_INDEX) => ApiError::new(ErrorCode::DuplicateVin, "A vehicle with this VIN is already listed"),
+        _ => err.into(),
+    }
+}
+
+/// Reject a VIN already used by another listed vehicle.
+///
+/// `VIN_INDEX` enforces this; checking first gives the usual error before any write.
+async fn ensure_vin_unique<'e>(
+    executor: impl sqlx::PgExecutor<'e>,
+    vin: Option<&str>,
+    except_vehicle_id: Option<i32>,
+) -> Result<(), ApiError> {
+    let Some(vin) = vin else {
+        return Ok(());
     };
 
-    if owner_username.as_deref() != Some(&user_name) {
-        return HttpResponse::Forbidden().body("You are not the owner of this vehicle");
+    let taken = sqlx::query_scalar!(
+        r#"SELECT EXISTS(
+            SELECT 1 FROM vehicles WHERE vin = $1 AND deleted_at IS NULL AND ($2::INT IS NULL OR id <> $2)
+        ) as "taken!""#,
+        vin,
+        except_vehicle_id
+    )
+    .fetch_one(executor)
+    .await?;
+
+    if taken {
+        return Err(ApiError::new(ErrorCode::DuplicateVin, "A vehicle with this VIN is already listed"));
     }
+    Ok(())
+}
 
-    // Delete the vehicle
-    match sqlx::query!("DELETE FROM vehicles WHERE id = $1", vehicle_id)
-        .execute(pool.as_ref())
-        .await
-    {
-        Ok(_) => HttpResponse::Ok().body("Vehicle Deleted"),
-        Err(_) => HttpResponse::InternalServerError().body("Failed to delete vehicle"),
+async fn fetch_vehicle<'e>(executor: impl sqlx::PgExecutor<'e>, vehicle_id: i32) -> Result<Vehicle, ApiError> {
+    sqlx::query_as!(
+        Vehicle,
+        "SELECT id, name, description, starting_price, make, model, year, vin, mileage, mileage_unit,
+                fuel_type, transmission, body_style, colour, engine, matching_numbers, registration_country,
+                condition_grade, category_id
+         FROM vehicles WHERE id = $1 AND deleted_at IS NULL",
+        vehicle_id
+    )
+    .fetch_optional(executor)
+    .await?
+    .ok_or_else(|| ApiError::new(ErrorCode::VehicleNotFound, "Vehicle not found"))
+}
+
+async fn fetch_vehicle_detail(pool: &PgPool, vehicle_id: i32) -> Result<VehicleDetail, ApiError> {
+    let vehicle = fetch_vehicle(pool, vehicle_id).await?;
+
+    let listing = sqlx::query!(
+        "SELECT u.username as owner_username, v.created_at,
+                (SELECT a.id FROM auctions a WHERE a.vehicle_id = v.id AND a.closed = FALSE AND a.status <> 'draft'
+                 LIMIT 1) as open_auction_id
+         FROM vehicles v INNER JOIN users u ON u.id = v.owner_id
+         WHERE v.id = $1",
+        vehicle_id
+    )
+    .fetch_one(pool)
+    .await?;
+
+    let edits = sqlx::query_as!(
+        VehicleEdit,
+        "SELECT auction_id, field, old_value, new_value, edited_at FROM vehicle_edits
+         WHERE vehicle_id = $1 ORDER BY edited_at, id",
+        vehicle_id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(VehicleDetail {
+        vehicle,
+        owner_username: listing.owner_username,
+        created_at: listing.created_at,
+        open_auction_id: listing.open_auction_id,
+        edits,
+        images: fetch_images(pool, vehicle_id).await?,
+    })
+}
+
+/// Whether the update touches anything bidders rely on: name, price or specification.
+fn changes_locked_fields(form: &UpdateVehicle, starting_price: Option<&BigDecimal>, current: &Vehicle) -> bool {
+    fn differs<T: PartialEq>(new: &Option<T>, old: &Option<T>) -> bool {
+        new.is_some() && new != old
     }
+
+    let spec = &form.spec;
+    form.name.as_ref().is_some_and(|name| *name != current.name)
+        || starting_price.is_some_and(|price| *price != current.starting_price)
+        || differs(&spec.make, &current.make)
+        || differs(&spec.model, &current.model)
+        || differs(&spec.year, &current.year)
+        || differs(&spec.vin, &current.vin)
+        || differs(&spec.mileage, &current.mileage)
+        || differs(&spec.mileage_unit, &current.mileage_unit)
+        || differs(&spec.fuel_type, &current.fuel_type)
+        || differs(&spec.transmission, &current.transmission)
+        || differs(&spec.body_style, &current.body_style)
+        || differs(&spec.colour, &current.colour)
+        || differs(&spec.engine, &current.engine)
+        || differs(&spec.matching_numbers, &current.matching_numbers)
+        || differs(&spec.registration_country, &current.registration_country)
+        || differs(&spec.condition_grade, &current.condition_grade)
 }
 
+pub async fn get_vehicle(
+    pool: web::Data<PgPool>,
+    path: web::Path<i32>,
+) -> Result<HttpResponse, ApiError> {
+    let vehicle = fetch_vehicle_detail(&pool, *path).await?;
+
+    Ok(HttpResponse::Ok().json(vehicle))
+}
+
+pub async fn update_vehicle(
+    pool: web::Data<PgPool>,
+    redis_client: web::Data<redis::Client>,
+    req: HttpRequest,
+    path: web::Path<i32>,
+    form: web::Json<UpdateVehicle>,
+) -> Result<HttpResponse, ApiError> {
+    let user = require_user(&req, &pool, &redis_client).await?;
+    form.validate()?;
+    let vehicle_id = *path;
+    let mut form = form.into_inner();
+    form.spec.normalize_vin();
+
+    let starting_price = form
+        .starting_price
+        .map(|price| BigDecimal::from_str(&price.to_string()))
+        .transpose()
+        .map_err(|_| ApiError::new(ErrorCode::InvalidPayload, "Failed to parse starting price"))?;
+
+    let mut tx = pool.begin().await?;
+
+    let owner_id = sqlx::query_scalar!(
+        "SELECT owner_id FROM vehicles WHERE id = $1 AND deleted_at IS NULL FOR UPDATE",
+        vehicle_id
+    )
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| ApiError::new(ErrorCode::VehicleNotFound, "Vehicle not found"))?;
+
+    if owner_id != user.id {
+        return Err(ApiError::new(ErrorCode::NotOwner, "You are not the owner of this vehicle"));
+    }
+
+    let current = fetch_vehicle(&mut *tx, vehicle_id).await?;
+
+    // Chassis number rules depend on the year, which may already be stored
+    let mut validator = Validator::default();
+    form.spec.check_vin(&mut validator, form.spec.year.or(current.year));
+    validator.finish()?;
+    ensure_vin_unique(&mut *tx, form.spec.vin.as_deref(), Some(vehicle_id)).await?;
+    ensure_category_exists(&mut *tx, form.category_id).await?;
+
+    let open_auction_id = sqlx::query_scalar!(
+        "SELECT id FROM auctions WHERE vehicle_id = $1 AND closed = FALSE AND status <> 'draft'",
+        vehicle_id
+    )
+    .fetch_optional(&mut *tx)
+    .await?;
+
+    if let Some(auction_id) = open_auction_id {
+        // Once published, bidders rely on the name, price and specification; only the description may change
+        if changes_locked_fields(&form, starting_price.as_ref(), &current) {
+            return Err(ApiError::new(
+                ErrorCode::VehicleLocked,
+                "Only the description can be changed while the vehicle is in an open auction",
+            ));
+        }
+
+        if let Some(description) = form.description.as_ref().filter(|d| **d != current.description) {
+            sqlx::query!(
+                "INSERT INTO vehicle_edits (vehicle_id, auction_id, field, old_value, new_value)
+                 VALUES ($1, $2, 'description', $3, $4)",
+                vehicle_id,
+                auction_id,
+                current.description,
+                description
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+    }
+
+    let spec = &form.spec;
+    sqlx::query!(
+        "UPDATE vehicles SET
+            name = COALESCE($2, name),
+            description = COALESCE($3, description),
+            starting_price = COALESCE($4, starting_price),
+            make = COALESCE($5, make),
+            model = COALESCE($6, model),
+            year = COALESCE($7, year),
+            vin = COALESCE($8, vin),
+            mileage = COALESCE($9, mileage),
+            mileage_unit = COALESCE($10, mileage_unit),
+            fuel_type = COALESCE($11, fuel_type),
+            transmission = COALESCE($12, transmission),
+            body_style = COALESCE($13, body_style),
+            colour = COALESCE($14, colour),
+            engine = COALESCE($15, engine),
+            matching_numbers = COALESCE($16, matching_numbers),
+            registration_country = COALESCE($17, registration_country),
+            condition_grade = COALESCE($18, condition_grade),
+            category_id = COALESCE($19, category_id)
+         WHERE id = $1",
+        vehicle_id,
+        form.name,
+        form.description,
+        starting_price,
+        spec.make,
+        spec.model,
+        spec.year,
+        spec.vin,
+        spec.mileage,
+        spec.mileage_unit,
+        spec.fuel_type,
+        spec.transmission,
+        spec.body_style,
+        spec.colour,
+        spec.engine,
+        spec.matching_numbers,
+        spec.registration_country,
+        spec.condition_grade,
+        form.category_id
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(vin_conflict)?;
+
+    tx.commit().await?;
+
+    let vehicle = fetch_vehicle_detail(&pool, vehicle_id).await?;
+    Ok(HttpResponse::Ok().json(vehicle))
+}
+
+/// Decode a VIN offline so clients can prefill the listing form.
+pub async fn decode_vin(path: web::Path<String>) -> Result<HttpResponse, ApiError> {
+    let vin = vin::normalize(&path);
+    if let Err(message) = vin::validate(&vin, None) {
+        Validator::default().check(false, "vin", message).finish()?;
+    }
+
+    let info = vin::decode(&vin)
+        .ok_or_else(|| ApiError::new(ErrorCode::InternalError, "Failed to decode VIN"))?;
+    Ok(HttpResponse::Ok().json(info))
+}
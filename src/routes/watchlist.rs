@@ -0,0 +1,90 @@
+use sqlx::PgPool;
+use actix_web::{web, HttpResponse, HttpRequest};
+use crate::errors::{ApiError, ErrorCode};
+use crate::models::WatchedAuction;
+use crate::session::require_user;
+
+/// Auctions a user watches, those ending soonest first.
+pub async fn fetch_watchlist(pool: &PgPool, user_id: i32) -> Result<Vec<WatchedAuction>, ApiError> {
+    let watchlist = sqlx::query_as!(
+        WatchedAuction,
+        r#"SELECT a.id as auction_id, a.vehicle_id, v.name as vehicle_name, a.starting_price, a.end_time,
+                  a.closed,
+                  (SELECT MAX(b.bid_amount) FROM bids b WHERE b.auction_id = a.id) as highest_bid,
+                  (SELECT COUNT(*) FROM bids b WHERE b.auction_id = a.id) as "bid_count!",
+                  (SELECT COUNT(*) FROM auction_watchers aw WHERE aw.auction_id = a.id) as "watcher_count!",
+                  w.created_at as watched_at
+           FROM auction_watchers w
+           INNER JOIN auctions a ON a.id = w.auction_id
+           INNER JOIN vehicles v ON v.id = a.vehicle_id
+           WHERE w.user_id = $1
+           ORDER BY a.closed, a.end_time, a.id"#,
+        user_id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(watchlist)
+}
+
+pub async fn watch_auction(
+    pool: web::Data<PgPool>,
+    redis_client: web::Data<redis::Client>,
+    req: HttpRequest,
+    path: web::Path<i32>,
+) -> Result<HttpResponse, ApiError> {
+    let user = require_user(&req, &pool, &redis_client).await?;
+    let auction_id = *path;
+
+    let closed = sqlx::query_scalar!(
+        r#"SELECT closed FROM auctions WHERE id = $1 AND status <> 'draft'"#,
+        auction_id
+    )
+    .fetch_optional(pool.as_ref())
+    .await?
+    .ok_or_else(|| ApiError::new(ErrorCode::AuctionNotFound, "Auction not found"))?;
+
+    if closed {
+        return Err(ApiError::new(ErrorCode::AuctionClosed, "The auction is already closed"));
+    }
+
+    // Watching twice is harmless
+    sqlx::query!(
+        "INSERT INTO auction_watchers (user_id, auction_id) VALUES ($1, $2) ON CONFLICT DO NOTHING",
+        user.id,
+        auction_id
+    )
+    .execute(pool.as_ref())
+    .await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+pub async fn unwatch_auction(
+    pool: web::Data<PgPool>,
+    redis_client: web::Data<redis::Client>,
+    req: HttpRequest,
+    path: web::Path<i32>,
+) -> Result<HttpResponse, ApiError> {
+    let user = require_user(&req, &pool, &redis_client).await?;
+
+    sqlx::query!(
+        "DELETE FROM auction_watchers WHERE user_id = $1 AND auction_id = $2",
+        user.id,
+        *path
+    )
+    .execute(pool.as_ref())
+    .await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+pub async fn get_my_watchlist(
+    pool: web::Data<PgPool>,
+    redis_client: web::Data<redis::Client>,
+    req: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    let user = require_user(&req, &pool, &redis_client).await?;
+    let watchlist = fetch_watchlist(&pool, user.id).await?;
+    Ok(HttpResponse::Ok().json(watchlist))
+}
@@ -0,0 +1,34 @@
+use sqlx::PgPool;
+use std::time::Duration;
+use crate::errors::ApiError;
+use crate::notifications::{reminder_offsets, send_daily_digests, send_ending_soon_reminders};
+use crate::routes::auction::start_scheduled_auctions;
+
+/// How often background jobs run unless `SCHEDULER_INTERVAL_SECONDS` says otherwise.
+pub const DEFAULT_INTERVAL_SECONDS: u64 = 60;
+
+/// One pass over all periodic jobs.
+pub async fn run_once(pool: &PgPool) -> Result<(), ApiError> {
+    start_scheduled_auctions(pool).await?;
+    send_daily_digests(pool).await?;
+    send_ending_soon_reminders(pool, &reminder_offsets()).await?;
+    Ok(())
+}
+
+/// Run the periodic jobs on the actix runtime for the lifetime of the server.
+pub fn spawn(pool: PgPool) {
+    let seconds = std::env::var("SCHEDULER_INTERVAL_SECONDS")
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .filter(|seconds| *seconds > 0)
+        .unwrap_or(DEFAULT_INTERVAL_SECONDS);
+
+    actix_web::rt::spawn(async move {
+        let mut interval = actix_web::rt::time::interval(Duration::from_secs(seconds));
+        loop {
+            interval.tick().await;
+            // Errors are already logged by ApiError; try again on the next tick
+            let _ = run_once(&pool).await;
+        }
+    });
+}
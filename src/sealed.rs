@@ -0,0 +1,86 @@
+use bigdecimal::BigDecimal;
+use openssl::symm::{decrypt_aead, encrypt_aead, Cipher};
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+use std::str::FromStr;
+use crate::errors::{ApiError, ErrorCode};
+
+const NONCE_LEN: usize = 12;
+const TAG_LEN: usize = 16;
+/// Shortest accepted `SEALED_BID_KEY`; the key is derived from it, so it must not be guessable.
+pub const MIN_SECRET_LENGTH: usize = 32;
+
+/// AES-256 key derived from a sealed-bid secret, which is trimmed before it is checked or hashed.
+pub fn key_from_secret(secret: Option<&str>) -> Result<[u8; 32], ApiError> {
+    let secret = secret
+        .map(str::trim)
+        .filter(|secret| secret.len() >= MIN_SECRET_LENGTH)
+        .ok_or_else(|| {
+            ApiError::new(
+                ErrorCode::SealedBiddingUnavailable,
+                "Sealed-bid auctions are not available on this server",
+            )
+        })?;
+    Ok(Sha256::digest(secret.as_bytes()).into())
+}
+
+/// AES-256 key derived from the `SEALED_BID_KEY` secret.
+fn key() -> Result<[u8; 32], ApiError> {
+    key_from_secret(std::env::var("SEALED_BID_KEY").ok().as_deref())
+}
+
+/// Whether `SEALED_BID_KEY` is usable; sealed auctions are refused without it.
+pub fn ensure_configured() -> Result<(), ApiError> {
+    key().map(|_| ())
+}
+
+/// Ties a sealed amount to its row, so ciphertexts cannot be swapped between bidders or auctions.
+fn associated_data(auction_id: i32, bidder_id: i32) -> Vec<u8> {
+    format!("sealed-bid:{}:{}", auction_id, bidder_id).into_bytes()
+}
+
+fn crypto_error(err: openssl::error::ErrorStack) -> ApiError {
+    eprintln!("Sealed bid crypto error: {:?}", err);
+    ApiError::new(ErrorCode::InternalError, "Failed to process sealed bid")
+}
+
+/// Encrypt a bid amount for `sealed_bids`: nonce, tag and ciphertext.
+pub fn seal_amount(amount: &BigDecimal, auction_id: i32, bidder_id: i32) -> Result<Vec<u8>, ApiError> {
+    let mut nonce = [0u8; NONCE_LEN];
+    rand::thread_rng().fill_bytes(&mut nonce);
+    let mut tag = [0u8; TAG_LEN];
+    let ciphertext = encrypt_aead(
+        Cipher::aes_256_gcm(),
+        &key()?,
+        Some(&nonce),
+        &associated_data(auction_id, bidder_id),
+        amount.to_string().as_bytes(),
+        &mut tag,
+    )
+    .map_err(crypto_error)?;
+
+    Ok([&nonce[..], &tag[..], &ciphertext[..]].concat())
+}
+
+/// Decrypt an amount written by [`seal_amount`] for the same auction and bidder.
+pub fn open_amount(sealed: &[u8], auction_id: i32, bidder_id: i32) -> Result<BigDecimal, ApiError> {
+    if sealed.len() < NONCE_LEN + TAG_LEN {
+        return Err(ApiError::new(ErrorCode::InternalError, "Sealed bid is corrupt"));
+    }
+    let (nonce, rest) = sealed.split_at(NONCE_LEN);
+    let (tag, ciphertext) = rest.split_at(TAG_LEN);
+    let plaintext = decrypt_aead(
+        Cipher::aes_256_gcm(),
+        &key()?,
+        Some(nonce),
+        &associated_data(auction_id, bidder_id),
+        ciphertext,
+        tag,
+    )
+    .map_err(crypto_error)?;
+
+    std::str::from_utf8(&plaintext)
+        .ok()
+        .and_then(|amount| BigDecimal::from_str(amount).ok())
+        .ok_or_else(|| ApiError::new(ErrorCode::InternalError, "Sealed bid is corrupt"))
+}
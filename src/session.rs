@@ -0,0 +1,91 @@
+use actix_web::HttpRequest;
+use redis::AsyncCommands;
+use sqlx::PgPool;
+use crate::errors::{ApiError, ErrorCode};
+
+/// Session lifetime in seconds (1 hour).
+pub const SESSION_TTL_SECONDS: u64 = 3600;
+
+/// The authenticated user behind a `Session-Code` header.
+pub struct SessionUser {
+    pub id: i32,
+    pub username: String,
+}
+
+/// Store a new session code for the user in Redis and return it.
+///
+/// Sessions hold the user id, so they survive a username change.
+pub async fn create_session(redis_client: &redis::Client, user_id: i32) -> Result<String, ApiError> {
+    let session_code = uuid::Uuid::new_v4().to_string();
+    let mut redis_conn = redis_client.get_multiplexed_async_connection().await?;
+    let _: () = redis_conn
+        .set_ex(format!("session:{}", session_code), user_id, SESSION_TTL_SECONDS)
+        .await?;
+
+    // Index the session under its user so all of them can be revoked at once
+    let index_key = format!("user_sessions:{}", user_id);
+    let _: () = redis_conn.sadd(&index_key, &session_code).await?;
+    let _: () = redis_conn.expire(&index_key, SESSION_TTL_SECONDS as i64).await?;
+    Ok(session_code)
+}
+
+/// Drop every session belonging to the user.
+pub async fn revoke_sessions(redis_client: &redis::Client, user_id: i32) -> Result<(), ApiError> {
+    let mut redis_conn = redis_client.get_multiplexed_async_connection().await?;
+    let index_key = format!("user_sessions:{}", user_id);
+    let session_codes: Vec<String> = redis_conn.smembers(&index_key).await?;
+
+    let mut keys: Vec<String> = session_codes
+        .iter()
+        .map(|code| format!("session:{}", code))
+        .collect();
+    keys.push(index_key);
+    let _: () = redis_conn.del(keys).await?;
+    Ok(())
+}
+
+/// Resolve the `Session-Code` header to an existing user.
+pub async fn require_user(
+    req: &HttpRequest,
+    pool: &PgPool,
+    redis_client: &redis::Client,
+) -> Result<SessionUser, ApiError> {
+    let session_code = req
+        .headers()
+        .get("Session-Code")
+        .and_then(|code| code.to_str().ok())
+        .ok_or_else(|| ApiError::new(ErrorCode::MissingSession, "Missing Session-Code header"))?;
+
+    let mut redis_conn = redis_client.get_multiplexed_async_connection().await?;
+    // Sessions created before they held user ids still hold a username; those are treated as expired
+    let user_id: Option<String> = redis_conn.get(format!("session:{}", session_code)).await?;
+    let user_id: i32 = user_id
+        .and_then(|user_id| user_id.parse().ok())
+        .ok_or_else(|| ApiError::new(ErrorCode::InvalidSession, "Invalid or expired session"))?;
+
+    sqlx::query_as!(
+        SessionUser,
+        "SELECT id, username FROM users WHERE id = $1 AND deleted_at IS NULL",
+        user_id
+    )
+    .fetch_optional(pool)
+    .await?
+    .ok_or_else(|| ApiError::new(ErrorCode::InvalidSession, "Username not exists"))
+}
+
+/// Like [`require_user`], but only for platform administrators.
+pub async fn require_admin(
+    req: &HttpRequest,
+    pool: &PgPool,
+    redis_client: &redis::Client,
+) -> Result<SessionUser, ApiError> {
+    let user = require_user(req, pool, redis_client).await?;
+    let is_admin = sqlx::query_scalar!("SELECT is_admin FROM users WHERE id = $1", user.id)
+        .fetch_one(pool)
+        .await?;
+
+    if !is_admin {
+        return Err(ApiError::new(ErrorCode::NotAdmin, "Administrator access required"));
+    }
+    Ok(user)
+}
@@ -0,0 +1,53 @@
+use async_trait::async_trait;
+use bytes::Bytes;
+use std::io::ErrorKind;
+use std::path::{Component, Path, PathBuf};
+use crate::errors::{ApiError, ErrorCode};
+use super::Storage;
+
+/// Stores objects as files below a root directory.
+pub struct LocalStorage {
+    root: PathBuf,
+}
+
+impl LocalStorage {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        LocalStorage { root: root.into() }
+    }
+
+    fn path(&self, key: &str) -> Result<PathBuf, ApiError> {
+        // Keys are generated server side, but never let one escape the root
+        let relative = Path::new(key);
+        if !relative.components().all(|c| matches!(c, Component::Normal(_))) {
+            return Err(ApiError::new(ErrorCode::StorageError, "Invalid storage key"));
+        }
+        Ok(self.root.join(relative))
+    }
+}
+
+#[async_trait]
+impl Storage for LocalStorage {
+    async fn put(&self, key: &str, _content_type: &str, data: Bytes) -> Result<(), ApiError> {
+        let path = self.path(key)?;
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        tokio::fs::write(path, data).await?;
+        Ok(())
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<Bytes>, ApiError> {
+        match tokio::fs::read(self.path(key)?).await {
+            Ok(data) => Ok(Some(Bytes::from(data))),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), ApiError> {
+        match tokio::fs::remove_file(self.path(key)?).await {
+            Err(err) if err.kind() != ErrorKind::NotFound => Err(err.into()),
+            _ => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,38 @@
+//! Binary file storage for uploaded media.
+//!
+//! Handlers depend on the [`Storage`] trait only; the backend is picked at startup
+//! from `STORAGE_BACKEND` (`local`, the default, or `s3`).
+
+mod local;
+mod s3;
+
+pub use local::LocalStorage;
+pub use s3::S3Storage;
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use std::sync::Arc;
+use crate::errors::ApiError;
+
+#[async_trait]
+pub trait Storage: Send + Sync {
+    /// Store `data` under `key`, replacing any existing object.
+    async fn put(&self, key: &str, content_type: &str, data: Bytes) -> Result<(), ApiError>;
+
+    /// Fetch the object under `key`, or `None` when it does not exist.
+    async fn get(&self, key: &str) -> Result<Option<Bytes>, ApiError>;
+
+    /// Remove the object under `key`; missing objects are not an error.
+    async fn delete(&self, key: &str) -> Result<(), ApiError>;
+}
+
+/// Build the storage backend configured in the environment.
+pub fn from_env() -> Arc<dyn Storage> {
+    match std::env::var("STORAGE_BACKEND").as_deref() {
+        Ok("s3") => Arc::new(S3Storage::from_env()),
+        _ => {
+            let dir = std::env::var("STORAGE_DIR").unwrap_or_else(|_| "uploads".to_string());
+            Arc::new(LocalStorage::new(dir))
+        }
+    }
+}
@@ -0,0 +1,153 @@
+use async_trait::async_trait;
+use bytes::Bytes;
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use reqwest::{Method, StatusCode, Url};
+use sha2::{Digest, Sha256};
+use crate::errors::{ApiError, ErrorCode};
+use super::Storage;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Stores objects in an S3-compatible bucket using path-style URLs and SigV4 signing.
+///
+/// Path-style addressing keeps it working against MinIO and other local stand-ins.
+pub struct S3Storage {
+    client: reqwest::Client,
+    endpoint: String,
+    bucket: String,
+    region: String,
+    access_key: String,
+    secret_key: String,
+}
+
+fn sha256_hex(data: &[u8]) -> String {
+    hex::encode(Sha256::digest(data))
+}
+
+fn hmac(key: &[u8], data: &str) -> Vec<u8> {
+    let mut mac = HmacSha256::new_from_slice(key).expect("HMAC accepts any key length");
+    mac.update(data.as_bytes());
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// Percent-encode a key for the request path, keeping `/` separators.
+fn encode_key(key: &str) -> String {
+    key.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+impl S3Storage {
+    pub fn new(endpoint: &str, bucket: &str, region: &str, access_key: &str, secret_key: &str) -> Self {
+        S3Storage {
+            client: reqwest::Client::new(),
+            endpoint: endpoint.trim_end_matches('/').to_string(),
+            bucket: bucket.to_string(),
+            region: region.to_string(),
+            access_key: access_key.to_string(),
+            secret_key: secret_key.to_string(),
+        }
+    }
+
+    pub fn from_env() -> Self {
+        let var = |name: &str| std::env::var(name).unwrap_or_else(|_| panic!("{} must be set", name));
+        S3Storage::new(
+            &var("S3_ENDPOINT"),
+            &var("S3_BUCKET"),
+            &std::env::var("S3_REGION").unwrap_or_else(|_| "us-east-1".to_string()),
+            &var("S3_ACCESS_KEY"),
+            &var("S3_SECRET_KEY"),
+        )
+    }
+
+    async fn send(&self, method: Method, key: &str, content_type: Option<&str>, body: Bytes) -> Result<reqwest::Response, ApiError> {
+        let path = format!("/{}/{}", self.bucket, encode_key(key));
+        let url = Url::parse(&format!("{}{}", self.endpoint, path))
+            .map_err(|_| ApiError::new(ErrorCode::StorageError, "Invalid S3 endpoint"))?;
+        let host = match (url.host_str(), url.port()) {
+            (Some(host), Some(port)) => format!("{}:{}", host, port),
+            (Some(host), None) => host.to_string(),
+            _ => return Err(ApiError::new(ErrorCode::StorageError, "Invalid S3 endpoint")),
+        };
+
+        let now = Utc::now();
+        let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let date = now.format("%Y%m%d").to_string();
+        let payload_hash = sha256_hex(&body);
+
+        let signed_headers = "host;x-amz-content-sha256;x-amz-date";
+        let canonical_request = format!(
+            "{}\n{}\n\nhost:{}\nx-amz-content-sha256:{}\nx-amz-date:{}\n\n{}\n{}",
+            method, path, host, payload_hash, amz_date, signed_headers, payload_hash
+        );
+        let scope = format!("{}/{}/s3/aws4_request", date, self.region);
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+            amz_date,
+            scope,
+            sha256_hex(canonical_request.as_bytes())
+        );
+
+        let signing_key = ["s3", "aws4_request"].iter().fold(
+            hmac(&hmac(format!("AWS4{}", self.secret_key).as_bytes(), &date), &self.region),
+            |key, part| hmac(&key, part),
+        );
+        let signature = hex::encode(hmac(&signing_key, &string_to_sign));
+
+        let mut request = self
+            .client
+            .request(method, url)
+            .header("x-amz-date", amz_date)
+            .header("x-amz-content-sha256", payload_hash)
+            .header(
+                "authorization",
+                format!(
+                    "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+                    self.access_key, scope, signed_headers, signature
+                ),
+            );
+        if let Some(content_type) = content_type {
+            request = request.header("content-type", content_type);
+        }
+
+        Ok(request.body(body).send().await?)
+    }
+}
+
+fn unexpected_status(status: StatusCode) -> ApiError {
+    eprintln!("S3 error: unexpected status {}", status);
+    ApiError::new(ErrorCode::StorageError, "File storage error")
+}
+
+#[async_trait]
+impl Storage for S3Storage {
+    async fn put(&self, key: &str, content_type: &str, data: Bytes) -> Result<(), ApiError> {
+        let response = self.send(Method::PUT, key, Some(content_type), data).await?;
+        if !response.status().is_success() {
+            return Err(unexpected_status(response.status()));
+        }
+        Ok(())
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<Bytes>, ApiError> {
+        let response = self.send(Method::GET, key, None, Bytes::new()).await?;
+        match response.status() {
+            StatusCode::NOT_FOUND => Ok(None),
+            status if status.is_success() => Ok(Some(response.bytes().await?)),
+            status => Err(unexpected_status(status)),
+        }
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), ApiError> {
+        let response = self.send(Method::DELETE, key, None, Bytes::new()).await?;
+        match response.status() {
+            StatusCode::NOT_FOUND => Ok(()),
+            status if status.is_success() => Ok(()),
+            status => Err(unexpected_status(status)),
+        }
+    }
+}
@@ -0,0 +1,141 @@
+//! VIN validation and offline decoding.
+//!
+//! Vehicles built from 1981 carry a 17 character ISO 3779 VIN with a check digit in
+//! position 9. Older vehicles only have a manufacturer chassis number, which is
+//! accepted with relaxed rules and never decoded.
+
+use chrono::{Datelike, Utc};
+use serde::{Deserialize, Serialize};
+
+/// World manufacturer identifiers (VIN positions 1-3).
+const WMI_TABLE: &str = include_str!("wmi.csv");
+/// Assembly plant codes (VIN position 11) per manufacturer identifier.
+const PLANT_TABLE: &str = include_str!("plants.csv");
+
+/// First model year required to carry a standardized 17 character VIN.
+pub const STANDARD_VIN_YEAR: i16 = 1981;
+
+const TRANSLITERATION: &str = "0123456789.ABCDEFGH..JKLMN.P.R..STUVWXYZ";
+const WEIGHTS: [u32; 17] = [8, 7, 6, 5, 4, 3, 2, 10, 0, 9, 8, 7, 6, 5, 4, 3, 2];
+const MODEL_YEAR_CODES: &str = "ABCDEFGHJKLMNPRSTVWXY123456789";
+
+/// What could be read from a standard VIN without any network lookups.
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct VinInfo {
+    pub vin: String,
+    pub wmi: String,
+    pub manufacturer: Option<String>,
+    pub model_year: Option<i16>,
+    pub plant_code: String,
+    pub plant: Option<String>,
+}
+
+/// Trim and upper case a VIN or chassis number before validating or storing it.
+pub fn normalize(raw: &str) -> String {
+    raw.trim().to_ascii_uppercase()
+}
+
+fn is_standard_form(vin: &str) -> bool {
+    vin.len() == 17 && vin.chars().all(|c| c.is_ascii_alphanumeric() && !matches!(c, 'I' | 'O' | 'Q'))
+}
+
+fn transliterate(c: char) -> Option<u32> {
+    c.to_digit(10).or_else(|| {
+        TRANSLITERATION
+            .find(c)
+            .filter(|_| c.is_ascii_uppercase())
+            .map(|index| index as u32 % 10)
+    })
+}
+
+/// The expected check digit for a 17 character VIN, `X` standing for 10.
+pub fn check_digit(vin: &str) -> Option<char> {
+    if !is_standard_form(vin) {
+        return None;
+    }
+
+    let mut sum = 0;
+    for (c, weight) in vin.chars().zip(WEIGHTS) {
+        sum += transliterate(c)? * weight;
+    }
+    match sum % 11 {
+        10 => Some('X'),
+        digit => char::from_digit(digit, 10),
+    }
+}
+
+/// Validate a normalized VIN against the declared model year.
+///
+/// Standard VINs must pass the check digit. Pre-1981 vehicles may instead carry a
+/// 4 to 20 character chassis number with `.`, `-`, `/` or space separators.
+pub fn validate(vin: &str, declared_year: Option<i16>) -> Result<(), &'static str> {
+    if declared_year.is_some_and(|year| year < STANDARD_VIN_YEAR) {
+        let valid = (4..=20).contains(&vin.len())
+            && vin.chars().any(|c| c.is_ascii_alphanumeric())
+            && vin.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '/' | ' '));
+        return if valid {
+            Ok(())
+        } else {
+            Err("must be a chassis number of 4 to 20 letters, digits or separators")
+        };
+    }
+
+    if !is_standard_form(vin) {
+        return Err("must be 17 letters or digits excluding I, O and Q (give a pre-1981 year for older chassis numbers)");
+    }
+    if check_digit(vin) != vin.chars().nth(8) {
+        return Err("has an invalid check digit");
+    }
+    Ok(())
+}
+
+fn lookup_manufacturer(wmi: &str) -> Option<String> {
+    WMI_TABLE
+        .lines()
+        .skip(1)
+        .filter_map(|line| line.split_once(','))
+        .find(|(code, _)| *code == wmi)
+        .map(|(_, manufacturer)| manufacturer.to_string())
+}
+
+fn lookup_plant(wmi: &str, code: &str) -> Option<String> {
+    PLANT_TABLE
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let mut columns = line.splitn(3, ',');
+            Some((columns.next()?, columns.next()?, columns.next()?))
+        })
+        .find(|(plant_wmi, plant_code, _)| *plant_wmi == wmi && *plant_code == code)
+        .map(|(_, _, plant)| plant.to_string())
+}
+
+/// Position 10 cycles every 30 years; a letter in position 7 marks the 2010-2039 cycle.
+fn decode_model_year(vin: &str) -> Option<i16> {
+    let code = vin.chars().nth(9)?;
+    let offset = MODEL_YEAR_CODES.find(code)? as i16;
+    let later_cycle = vin.chars().nth(6).is_some_and(|c| c.is_ascii_alphabetic());
+    let year = if later_cycle { 2010 + offset } else { 1980 + offset };
+
+    // Manufacturers outside North America do not always follow the position 7 rule
+    let max_year = Utc::now().year() as i16 + 1;
+    Some(if year > max_year { year - 30 } else { year })
+}
+
+/// Decode manufacturer, model year and plant from a valid standard VIN.
+///
+/// Returns `None` for chassis numbers and anything failing validation.
+pub fn decode(vin: &str) -> Option<VinInfo> {
+    validate(vin, None).ok()?;
+
+    let wmi = &vin[0..3];
+    let plant_code = &vin[10..11];
+    Some(VinInfo {
+        vin: vin.to_string(),
+        wmi: wmi.to_string(),
+        manufacturer: lookup_manufacturer(wmi),
+        model_year: decode_model_year(vin),
+        plant_code: plant_code.to_string(),
+        plant: lookup_plant(wmi, plant_code),
+    })
+}
@@ -0,0 +1,279 @@
+use actix_web::{test, App, web};
+use redis::Client;
+use sqlx::PgPool;
+use argon2::{password_hash::SaltString, Argon2, PasswordHasher};
+use bigdecimal::BigDecimal;
+use chrono::{Duration, Utc};
+use serde_json::json;
+use vehicle_auctions::auction_state::AuctionStatus;
+use vehicle_auctions::routes::auction::{close_auction, create_auction, place_bid};
+use vehicle_auctions::routes::cancellation::{
+    cancel_auction, list_pending_cancellations, approve_cancellation, reject_cancellation,
+};
+use vehicle_auctions::routes::user::{get_public_profile, user_login};
+use vehicle_auctions::models::{
+    AuctionCancellation, AuctionSettlement, AuctionSummary, LoginResponse, PublicProfile,
+};
+use vehicle_auctions::errors::{ErrorBody, ErrorCode};
+
+#[actix_web::test]
+async fn test_seller_cancellation_rules() {
+    dotenv::dotenv().ok();
+    let database_url = std::env::var("DATABASE_URL").expect("DATABASE_URL must be set");
+    let pool = PgPool::connect(&database_url)
+        .await
+        .expect("Failed to connect to database");
+
+    let salt = SaltString::generate(&mut rand::thread_rng());
+    let hashed_password = Argon2::default()
+        .hash_password("password_cancel".as_bytes(), &salt)
+        .unwrap()
+        .to_string();
+
+    let mut user_ids = Vec::new();
+    for (username, is_admin) in [
+        ("testuser_cancel_seller", false),
+        ("testuser_cancel_bidder", false),
+        ("testuser_cancel_admin", true),
+    ] {
+        let user_id = sqlx::query_scalar!(
+            "INSERT INTO users (username, password, is_admin) VALUES ($1, $2, $3) RETURNING id",
+            username,
+            hashed_password,
+            is_admin
+        ).fetch_one(&pool)
+        .await
+        .unwrap();
+        user_ids.push(user_id);
+    }
+    let (seller_id, bidder_id) = (user_ids[0], user_ids[1]);
+
+    let mut vehicle_ids = Vec::new();
+    for name in ["Alvis TD21", "Bristol 401", "Lea-Francis 14", "Healey Silverstone", "Jowett Jupiter"] {
+        let vehicle_id = sqlx::query_scalar!(
+            "INSERT INTO vehicles (name, description, starting_price, owner_id) VALUES ($1, '', 10000, $2) RETURNING id",
+            name,
+            seller_id
+        ).fetch_one(&pool)
+        .await
+        .unwrap();
+        vehicle_ids.push(vehicle_id);
+    }
+
+    let redis_client_url = std::env::var("REDIS_URL").expect("REDIS_URL must be set");
+    let redis_client = Client::open(redis_client_url).expect("Failed to connect to Redis");
+
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(pool.clone()))
+            .app_data(web::Data::new(redis_client.clone()))
+            .route("/login", web::post().to(user_login))
+            .route("/auctions/create", web::post().to(create_auction))
+            .route("/auctions/bid", web::post().to(place_bid))
+            .route("/auctions/close/{id}", web::post().to(close_auction))
+            .route("/auctions/{id}/cancel", web::post().to(cancel_auction))
+            .route("/admin/cancellations", web::get().to(list_pending_cancellations))
+            .route("/admin/cancellations/{id}/approve", web::post().to(approve_cancellation))
+            .route("/admin/cancellations/{id}/reject", web::post().to(reject_cancellation))
+            .route("/users/{username}", web::get().to(get_public_profile)),
+    )
+    .await;
+
+    let login = |username: &str| {
+        test::TestRequest::post()
+            .uri("/login")
+            .set_json(json!({ "username": username, "password": "password_cancel" }))
+            .to_request()
+    };
+    let seller: LoginResponse = test::call_and_read_body_json(&app, login("testuser_cancel_seller")).await;
+    let bidder: LoginResponse = test::call_and_read_body_json(&app, login("testuser_cancel_bidder")).await;
+    let admin: LoginResponse = test::call_and_read_body_json(&app, login("testuser_cancel_admin")).await;
+
+    let mut auctions = Vec::new();
+    for vehicle_id in &vehicle_ids {
+        let req = test::TestRequest::post()
+            .uri("/auctions/create")
+            .insert_header(("Session-Code", seller.session_code.clone()))
+            .set_json(json!({
+                "vehicle_id": vehicle_id,
+                "starting_price": 10000,
+                "end_time": (Utc::now() + Duration::days(7)).format("%Y-%m-%dT%H:%M:%S").to_string(),
+            }))
+            .to_request();
+        let auction: AuctionSummary = test::call_and_read_body_json(&app, req).await;
+        auctions.push(auction.id);
+    }
+    for auction_id in &auctions[1..] {
+        let req = test::TestRequest::post()
+            .uri("/auctions/bid")
+            .insert_header(("Session-Code", bidder.session_code.clone()))
+            .set_json(json!({ "auction_id": auction_id, "bid_amount": 12345 }))
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 200);
+    }
+
+    let cancel = |session: &str, auction_id: i32, body: serde_json::Value| {
+        test::TestRequest::post()
+            .uri(&format!("/auctions/{}/cancel", auction_id))
+            .insert_header(("Session-Code", session.to_string()))
+            .set_json(body)
+            .to_request()
+    };
+    let status_of = |auction_id: i32| {
+        let pool = pool.clone();
+        async move {
+            sqlx::query_scalar!(r#"SELECT status as "status: AuctionStatus" FROM auctions WHERE id = $1"#, auction_id)
+                .fetch_one(&pool)
+                .await
+                .unwrap()
+        }
+    };
+
+    let resp = test::call_service(&app, cancel(&seller.session_code, auctions[0], json!({ "reason": " " }))).await;
+    let body: ErrorBody = test::read_body_json(resp).await;
+    assert_eq!(body.details[0].field, "reason");
+    let body: ErrorBody = test::call_and_read_body_json(
+        &app,
+        cancel(&bidder.session_code, auctions[0], json!({ "reason": "Mine now" })),
+    )
+    .await;
+    assert_eq!(body.code, ErrorCode::NotOwner);
+
+    // No bids yet: the seller withdraws for free
+    let cancellation: AuctionCancellation = test::call_and_read_body_json(
+        &app,
+        cancel(&seller.session_code, auctions[0], json!({ "reason": "Sold privately" })),
+    )
+    .await;
+    assert_eq!(cancellation.status, "completed");
+    assert_eq!(cancellation.fee, None);
+    assert_eq!(status_of(auctions[0]).await, AuctionStatus::Cancelled);
+
+    // With bids the seller may pay a share of the highest bid
+    let cancellation: AuctionCancellation = test::call_and_read_body_json(
+        &app,
+        cancel(&seller.session_code, auctions[1], json!({ "reason": "Engine failure", "pay_fee": true })),
+    )
+    .await;
+    assert_eq!(cancellation.status, "completed");
+    assert_eq!(cancellation.fee, Some("1234.50".parse::<BigDecimal>().unwrap()));
+    assert_eq!(status_of(auctions[1]).await, AuctionStatus::Cancelled);
+
+    // ...or ask an administrator
+    for auction_id in [auctions[2], auctions[3]] {
+        let pending: AuctionCancellation = test::call_and_read_body_json(
+            &app,
+            cancel(&seller.session_code, auction_id, json!({ "reason": "Title problem" })),
+        )
+        .await;
+        assert_eq!(pending.status, "pending");
+        assert_eq!(status_of(auction_id).await, AuctionStatus::Live);
+    }
+    let body: ErrorBody = test::call_and_read_body_json(
+        &app,
+        cancel(&seller.session_code, auctions[2], json!({ "reason": "Please" })),
+    )
+    .await;
+    assert_eq!(body.code, ErrorCode::CancellationPending);
+
+    let req = test::TestRequest::get()
+        .uri("/admin/cancellations")
+        .insert_header(("Session-Code", seller.session_code.clone()))
+        .to_request();
+    let body: ErrorBody = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body.code, ErrorCode::NotAdmin);
+    let req = test::TestRequest::get()
+        .uri("/admin/cancellations")
+        .insert_header(("Session-Code", admin.session_code.clone()))
+        .to_request();
+    let pending: Vec<AuctionCancellation> = test::call_and_read_body_json(&app, req).await;
+    let pending: Vec<_> = pending.into_iter().filter(|c| c.requested_by == "testuser_cancel_seller").collect();
+    assert_eq!(pending.len(), 2);
+
+    let decide = |cancellation_id: i32, decision: &str| {
+        test::TestRequest::post()
+            .uri(&format!("/admin/cancellations/{}/{}", cancellation_id, decision))
+            .insert_header(("Session-Code", admin.session_code.clone()))
+            .to_request()
+    };
+    let approved: AuctionCancellation = test::call_and_read_body_json(&app, decide(pending[0].id, "approve")).await;
+    assert_eq!(approved.status, "approved");
+    assert!(approved.decided_at.is_some());
+    assert_eq!(status_of(auctions[2]).await, AuctionStatus::Cancelled);
+    let body: ErrorBody = test::call_and_read_body_json(&app, decide(pending[0].id, "reject")).await;
+    assert_eq!(body.code, ErrorCode::CancellationNotFound);
+
+    let rejected: AuctionCancellation = test::call_and_read_body_json(&app, decide(pending[1].id, "reject")).await;
+    assert_eq!(rejected.status, "rejected");
+    assert_eq!(status_of(auctions[3]).await, AuctionStatus::Live);
+
+    // Never in the final hour, even with a fee
+    sqlx::query!("UPDATE auctions SET end_time = NOW() + INTERVAL '30 minutes' WHERE id = $1", auctions[3])
+        .execute(&pool)
+        .await
+        .unwrap();
+    let body: ErrorBody = test::call_and_read_body_json(
+        &app,
+        cancel(&seller.session_code, auctions[3], json!({ "reason": "Changed my mind", "pay_fee": true })),
+    )
+    .await;
+    assert_eq!(body.code, ErrorCode::CancellationWindowClosed);
+
+    let notified = sqlx::query_scalar!(
+        "SELECT (payload->>'auction_id')::INT FROM notifications
+         WHERE user_id = $1 AND kind = 'auction_cancelled' ORDER BY id",
+        bidder_id
+    )
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+    assert_eq!(notified, vec![Some(auctions[1]), Some(auctions[2])]);
+
+    let reason = sqlx::query_scalar!(
+        "SELECT reason FROM auction_transitions WHERE auction_id = $1 AND to_status = 'cancelled'",
+        auctions[0]
+    )
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!(reason.as_deref(), Some("Sold privately"));
+
+    let decisions = sqlx::query_scalar!(
+        "SELECT COUNT(*) FROM notifications WHERE user_id = $1 AND kind = 'auction_cancellation_decided'",
+        seller_id
+    )
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!(decisions, Some(2));
+
+    // Withdrawn auctions are closed, but they are not sales
+    let req = test::TestRequest::get().uri("/users/testuser_cancel_seller").to_request();
+    let page: PublicProfile = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(page.sales_count, 0);
+
+    // A request nobody decided on expires when the auction ends, and can no longer be approved
+    let pending: AuctionCancellation = test::call_and_read_body_json(
+        &app,
+        cancel(&seller.session_code, auctions[4], json!({ "reason": "Buyer abroad" })),
+    )
+    .await;
+    sqlx::query!("UPDATE auctions SET end_time = NOW() - INTERVAL '1 minute' WHERE id = $1", auctions[4])
+        .execute(&pool)
+        .await
+        .unwrap();
+    let req = test::TestRequest::post()
+        .uri(&format!("/auctions/close/{}", auctions[4]))
+        .insert_header(("Session-Code", seller.session_code.clone()))
+        .to_request();
+    let settlement: AuctionSettlement = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(settlement.status, AuctionStatus::Sold);
+    let status = sqlx::query_scalar!("SELECT status FROM auction_cancellations WHERE id = $1", pending.id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(status, "expired");
+    let body: ErrorBody = test::call_and_read_body_json(&app, decide(pending.id, "approve")).await;
+    assert_eq!(body.code, ErrorCode::CancellationNotFound);
+    assert_eq!(status_of(auctions[4]).await, AuctionStatus::Sold);
+}
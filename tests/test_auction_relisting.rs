@@ -0,0 +1,186 @@
+use actix_web::{test, App, web};
+use redis::Client;
+use sqlx::PgPool;
+use argon2::{password_hash::SaltString, Argon2, PasswordHasher};
+use bigdecimal::BigDecimal;
+use chrono::{Duration, Utc};
+use serde_json::json;
+use vehicle_auctions::auction_state::AuctionStatus;
+use vehicle_auctions::routes::auction::{create_auction, place_bid, get_auction, relist_auction};
+use vehicle_auctions::routes::user::user_login;
+use vehicle_auctions::models::{AuctionDetail, AuctionSummary, LoginResponse, DEFAULT_RELIST_DAYS};
+use vehicle_auctions::errors::{ErrorBody, ErrorCode};
+
+fn timestamp(offset: Duration) -> String {
+    (Utc::now() + offset).format("%Y-%m-%dT%H:%M:%S").to_string()
+}
+
+#[actix_web::test]
+async fn test_relisting_unsold_auctions() {
+    dotenv::dotenv().ok();
+    let database_url = std::env::var("DATABASE_URL").expect("DATABASE_URL must be set");
+    let pool = PgPool::connect(&database_url)
+        .await
+        .expect("Failed to connect to database");
+
+    let salt = SaltString::generate(&mut rand::thread_rng());
+    let hashed_password = Argon2::default()
+        .hash_password("password_relist".as_bytes(), &salt)
+        .unwrap()
+        .to_string();
+
+    let seller_id = sqlx::query_scalar!(
+        "INSERT INTO users (username, password) VALUES ($1, $2) RETURNING id",
+        "testuser_relist_seller",
+        hashed_password
+    ).fetch_one(&pool)
+    .await
+    .unwrap();
+    sqlx::query!(
+        "INSERT INTO users (username, password) VALUES ($1, $2)",
+        "testuser_relist_bidder",
+        hashed_password
+    ).execute(&pool)
+    .await
+    .unwrap();
+    let vehicle_id = sqlx::query_scalar!(
+        "INSERT INTO vehicles (name, description, starting_price, owner_id) VALUES ('Reliant Scimitar GTE', '', 9000, $1)
+         RETURNING id",
+        seller_id
+    ).fetch_one(&pool)
+    .await
+    .unwrap();
+
+    let redis_client_url = std::env::var("REDIS_URL").expect("REDIS_URL must be set");
+    let redis_client = Client::open(redis_client_url).expect("Failed to connect to Redis");
+
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(pool.clone()))
+            .app_data(web::Data::new(redis_client.clone()))
+            .route("/login", web::post().to(user_login))
+            .route("/auctions/create", web::post().to(create_auction))
+            .route("/auctions/bid", web::post().to(place_bid))
+            .route("/auctions/{id}", web::get().to(get_auction))
+            .route("/auctions/{id}/relist", web::post().to(relist_auction)),
+    )
+    .await;
+
+    let login = |username: &str| {
+        test::TestRequest::post()
+            .uri("/login")
+            .set_json(json!({ "username": username, "password": "password_relist" }))
+            .to_request()
+    };
+    let seller: LoginResponse = test::call_and_read_body_json(&app, login("testuser_relist_seller")).await;
+    let bidder: LoginResponse = test::call_and_read_body_json(&app, login("testuser_relist_bidder")).await;
+
+    let req = test::TestRequest::post()
+        .uri("/auctions/create")
+        .insert_header(("Session-Code", seller.session_code.clone()))
+        .set_json(json!({
+            "vehicle_id": vehicle_id,
+            "starting_price": 9000,
+            "end_time": timestamp(Duration::days(5)),
+            "min_buyer_rating": 3,
+        }))
+        .to_request();
+    let first: AuctionSummary = test::call_and_read_body_json(&app, req).await;
+
+    let relist = |session: &str, auction_id: i32, body: serde_json::Value| {
+        test::TestRequest::post()
+            .uri(&format!("/auctions/{}/relist", auction_id))
+            .insert_header(("Session-Code", session.to_string()))
+            .set_json(body)
+            .to_request()
+    };
+
+    let body: ErrorBody = test::call_and_read_body_json(&app, relist(&seller.session_code, first.id, json!({}))).await;
+    assert_eq!(body.code, ErrorCode::AuctionNotRelistable);
+
+    // The end time passes without a bid
+    sqlx::query!(
+        "UPDATE auctions SET start_time = NOW() - INTERVAL '5 days 1 minute', end_time = NOW() - INTERVAL '1 minute'
+         WHERE id = $1",
+        first.id
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let body: ErrorBody = test::call_and_read_body_json(&app, relist(&bidder.session_code, first.id, json!({}))).await;
+    assert_eq!(body.code, ErrorCode::NotOwner);
+    let resp = test::call_service(
+        &app,
+        relist(&seller.session_code, first.id, json!({ "end_time": timestamp(-Duration::hours(1)) })),
+    )
+    .await;
+    let body: ErrorBody = test::read_body_json(resp).await;
+    assert_eq!(body.details[0].field, "end_time");
+
+    // Same terms, running as long as the original did
+    let second: AuctionSummary =
+        test::call_and_read_body_json(&app, relist(&seller.session_code, first.id, json!({}))).await;
+    assert_eq!(second.status, AuctionStatus::Live);
+    assert_eq!(second.vehicle_id, vehicle_id);
+    assert_eq!(second.starting_price, BigDecimal::from(9000));
+    let duration = second.end_time - second.start_time.unwrap();
+    assert!((duration - Duration::days(5)).num_seconds().abs() < 60);
+
+    let body: ErrorBody = test::call_and_read_body_json(&app, relist(&seller.session_code, first.id, json!({}))).await;
+    assert_eq!(body.code, ErrorCode::AuctionNotRelistable);
+
+    // Without a usable start the original's length is unknown, so the relist runs for the default
+    sqlx::query!(
+        "UPDATE auctions SET status = 'ended_unsold', start_time = end_time, created_at = NULL WHERE id = $1",
+        second.id
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+    let third: AuctionSummary =
+        test::call_and_read_body_json(&app, relist(&seller.session_code, second.id, json!({ "starting_price": 7500 })))
+            .await;
+    assert_eq!(third.starting_price, BigDecimal::from(7500));
+    let duration = third.end_time - third.start_time.unwrap();
+    assert!((duration - Duration::days(DEFAULT_RELIST_DAYS)).num_seconds().abs() < 60);
+
+    // An auction with bids is not unsold
+    let req = test::TestRequest::post()
+        .uri("/auctions/bid")
+        .insert_header(("Session-Code", bidder.session_code.clone()))
+        .set_json(json!({ "auction_id": third.id, "bid_amount": 7500 }))
+        .to_request();
+    let body: ErrorBody = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body.code, ErrorCode::BuyerRatingTooLow);
+    sqlx::query!("INSERT INTO bids (auction_id, bid_amount, bidder_id) VALUES ($1, 7500, $2)", third.id, seller_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+    sqlx::query!("UPDATE auctions SET end_time = NOW() - INTERVAL '1 minute' WHERE id = $1", third.id)
+        .execute(&pool)
+        .await
+        .unwrap();
+    let body: ErrorBody = test::call_and_read_body_json(&app, relist(&seller.session_code, third.id, json!({}))).await;
+    assert_eq!(body.code, ErrorCode::AuctionNotRelistable);
+
+    // Every listing shows the whole chain
+    for auction_id in [first.id, third.id] {
+        let req = test::TestRequest::get().uri(&format!("/auctions/{}", auction_id)).to_request();
+        let detail: AuctionDetail = test::call_and_read_body_json(&app, req).await;
+        let chain: Vec<_> = detail.relist_chain.iter().map(|listing| (listing.id, listing.status)).collect();
+        assert_eq!(
+            chain,
+            vec![
+                (first.id, AuctionStatus::EndedUnsold),
+                (second.id, AuctionStatus::EndedUnsold),
+                (third.id, AuctionStatus::Live),
+            ]
+        );
+    }
+    let req = test::TestRequest::get().uri(&format!("/auctions/{}", third.id)).to_request();
+    let detail: AuctionDetail = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(detail.relisted_from, Some(second.id));
+    assert_eq!(detail.min_buyer_rating, Some(BigDecimal::from(3)));
+    assert_eq!(detail.relist_chain[2].bid_count, 1);
+}
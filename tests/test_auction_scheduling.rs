@@ -0,0 +1,211 @@
+use actix_web::{test, App, web};
+use redis::Client;
+use sqlx::postgres::PgPoolOptions;
+use sqlx::{Executor, PgPool};
+use argon2::{password_hash::SaltString, Argon2, PasswordHasher};
+use chrono::{Duration, Utc};
+use serde_json::json;
+use vehicle_auctions::routes::auction::{
+    create_auction, place_bid, get_auction, preview_auction, update_auction, publish_auction, delete_draft_auction,
+    start_scheduled_auctions,
+};
+use vehicle_auctions::routes::user::user_login;
+use vehicle_auctions::auction_state::AuctionStatus;
+use vehicle_auctions::models::{AuctionDetail, AuctionSummary, LoginResponse};
+use vehicle_auctions::errors::{ErrorBody, ErrorCode};
+
+fn timestamp(offset: Duration) -> String {
+    (Utc::now() + offset).format("%Y-%m-%dT%H:%M:%S").to_string()
+}
+
+#[actix_web::test]
+async fn test_draft_scheduled_and_live_auctions() {
+    dotenv::dotenv().ok();
+    let database_url = std::env::var("DATABASE_URL").expect("DATABASE_URL must be set");
+    let pool = PgPool::connect(&database_url)
+        .await
+        .expect("Failed to connect to database");
+
+    let salt = SaltString::generate(&mut rand::thread_rng());
+    let hashed_password = Argon2::default()
+        .hash_password("password_schedule".as_bytes(), &salt)
+        .unwrap()
+        .to_string();
+
+    let seller_id = sqlx::query_scalar!(
+        "INSERT INTO users (username, password) VALUES ($1, $2) RETURNING id",
+        "testuser_schedule_seller",
+        hashed_password
+    ).fetch_one(&pool)
+    .await
+    .unwrap();
+    sqlx::query!(
+        "INSERT INTO users (username, password) VALUES ($1, $2)",
+        "testuser_schedule_bidder",
+        hashed_password
+    ).execute(&pool)
+    .await
+    .unwrap();
+
+    let mut vehicle_ids = Vec::new();
+    for name in ["Lancia Fulvia Coupe", "Fiat Dino Spider"] {
+        let vehicle_id = sqlx::query_scalar!(
+            "INSERT INTO vehicles (name, description, starting_price, owner_id) VALUES ($1, '', 18000, $2) RETURNING id",
+            name,
+            seller_id
+        ).fetch_one(&pool)
+        .await
+        .unwrap();
+        vehicle_ids.push(vehicle_id);
+    }
+
+    let redis_client_url = std::env::var("REDIS_URL").expect("REDIS_URL must be set");
+    let redis_client = Client::open(redis_client_url).expect("Failed to connect to Redis");
+
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(pool.clone()))
+            .app_data(web::Data::new(redis_client.clone()))
+            .route("/login", web::post().to(user_login))
+            .route("/auctions/create", web::post().to(create_auction))
+            .route("/auctions/bid", web::post().to(place_bid))
+            .route("/auctions/{id}", web::get().to(get_auction))
+            .route("/auctions/{id}", web::patch().to(update_auction))
+            .route("/auctions/{id}", web::delete().to(delete_draft_auction))
+            .route("/auctions/{id}/preview", web::get().to(preview_auction))
+            .route("/auctions/{id}/publish", web::post().to(publish_auction)),
+    )
+    .await;
+
+    let login = |username: &str| {
+        test::TestRequest::post()
+            .uri("/login")
+            .set_json(json!({ "username": username, "password": "password_schedule" }))
+            .to_request()
+    };
+    let seller: LoginResponse = test::call_and_read_body_json(&app, login("testuser_schedule_seller")).await;
+    let bidder: LoginResponse = test::call_and_read_body_json(&app, login("testuser_schedule_bidder")).await;
+
+    let create = |body: serde_json::Value| {
+        test::TestRequest::post()
+            .uri("/auctions/create")
+            .insert_header(("Session-Code", seller.session_code.clone()))
+            .set_json(body)
+            .to_request()
+    };
+    let bid = |auction_id: i32| {
+        test::TestRequest::post()
+            .uri("/auctions/bid")
+            .insert_header(("Session-Code", bidder.session_code.clone()))
+            .set_json(json!({ "auction_id": auction_id, "bid_amount": 20000 }))
+            .to_request()
+    };
+    let as_seller = |request: test::TestRequest| request.insert_header(("Session-Code", seller.session_code.clone()));
+
+    let resp = test::call_service(&app, create(json!({
+        "vehicle_id": vehicle_ids[0],
+        "starting_price": 18000,
+        "start_time": timestamp(Duration::days(3)),
+        "end_time": timestamp(Duration::days(2)),
+    }))).await;
+    let body: ErrorBody = test::read_body_json(resp).await;
+    assert_eq!(body.details[0].field, "start_time");
+
+    let draft: AuctionSummary = test::call_and_read_body_json(&app, create(json!({
+        "vehicle_id": vehicle_ids[0],
+        "starting_price": 18000,
+        "end_time": timestamp(Duration::days(7)),
+        "draft": true,
+    }))).await;
+    assert_eq!(draft.status, AuctionStatus::Draft);
+    assert_eq!(draft.start_time, None);
+
+    // Only the seller sees a draft, and nobody can bid on it
+    let req = test::TestRequest::get().uri(&format!("/auctions/{}", draft.id)).to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), 404);
+    let req = as_seller(test::TestRequest::get().uri(&format!("/auctions/{}/preview", draft.id))).to_request();
+    let preview: AuctionDetail = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(preview.vehicle_name, "Lancia Fulvia Coupe");
+    let req = test::TestRequest::get()
+        .uri(&format!("/auctions/{}/preview", draft.id))
+        .insert_header(("Session-Code", bidder.session_code.clone()))
+        .to_request();
+    let body: ErrorBody = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body.code, ErrorCode::NotOwner);
+    let body: ErrorBody = test::call_and_read_body_json(&app, bid(draft.id)).await;
+    assert_eq!(body.code, ErrorCode::AuctionNotFound);
+
+    let req = as_seller(test::TestRequest::patch().uri(&format!("/auctions/{}", draft.id)))
+        .set_json(json!({ "starting_price": 19000, "start_time": timestamp(Duration::hours(2)) }))
+        .to_request();
+    let updated: AuctionSummary = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(updated.starting_price, bigdecimal::BigDecimal::from(19000));
+
+    let req = as_seller(test::TestRequest::post().uri(&format!("/auctions/{}/publish", draft.id))).to_request();
+    let scheduled: AuctionSummary = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(scheduled.status, AuctionStatus::Scheduled);
+
+    let body: ErrorBody = test::call_and_read_body_json(&app, bid(draft.id)).await;
+    assert_eq!(body.code, ErrorCode::AuctionNotStarted);
+
+    let req = as_seller(test::TestRequest::patch().uri(&format!("/auctions/{}", draft.id)))
+        .set_json(json!({ "starting_price": 1 }))
+        .to_request();
+    let body: ErrorBody = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body.code, ErrorCode::AuctionNotDraft);
+
+    // Start times are UTC whatever the database session's time zone
+    let far_east_pool = PgPoolOptions::new()
+        .after_connect(|conn, _| Box::pin(async move {
+            conn.execute("SET TIME ZONE 'Pacific/Kiritimati'").await?;
+            Ok(())
+        }))
+        .connect(&database_url)
+        .await
+        .unwrap();
+    sqlx::query!(
+        "UPDATE auctions SET start_time = $2 WHERE id = $1",
+        draft.id,
+        Utc::now().naive_utc() + Duration::hours(2)
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+    start_scheduled_auctions(&far_east_pool).await.unwrap();
+    let req = test::TestRequest::get().uri(&format!("/auctions/{}", draft.id)).to_request();
+    let detail: AuctionDetail = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(detail.status, AuctionStatus::Scheduled);
+
+    // The start time comes and the scheduler opens bidding
+    sqlx::query!("UPDATE auctions SET start_time = NOW() - INTERVAL '1 minute' WHERE id = $1", draft.id)
+        .execute(&pool)
+        .await
+        .unwrap();
+    start_scheduled_auctions(&pool).await.unwrap();
+
+    let req = test::TestRequest::get().uri(&format!("/auctions/{}", draft.id)).to_request();
+    let detail: AuctionDetail = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(detail.status, AuctionStatus::Live);
+    let resp = test::call_service(&app, bid(draft.id)).await;
+    assert_eq!(resp.status(), 200);
+
+    // Without a start time an auction opens at once; a discarded draft frees the vehicle
+    let discarded: AuctionSummary = test::call_and_read_body_json(&app, create(json!({
+        "vehicle_id": vehicle_ids[1],
+        "starting_price": 30000,
+        "end_time": timestamp(Duration::days(7)),
+        "draft": true,
+    }))).await;
+    let req = as_seller(test::TestRequest::delete().uri(&format!("/auctions/{}", discarded.id))).to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), 204);
+
+    let live: AuctionSummary = test::call_and_read_body_json(&app, create(json!({
+        "vehicle_id": vehicle_ids[1],
+        "starting_price": 18000,
+        "end_time": timestamp(Duration::days(7)),
+    }))).await;
+    assert_eq!(live.status, AuctionStatus::Live);
+    assert!(live.start_time.is_some());
+    let resp = test::call_service(&app, bid(live.id)).await;
+    assert_eq!(resp.status(), 200);
+}
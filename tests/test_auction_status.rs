@@ -0,0 +1,182 @@
+use actix_web::{test, App, web};
+use redis::Client;
+use sqlx::PgPool;
+use argon2::{password_hash::SaltString, Argon2, PasswordHasher};
+use chrono::{Duration, Utc};
+use serde_json::json;
+use vehicle_auctions::auction_state::{transition, AuctionStatus};
+use vehicle_auctions::routes::auction::{create_auction, place_bid, close_auction, get_auction};
+use vehicle_auctions::routes::user::user_login;
+use vehicle_auctions::models::{AuctionDetail, AuctionSettlement, AuctionSummary, LoginResponse};
+use vehicle_auctions::errors::{ErrorBody, ErrorCode};
+
+#[actix_web::test]
+async fn test_auction_status_transitions_are_enforced_and_logged() {
+    dotenv::dotenv().ok();
+    let database_url = std::env::var("DATABASE_URL").expect("DATABASE_URL must be set");
+    let pool = PgPool::connect(&database_url)
+        .await
+        .expect("Failed to connect to database");
+
+    let salt = SaltString::generate(&mut rand::thread_rng());
+    let hashed_password = Argon2::default()
+        .hash_password("password_status".as_bytes(), &salt)
+        .unwrap()
+        .to_string();
+
+    let seller_id = sqlx::query_scalar!(
+        "INSERT INTO users (username, password) VALUES ($1, $2) RETURNING id",
+        "testuser_status_seller",
+        hashed_password
+    ).fetch_one(&pool)
+    .await
+    .unwrap();
+    let bidder_id = sqlx::query_scalar!(
+        "INSERT INTO users (username, password) VALUES ($1, $2) RETURNING id",
+        "testuser_status_bidder",
+        hashed_password
+    ).fetch_one(&pool)
+    .await
+    .unwrap();
+    let vehicle_id = sqlx::query_scalar!(
+        "INSERT INTO vehicles (name, description, starting_price, owner_id) VALUES ('Jensen Interceptor', '', 22000, $1)
+         RETURNING id",
+        seller_id
+    ).fetch_one(&pool)
+    .await
+    .unwrap();
+
+    let redis_client_url = std::env::var("REDIS_URL").expect("REDIS_URL must be set");
+    let redis_client = Client::open(redis_client_url).expect("Failed to connect to Redis");
+
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(pool.clone()))
+            .app_data(web::Data::new(redis_client.clone()))
+            .route("/login", web::post().to(user_login))
+            .route("/auctions/create", web::post().to(create_auction))
+            .route("/auctions/bid", web::post().to(place_bid))
+            .route("/auctions/close/{id}", web::post().to(close_auction))
+            .route("/auctions/{id}", web::get().to(get_auction)),
+    )
+    .await;
+
+    let login = |username: &str| {
+        test::TestRequest::post()
+            .uri("/login")
+            .set_json(json!({ "username": username, "password": "password_status" }))
+            .to_request()
+    };
+    let seller: LoginResponse = test::call_and_read_body_json(&app, login("testuser_status_seller")).await;
+    let bidder: LoginResponse = test::call_and_read_body_json(&app, login("testuser_status_bidder")).await;
+
+    let req = test::TestRequest::post()
+        .uri("/auctions/create")
+        .insert_header(("Session-Code", seller.session_code.clone()))
+        .set_json(json!({
+            "vehicle_id": vehicle_id,
+            "starting_price": 22000,
+            "end_time": (Utc::now() + Duration::days(7)).format("%Y-%m-%dT%H:%M:%S").to_string(),
+        }))
+        .to_request();
+    let auction: AuctionSummary = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(auction.status, AuctionStatus::Live);
+    assert!(!auction.closed);
+
+    let bid = || {
+        test::TestRequest::post()
+            .uri("/auctions/bid")
+            .insert_header(("Session-Code", bidder.session_code.clone()))
+            .set_json(json!({ "auction_id": auction.id, "bid_amount": 23000 }))
+            .to_request()
+    };
+    let resp = test::call_service(&app, bid()).await;
+    assert_eq!(resp.status(), 200);
+
+    let close = || {
+        test::TestRequest::post()
+            .uri(&format!("/auctions/close/{}", auction.id))
+            .insert_header(("Session-Code", seller.session_code.clone()))
+            .to_request()
+    };
+    let resp = test::call_service(&app, close()).await;
+    assert_eq!(resp.status(), 200);
+
+    let req = test::TestRequest::get().uri(&format!("/auctions/{}", auction.id)).to_request();
+    let detail: AuctionDetail = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(detail.status, AuctionStatus::Sold);
+    assert!(detail.closed);
+
+    let body: ErrorBody = test::call_and_read_body_json(&app, bid()).await;
+    assert_eq!(body.code, ErrorCode::AuctionClosed);
+
+    // A sale can be disputed, but never reopened
+    let mut tx = pool.begin().await.unwrap();
+    let err = transition(&mut tx, auction.id, AuctionStatus::Live, Some(seller_id), None).await.unwrap_err();
+    assert_eq!(err.code, ErrorCode::IllegalAuctionTransition);
+    let previous = transition(&mut tx, auction.id, AuctionStatus::Disputed, Some(bidder_id), Some("Not as described"))
+        .await
+        .unwrap();
+    assert_eq!(previous, AuctionStatus::Sold);
+    tx.commit().await.unwrap();
+
+    let log = sqlx::query!(
+        r#"SELECT from_status as "from_status: AuctionStatus", to_status as "to_status: AuctionStatus", actor_id, reason
+           FROM auction_transitions WHERE auction_id = $1 ORDER BY id"#,
+        auction.id
+    )
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+    let steps: Vec<_> = log.iter().map(|entry| (entry.from_status, entry.to_status)).collect();
+    assert_eq!(
+        steps,
+        vec![
+            (None, AuctionStatus::Draft),
+            (Some(AuctionStatus::Draft), AuctionStatus::Live),
+            (Some(AuctionStatus::Live), AuctionStatus::Sold),
+            (Some(AuctionStatus::Sold), AuctionStatus::Disputed),
+        ]
+    );
+    assert_eq!(log[2].actor_id, Some(seller_id));
+    assert_eq!(log[3].reason.as_deref(), Some("Not as described"));
+
+    // Without bids closing has to wait for the end time, after which the auction ended unsold
+    let vehicle_id = sqlx::query_scalar!(
+        "INSERT INTO vehicles (name, description, starting_price, owner_id) VALUES ('Jensen FF', '', 30000, $1)
+         RETURNING id",
+        seller_id
+    ).fetch_one(&pool)
+    .await
+    .unwrap();
+    let req = test::TestRequest::post()
+        .uri("/auctions/create")
+        .insert_header(("Session-Code", seller.session_code.clone()))
+        .set_json(json!({
+            "vehicle_id": vehicle_id,
+            "starting_price": 30000,
+            "end_time": (Utc::now() + Duration::days(7)).format("%Y-%m-%dT%H:%M:%S").to_string(),
+        }))
+        .to_request();
+    let unsold: AuctionSummary = test::call_and_read_body_json(&app, req).await;
+    let close = || {
+        test::TestRequest::post()
+            .uri(&format!("/auctions/close/{}", unsold.id))
+            .insert_header(("Session-Code", seller.session_code.clone()))
+            .to_request()
+    };
+    let body: ErrorBody = test::call_and_read_body_json(&app, close()).await;
+    assert_eq!(body.code, ErrorCode::NoBids);
+
+    sqlx::query!("UPDATE auctions SET end_time = NOW() - INTERVAL '1 minute' WHERE id = $1", unsold.id)
+        .execute(&pool)
+        .await
+        .unwrap();
+    let settlement: AuctionSettlement = test::call_and_read_body_json(&app, close()).await;
+    assert_eq!(settlement.status, AuctionStatus::EndedUnsold);
+    assert_eq!(settlement.winner_username, None);
+    let req = test::TestRequest::get().uri(&format!("/auctions/{}", unsold.id)).to_request();
+    let detail: AuctionDetail = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(detail.status, AuctionStatus::EndedUnsold);
+    assert!(detail.closed);
+}
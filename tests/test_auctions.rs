@@ -8,8 +8,8 @@ use serde_json::json;
 
 
 // Import the handlers and models
-use vehicle_auctions::{routes::{auction::{create_auction, place_bid, close_auction}, user::user_login, vehicle::{create_vehicle, list_vehicles}}, 
-    models::{CreateAuction, CreateVehicle, Vehicle, PlaceBid, Auction}}; // Replace `your_crate_name` with your actual crate name.
+use vehicle_auctions::{routes::{auction::{create_auction, place_bid, close_auction, get_auction}, rating::rate_auction, user::user_login, vehicle::{create_vehicle, list_vehicles}}, 
+    models::{CreateAuction, CreateVehicle, Vehicle, VehiclePage, PlaceBid, Auction, LoginResponse, AuctionSummary, BidReceipt, AuctionSettlement, AuctionDetail, Rating}}; // Replace `your_crate_name` with your actual crate name.
 
 #[actix_web::test]
 async fn test_create_auction() {
@@ -53,7 +53,9 @@ async fn test_create_auction() {
             .route("/list_vehicles", web::get().to(list_vehicles))
             .route("/create_auction", web::post().to(create_auction))
             .route("/place_bid", web::post().to(place_bid))
-            .route("/close/{id}", web::post().to(close_auction)),
+            .route("/close/{id}", web::post().to(close_auction))
+            .route("/auctions/{id}", web::get().to(get_auction))
+            .route("/auctions/{id}/rating", web::post().to(rate_auction)),
     )
     .await;
 
@@ -68,17 +70,15 @@ async fn test_create_auction() {
     println!("Response1: {:?}", resp);
     assert_eq!(resp.status(), 200);
 
-    let body: String = test::read_body_json(resp).await;
-    assert!(body.contains("Login successful"));
-
-    // Verify session in Redis
-    let session_key = format!("{}", body.split(':').last().unwrap().trim());
+    let body: LoginResponse = test::read_body_json(resp).await;
+    let session_key = body.session_code;
 
    // Mock request
    let form = CreateVehicle {
     name: "Test Vehicle".to_string(),
     description: "A vehicle for testing".to_string(),
     starting_price: 1000.01,
+    ..Default::default()
     };
 
     let req2 = test::TestRequest::post()
@@ -95,19 +95,19 @@ async fn test_create_auction() {
     // Assert response
     println!("Response2: {:?}", resp2);
     assert_eq!(resp2.status(), 200, "Expected HTTP 200 OK");
-    let body2 = test::read_body(resp2).await;
-    assert_eq!(body2, "Vehicle Created");
+    let created: Vehicle = test::read_body_json(resp2).await;
+    assert_eq!(created.name, "Test Vehicle");
 
-    let req4 = test::TestRequest::get().uri("/list_vehicles").to_request();
+    let req4 = test::TestRequest::get().uri("/list_vehicles?owner=test_user_auction").to_request();
     let resp4 = test::call_service(&app, req4).await;
 
     println!("Response4: {:?}", resp4);
     assert_eq!(resp4.status(), 200, "Expected 200 OK");
-    let vehicles: Vec<Vehicle> = test::read_body_json(resp4).await;
+    let page: VehiclePage = test::read_body_json(resp4).await;
 
-    let id_vehicle =vehicles[0].id;
+    let id_vehicle = page.items[0].id;
     println!("Vehicle ID: {:?}", id_vehicle);
-    let end_time_str = "2025-01-28 15:00:00";
+    let end_time_str = "2099-01-28 15:00:00";
     let end_time2 = NaiveDateTime::parse_from_str(end_time_str, "%Y-%m-%d %H:%M:%S")
         .expect("Failed to parse end_time");
     println!("End time: {:?}", end_time2);
@@ -116,7 +116,11 @@ async fn test_create_auction() {
     let create_auction_data = CreateAuction {
         vehicle_id: id_vehicle,
         starting_price: 1200.02,
-        end_time: end_time2
+        end_time: end_time2,
+        min_buyer_rating: None,
+        start_time: None,
+        draft: false,
+        auction_type: Default::default(),
     };
 
     // Send a test request
@@ -136,8 +140,9 @@ async fn test_create_auction() {
     // Assert the response
     println!("Response3: {:?}", resp3);
     assert_eq!(resp3.status(), 200, "Expected 200 OK");
-    let body3 = test::read_body(resp3).await;
-    assert_eq!(body3, "Auction Created");
+    let auction: AuctionSummary = test::read_body_json(resp3).await;
+    assert_eq!(auction.vehicle_id, id_vehicle);
+    assert!(!auction.closed);
 
     let salt2 = SaltString::generate(&mut rand::thread_rng());
     let hashed_password2 = Argon2::default()
@@ -162,11 +167,8 @@ async fn test_create_auction() {
     let resp5 = test::call_service(&app, req5).await;
     assert_eq!(resp5.status(), 200);
 
-    let body5: String = test::read_body_json(resp5).await;
-    assert!(body5.contains("Login successful"));
-
-    // Verify session in Redis
-    let session_key2 = format!("{}", body5.split(':').last().unwrap().trim());
+    let body5: LoginResponse = test::read_body_json(resp5).await;
+    let session_key2 = body5.session_code;
 
     // let id_auction:(i32, bool) = sqlx::query_as(
     //     "SELECT id, closed FROM auctions WHERE vehicle_id = $1 AND closed = FALSE"
@@ -214,8 +216,9 @@ async fn test_create_auction() {
     dbg!(&resp6); 
     assert_eq!(resp6.status(), StatusCode::OK);
 
-    let body6 = test::read_body(resp6).await;
-    assert_eq!(body6, "Bid Placed");
+    let bid: BidReceipt = test::read_body_json(resp6).await;
+    assert_eq!(bid.auction_id, id_auction.id);
+    assert_eq!(bid.bidder_username, "test_user_auction_2");
 
     // Send a test request
     let req7 = test::TestRequest::post()
@@ -232,6 +235,44 @@ async fn test_create_auction() {
     dbg!(&resp7); 
     assert_eq!(resp7.status(), StatusCode::OK);
 
-    let body7 = test::read_body(resp7).await;
-    assert_eq!(body7, "Auction Closed and Vehicle Ownership Transferred");
+    let settlement: AuctionSettlement = test::read_body_json(resp7).await;
+    assert_eq!(settlement.winner_username.as_deref(), Some("test_user_auction_2"));
+
+    // The vehicle now belongs to the buyer; as just sold it only shows up when sold vehicles are included
+    let req = test::TestRequest::get().uri("/list_vehicles?owner=test_user_auction_2").to_request();
+    let page: VehiclePage = test::call_and_read_body_json(&app, req).await;
+    assert!(page.items.iter().all(|vehicle| vehicle.id != settlement.vehicle_id));
+    let req = test::TestRequest::get()
+        .uri("/list_vehicles?owner=test_user_auction_2&include_sold=true")
+        .to_request();
+    let page: VehiclePage = test::call_and_read_body_json(&app, req).await;
+    assert!(page.items.iter().any(|vehicle| vehicle.id == settlement.vehicle_id));
+
+    // Both parties rate each other once
+    let req8 = test::TestRequest::post()
+        .uri(&format!("/auctions/{}/rating", id_auction.id))
+        .insert_header(("Session-Code", session_key2.as_str()))
+        .set_json(json!({ "score": 5, "comment": "Smooth handover" }))
+        .to_request();
+    let rating: Rating = test::call_and_read_body_json(&app, req8).await;
+    assert_eq!(rating.ratee_username, "test_user_auction");
+    assert_eq!(rating.ratee_role, "seller");
+
+    let req9 = test::TestRequest::post()
+        .uri(&format!("/auctions/{}/rating", id_auction.id))
+        .insert_header(("Session-Code", session_key2.as_str()))
+        .set_json(json!({ "score": 1 }))
+        .to_request();
+    let resp9 = test::call_service(&app, req9).await;
+    assert_eq!(resp9.status(), StatusCode::CONFLICT);
+
+    let req10 = test::TestRequest::get()
+        .uri(&format!("/auctions/{}", id_auction.id))
+        .to_request();
+    let detail: AuctionDetail = test::call_and_read_body_json(&app, req10).await;
+    assert!(detail.closed);
+    assert_eq!(detail.bid_count, 1);
+    let reputation = detail.seller_reputation.expect("seller reputation");
+    assert_eq!(reputation.rating_count, 1);
+    assert_eq!(reputation.seller_score, Some(5.0));
 }
@@ -0,0 +1,183 @@
+use actix_web::{test, App, web};
+use redis::Client;
+use sqlx::postgres::PgPoolOptions;
+use sqlx::{Executor, PgPool};
+use argon2::{password_hash::SaltString, Argon2, PasswordHasher};
+use bigdecimal::BigDecimal;
+use chrono::{Duration, Utc};
+use serde_json::json;
+use vehicle_auctions::routes::auction::{create_auction, place_bid, get_auction};
+use vehicle_auctions::routes::bid::{retract_bid, list_auction_bids};
+use vehicle_auctions::routes::user::{export_my_data, user_login};
+use vehicle_auctions::models::{
+    AuctionBid, AuctionDetail, AuctionSummary, BidReceipt, BidRetraction, LoginResponse, UserExport,
+};
+use vehicle_auctions::errors::{ErrorBody, ErrorCode};
+
+#[actix_web::test]
+async fn test_bid_retraction_policy() {
+    dotenv::dotenv().ok();
+    let database_url = std::env::var("DATABASE_URL").expect("DATABASE_URL must be set");
+    let pool = PgPool::connect(&database_url)
+        .await
+        .expect("Failed to connect to database");
+
+    let salt = SaltString::generate(&mut rand::thread_rng());
+    let hashed_password = Argon2::default()
+        .hash_password("password_retract".as_bytes(), &salt)
+        .unwrap()
+        .to_string();
+
+    let mut user_ids = Vec::new();
+    for username in ["testuser_retract_seller", "testuser_retract_bidder", "testuser_retract_rival"] {
+        let user_id = sqlx::query_scalar!(
+            "INSERT INTO users (username, password) VALUES ($1, $2) RETURNING id",
+            username,
+            hashed_password
+        ).fetch_one(&pool)
+        .await
+        .unwrap();
+        user_ids.push(user_id);
+    }
+    let vehicle_id = sqlx::query_scalar!(
+        "INSERT INTO vehicles (name, description, starting_price, owner_id) VALUES ('Gordon-Keeble GK1', '', 25000, $1)
+         RETURNING id",
+        user_ids[0]
+    ).fetch_one(&pool)
+    .await
+    .unwrap();
+
+    let redis_client_url = std::env::var("REDIS_URL").expect("REDIS_URL must be set");
+    let redis_client = Client::open(redis_client_url).expect("Failed to connect to Redis");
+
+    // Bid times are UTC whatever the database session's time zone
+    let far_east_pool = PgPoolOptions::new()
+        .after_connect(|conn, _| Box::pin(async move {
+            conn.execute("SET TIME ZONE 'Pacific/Kiritimati'").await?;
+            Ok(())
+        }))
+        .connect(&database_url)
+        .await
+        .unwrap();
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(far_east_pool))
+            .app_data(web::Data::new(redis_client.clone()))
+            .route("/login", web::post().to(user_login))
+            .route("/auctions/create", web::post().to(create_auction))
+            .route("/auctions/bid", web::post().to(place_bid))
+            .route("/auctions/bids/{id}/retract", web::post().to(retract_bid))
+            .route("/auctions/{id}", web::get().to(get_auction))
+            .route("/auctions/{id}/bids", web::get().to(list_auction_bids))
+            .route("/users/me/export", web::get().to(export_my_data)),
+    )
+    .await;
+
+    let login = |username: &str| {
+        test::TestRequest::post()
+            .uri("/login")
+            .set_json(json!({ "username": username, "password": "password_retract" }))
+            .to_request()
+    };
+    let seller: LoginResponse = test::call_and_read_body_json(&app, login("testuser_retract_seller")).await;
+    let bidder: LoginResponse = test::call_and_read_body_json(&app, login("testuser_retract_bidder")).await;
+    let rival: LoginResponse = test::call_and_read_body_json(&app, login("testuser_retract_rival")).await;
+
+    let req = test::TestRequest::post()
+        .uri("/auctions/create")
+        .insert_header(("Session-Code", seller.session_code.clone()))
+        .set_json(json!({
+            "vehicle_id": vehicle_id,
+            "starting_price": 25000,
+            "end_time": (Utc::now() + Duration::days(3)).format("%Y-%m-%dT%H:%M:%S").to_string(),
+        }))
+        .to_request();
+    let auction: AuctionSummary = test::call_and_read_body_json(&app, req).await;
+
+    let bid = |session: &str, amount: i32| {
+        test::TestRequest::post()
+            .uri("/auctions/bid")
+            .insert_header(("Session-Code", session.to_string()))
+            .set_json(json!({ "auction_id": auction.id, "bid_amount": amount }))
+            .to_request()
+    };
+    let rival_bid: BidReceipt = test::call_and_read_body_json(&app, bid(&rival.session_code, 25000)).await;
+    let typo: BidReceipt = test::call_and_read_body_json(&app, bid(&bidder.session_code, 250000)).await;
+
+    let retract = |session: &str, bid_id: i32, reason: &str| {
+        test::TestRequest::post()
+            .uri(&format!("/auctions/bids/{}/retract", bid_id))
+            .insert_header(("Session-Code", session.to_string()))
+            .set_json(json!({ "reason": reason }))
+            .to_request()
+    };
+
+    let resp = test::call_service(&app, retract(&bidder.session_code, typo.id, "  ")).await;
+    let body: ErrorBody = test::read_body_json(resp).await;
+    assert_eq!(body.details[0].field, "reason");
+    let body: ErrorBody =
+        test::call_and_read_body_json(&app, retract(&rival.session_code, typo.id, "Not mine")).await;
+    assert_eq!(body.code, ErrorCode::NotOwner);
+
+    // The high bid falls back to the best remaining one
+    let retraction: BidRetraction =
+        test::call_and_read_body_json(&app, retract(&bidder.session_code, typo.id, "Extra zero")).await;
+    assert_eq!(retraction.bid_amount, BigDecimal::from(250000));
+    assert_eq!(retraction.highest_bid, Some(BigDecimal::from(25000)));
+
+    let req = test::TestRequest::get().uri(&format!("/auctions/{}", auction.id)).to_request();
+    let detail: AuctionDetail = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(detail.highest_bid, Some(BigDecimal::from(25000)));
+    assert_eq!(detail.bid_count, 1);
+
+    let body: ErrorBody =
+        test::call_and_read_body_json(&app, retract(&bidder.session_code, typo.id, "Extra zero")).await;
+    assert_eq!(body.code, ErrorCode::BidNotFound);
+
+    // Bidding resumes from the corrected high bid
+    let corrected: BidReceipt = test::call_and_read_body_json(&app, bid(&bidder.session_code, 25500)).await;
+
+    // Too long after placing it
+    sqlx::query!("UPDATE bids SET created_at = created_at - INTERVAL '2 hours' WHERE id = $1", rival_bid.id)
+        .execute(&pool)
+        .await
+        .unwrap();
+    let body: ErrorBody =
+        test::call_and_read_body_json(&app, retract(&rival.session_code, rival_bid.id, "Changed my mind")).await;
+    assert_eq!(body.code, ErrorCode::RetractionNotAllowed);
+
+    // Too close to the end
+    sqlx::query!("UPDATE auctions SET end_time = NOW() + INTERVAL '6 hours' WHERE id = $1", auction.id)
+        .execute(&pool)
+        .await
+        .unwrap();
+    let body: ErrorBody =
+        test::call_and_read_body_json(&app, retract(&bidder.session_code, corrected.id, "Changed my mind")).await;
+    assert_eq!(body.code, ErrorCode::RetractionNotAllowed);
+
+    // Sellers see who retracts
+    let list = |session: &str| {
+        test::TestRequest::get()
+            .uri(&format!("/auctions/{}/bids", auction.id))
+            .insert_header(("Session-Code", session.to_string()))
+            .to_request()
+    };
+    let body: ErrorBody = test::call_and_read_body_json(&app, list(&bidder.session_code)).await;
+    assert_eq!(body.code, ErrorCode::NotOwner);
+    let bids: Vec<AuctionBid> = test::call_and_read_body_json(&app, list(&seller.session_code)).await;
+    let counts: Vec<_> = bids.iter().map(|bid| (bid.bidder_username.as_str(), bid.bidder_retraction_count)).collect();
+    assert_eq!(counts, vec![("testuser_retract_bidder", 1), ("testuser_retract_rival", 0)]);
+
+    // The bidder's export still includes what they withdrew
+    let req = test::TestRequest::get()
+        .uri("/users/me/export")
+        .insert_header(("Session-Code", bidder.session_code.clone()))
+        .to_request();
+    let export: UserExport = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(export.bids.len(), 1);
+    assert_eq!(export.retracted_bids.len(), 1);
+    let retracted = &export.retracted_bids[0];
+    assert_eq!((retracted.bid_id, retracted.bid_amount.clone()), (typo.id, BigDecimal::from(250000)));
+    assert_eq!(retracted.reason, "Extra zero");
+    assert_eq!(retracted.placed_at, typo.created_at);
+}
@@ -0,0 +1,200 @@
+use actix_web::{test, App, web};
+use redis::Client;
+use sqlx::PgPool;
+use argon2::{password_hash::SaltString, Argon2, PasswordHasher};
+use serde_json::json;
+use vehicle_auctions::routes::category::{list_categories, create_category, update_category, delete_category};
+use vehicle_auctions::routes::vehicle::{create_vehicle, list_vehicles};
+use vehicle_auctions::routes::search::search_vehicles;
+use vehicle_auctions::routes::user::user_login;
+use vehicle_auctions::models::{Category, CategoryNode, FacetCount, LoginResponse, SearchResults, Vehicle, VehiclePage};
+use vehicle_auctions::errors::{ErrorBody, ErrorCode};
+
+fn count_of(counts: &[FacetCount], slug: &str) -> i64 {
+    counts.iter().find(|facet| facet.value == slug).map_or(0, |facet| facet.count)
+}
+
+fn find_node<'a>(nodes: &'a [CategoryNode], slug: &str) -> Option<&'a CategoryNode> {
+    nodes.iter().find_map(|node| {
+        if node.category.slug == slug {
+            Some(node)
+        } else {
+            find_node(&node.children, slug)
+        }
+    })
+}
+
+#[actix_web::test]
+async fn test_category_tree_and_filters() {
+    dotenv::dotenv().ok();
+    let database_url = std::env::var("DATABASE_URL").expect("DATABASE_URL must be set");
+    let pool = PgPool::connect(&database_url)
+        .await
+        .expect("Failed to connect to database");
+
+    let salt = SaltString::generate(&mut rand::thread_rng());
+    let hashed_password = Argon2::default()
+        .hash_password("password_category".as_bytes(), &salt)
+        .unwrap()
+        .to_string();
+
+    for (username, is_admin) in [("testuser_category_seller", false), ("testuser_category_admin", true)] {
+        sqlx::query!(
+            "INSERT INTO users (username, password, is_admin) VALUES ($1, $2, $3)",
+            username,
+            hashed_password,
+            is_admin
+        ).execute(&pool)
+        .await
+        .unwrap();
+    }
+
+    let redis_client_url = std::env::var("REDIS_URL").expect("REDIS_URL must be set");
+    let redis_client = Client::open(redis_client_url).expect("Failed to connect to Redis");
+
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(pool.clone()))
+            .app_data(web::Data::new(redis_client.clone()))
+            .route("/login", web::post().to(user_login))
+            .route("/categories", web::get().to(list_categories))
+            .route("/admin/categories", web::post().to(create_category))
+            .route("/admin/categories/{id}", web::patch().to(update_category))
+            .route("/admin/categories/{id}", web::delete().to(delete_category))
+            .route("/vehicles/create", web::post().to(create_vehicle))
+            .route("/vehicles/list", web::get().to(list_vehicles))
+            .route("/vehicles/search", web::get().to(search_vehicles)),
+    )
+    .await;
+
+    let login = |username: &str| {
+        test::TestRequest::post()
+            .uri("/login")
+            .set_json(json!({ "username": username, "password": "password_category" }))
+            .to_request()
+    };
+    let seller: LoginResponse = test::call_and_read_body_json(&app, login("testuser_category_seller")).await;
+    let admin: LoginResponse = test::call_and_read_body_json(&app, login("testuser_category_admin")).await;
+
+    let req = test::TestRequest::get().uri("/categories").to_request();
+    let tree: Vec<CategoryNode> = test::call_and_read_body_json(&app, req).await;
+    let cars = find_node(&tree, "cars").unwrap();
+    assert_eq!(cars.children[0].category.slug, "sports-cars");
+    assert_eq!(cars.children[0].children[0].category.slug, "roadsters");
+    let cars_id = cars.category.id;
+    let roadsters_id = find_node(&tree, "roadsters").unwrap().category.id;
+    let motorcycles_id = find_node(&tree, "motorcycles").unwrap().category.id;
+
+    let new_category = |session: &str, body: serde_json::Value| {
+        test::TestRequest::post()
+            .uri("/admin/categories")
+            .insert_header(("Session-Code", session.to_string()))
+            .set_json(body)
+            .to_request()
+    };
+
+    let resp = test::call_service(&app, new_category(&seller.session_code, json!({ "name": "Kit cars" }))).await;
+    let body: ErrorBody = test::read_body_json(resp).await;
+    assert_eq!(body.code, ErrorCode::NotAdmin);
+
+    let kit_cars: Category = test::call_and_read_body_json(
+        &app,
+        new_category(&admin.session_code, json!({ "name": "Kit cars", "parent_id": cars_id })),
+    )
+    .await;
+    assert_eq!(kit_cars.slug, "kit-cars");
+    assert_eq!(kit_cars.parent_id, Some(cars_id));
+
+    let resp = test::call_service(&app, new_category(&admin.session_code, json!({ "name": "Kit Cars!" }))).await;
+    let body: ErrorBody = test::read_body_json(resp).await;
+    assert_eq!(body.details[0].field, "slug");
+
+    let create = |name: &str, category_id: i32| {
+        test::TestRequest::post()
+            .uri("/vehicles/create")
+            .insert_header(("Session-Code", seller.session_code.clone()))
+            .set_json(json!({
+                "name": name,
+                "description": "Quokkamobile collection",
+                "starting_price": 15000,
+                "category_id": category_id,
+            }))
+            .to_request()
+    };
+    for (name, category_id) in [("Lotus Seven", roadsters_id), ("Caterham 7", kit_cars.id), ("Norton Commando", motorcycles_id)] {
+        let vehicle: Vehicle = test::call_and_read_body_json(&app, create(name, category_id)).await;
+        assert_eq!(vehicle.category_id, Some(category_id));
+    }
+
+    let resp = test::call_service(&app, create("Ghost car", 999_999)).await;
+    assert_eq!(resp.status(), 400);
+    let body: ErrorBody = test::read_body_json(resp).await;
+    assert_eq!(body.details[0].field, "category_id");
+
+    // Subcategories are included and counted under every ancestor
+    let req = test::TestRequest::get().uri("/vehicles/list?owner=testuser_category_seller&category=cars").to_request();
+    let page: VehiclePage = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(page.total_count, 2);
+    assert_eq!(count_of(&page.category_counts, "cars"), 2);
+    assert_eq!(count_of(&page.category_counts, "sports-cars"), 1);
+    assert_eq!(count_of(&page.category_counts, "motorcycles"), 0);
+
+    let req = test::TestRequest::get().uri("/vehicles/search?q=quokkamobile&category=sports-cars").to_request();
+    let results: SearchResults = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(results.total_count, 1);
+    assert_eq!(results.items[0].vehicle.name, "Lotus Seven");
+    assert_eq!(count_of(&results.facets.categories, "roadsters"), 1);
+
+    let req = test::TestRequest::get().uri("/vehicles/search?q=quokkamobile").to_request();
+    let results: SearchResults = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(count_of(&results.facets.categories, "cars"), 2);
+    assert_eq!(count_of(&results.facets.categories, "motorcycles"), 1);
+
+    let update = |category_id: i32, body: serde_json::Value| {
+        test::TestRequest::patch()
+            .uri(&format!("/admin/categories/{}", category_id))
+            .insert_header(("Session-Code", admin.session_code.clone()))
+            .set_json(body)
+            .to_request()
+    };
+
+    let resp = test::call_service(&app, update(cars_id, json!({ "parent_id": roadsters_id }))).await;
+    let body: ErrorBody = test::read_body_json(resp).await;
+    assert_eq!(body.details[0].field, "parent_id");
+
+    let moved: Category = test::call_and_read_body_json(
+        &app,
+        update(kit_cars.id, json!({ "parent_id": null, "name": "Kit and replica cars" })),
+    )
+    .await;
+    assert_eq!(moved.parent_id, None);
+    assert_eq!(moved.slug, "kit-cars");
+
+    let req = test::TestRequest::get().uri("/categories").to_request();
+    let tree: Vec<CategoryNode> = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(find_node(&tree, "cars").unwrap().vehicle_count, 1);
+    assert!(tree.iter().any(|node| node.category.slug == "kit-cars" && node.vehicle_count == 1));
+
+    let delete = |category_id: i32| {
+        test::TestRequest::delete()
+            .uri(&format!("/admin/categories/{}", category_id))
+            .insert_header(("Session-Code", admin.session_code.clone()))
+            .to_request()
+    };
+
+    for category_id in [kit_cars.id, cars_id] {
+        let resp = test::call_service(&app, delete(category_id)).await;
+        let body: ErrorBody = test::read_body_json(resp).await;
+        assert_eq!(body.code, ErrorCode::CategoryInUse);
+    }
+
+    let empty: Category = test::call_and_read_body_json(
+        &app,
+        new_category(&admin.session_code, json!({ "name": "Tractors", "parent_id": kit_cars.id })),
+    )
+    .await;
+    let resp = test::call_service(&app, delete(empty.id)).await;
+    assert_eq!(resp.status(), 204);
+    let resp = test::call_service(&app, delete(empty.id)).await;
+    assert_eq!(resp.status(), 404);
+}
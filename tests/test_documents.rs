@@ -0,0 +1,247 @@
+use actix_web::{test, App, web};
+use redis::Client;
+use sqlx::PgPool;
+use std::sync::Arc;
+use vehicle_auctions::routes::document::{
+    upload_vehicle_document, list_vehicle_documents, download_vehicle_document, delete_vehicle_document,
+};
+use vehicle_auctions::routes::condition::{create_condition_report, list_condition_reports};
+use vehicle_auctions::routes::image::serve_media;
+use vehicle_auctions::routes::vehicle::delete_vehicle;
+use vehicle_auctions::models::{ConditionReport, LoginResponse, VehicleDocument};
+use vehicle_auctions::errors::{ErrorBody, ErrorCode};
+use vehicle_auctions::routes::user::user_login;
+use vehicle_auctions::storage::{LocalStorage, Storage};
+use argon2::{password_hash::SaltString, Argon2, PasswordHasher};
+use serde_json::json;
+
+const BOUNDARY: &str = "vehicle-document-boundary";
+const PDF: &[u8] = b"%PDF-1.4\n1 0 obj << >> endobj\ntrailer << >>\n%%EOF\n";
+
+fn multipart_body(doc_type: &str, title: &str, data: &[u8]) -> Vec<u8> {
+    let mut body = format!(
+        "--{b}\r\nContent-Disposition: form-data; name=\"doc_type\"\r\n\r\n{doc_type}\r\n\
+         --{b}\r\nContent-Disposition: form-data; name=\"title\"\r\n\r\n{title}\r\n\
+         --{b}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"upload\"\r\n\
+         Content-Type: application/octet-stream\r\n\r\n",
+        b = BOUNDARY,
+    )
+    .into_bytes();
+    body.extend_from_slice(data);
+    body.extend_from_slice(format!("\r\n--{}--\r\n", BOUNDARY).as_bytes());
+    body
+}
+
+#[actix_web::test]
+async fn test_vehicle_documents_and_condition_reports() {
+    dotenv::dotenv().ok();
+    let database_url = std::env::var("DATABASE_URL").expect("DATABASE_URL must be set");
+    let pool = PgPool::connect(&database_url)
+        .await
+        .expect("Failed to connect to database");
+
+    let salt = SaltString::generate(&mut rand::thread_rng());
+    let hashed_password = Argon2::default()
+        .hash_password("password_docs".as_bytes(), &salt)
+        .unwrap()
+        .to_string();
+
+    let user_id = sqlx::query_scalar!(
+        "INSERT INTO users (username, password) VALUES ($1, $2) RETURNING id",
+        "testuser_docs",
+        hashed_password
+    ).fetch_one(&pool)
+    .await
+    .unwrap();
+
+    let vehicle_id = sqlx::query_scalar!(
+        "INSERT INTO vehicles (name, description, starting_price, owner_id) VALUES ('Lancia Fulvia', '', 30000, $1) RETURNING id",
+        user_id
+    ).fetch_one(&pool)
+    .await
+    .unwrap();
+    let other_vehicle_id = sqlx::query_scalar!(
+        "INSERT INTO vehicles (name, description, starting_price, owner_id) VALUES ('Fiat Dino', '', 60000, $1) RETURNING id",
+        user_id
+    ).fetch_one(&pool)
+    .await
+    .unwrap();
+
+    let image_id = sqlx::query_scalar!(
+        "INSERT INTO vehicle_images (vehicle_id, position, is_primary, web_key, thumbnail_key, content_type, width, height)
+         VALUES ($1, 0, TRUE, 'vehicles/sill-web.jpg', 'vehicles/sill-thumb.jpg', 'image/jpeg', 800, 600) RETURNING id",
+        vehicle_id
+    ).fetch_one(&pool)
+    .await
+    .unwrap();
+    let other_image_id = sqlx::query_scalar!(
+        "INSERT INTO vehicle_images (vehicle_id, position, is_primary, web_key, thumbnail_key, content_type, width, height)
+         VALUES ($1, 0, TRUE, 'vehicles/dino-web.jpg', 'vehicles/dino-thumb.jpg', 'image/jpeg', 800, 600) RETURNING id",
+        other_vehicle_id
+    ).fetch_one(&pool)
+    .await
+    .unwrap();
+
+    let redis_client_url = std::env::var("REDIS_URL").expect("REDIS_URL must be set");
+    let redis_client = Client::open(redis_client_url).expect("Failed to connect to Redis");
+
+    let storage_dir = tempfile::tempdir().unwrap();
+    let storage: Arc<dyn Storage> = Arc::new(LocalStorage::new(storage_dir.path()));
+
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(pool.clone()))
+            .app_data(web::Data::new(redis_client.clone()))
+            .app_data(web::Data::from(storage))
+            .route("/login", web::post().to(user_login))
+            .route("/vehicles/delete/{id}", web::delete().to(delete_vehicle))
+            .route("/vehicles/{id}/documents", web::post().to(upload_vehicle_document))
+            .route("/vehicles/{id}/documents", web::get().to(list_vehicle_documents))
+            .route("/vehicles/{id}/documents/{document_id}/file", web::get().to(download_vehicle_document))
+            .route("/vehicles/{id}/documents/{document_id}", web::delete().to(delete_vehicle_document))
+            .route("/vehicles/{id}/condition-reports", web::post().to(create_condition_report))
+            .route("/vehicles/{id}/condition-reports", web::get().to(list_condition_reports))
+            .route("/media/{key:.*}", web::get().to(serve_media)),
+    )
+    .await;
+
+    let req = test::TestRequest::post()
+        .uri("/login")
+        .set_json(json!({ "username": "testuser_docs", "password": "password_docs" }))
+        .to_request();
+    let login: LoginResponse = test::call_and_read_body_json(&app, req).await;
+
+    let upload = |doc_type: &str, data: &[u8]| {
+        test::TestRequest::post()
+            .uri(&format!("/vehicles/{}/documents", vehicle_id))
+            .insert_header(("Session-Code", login.session_code.clone()))
+            .insert_header(("Content-Type", format!("multipart/form-data; boundary={}", BOUNDARY)))
+            .set_payload(multipart_body(doc_type, "Inspection 2024", data))
+            .to_request()
+    };
+
+    let document: VehicleDocument = test::call_and_read_body_json(&app, upload("inspection_report", PDF)).await;
+    assert_eq!(document.content_type, "application/pdf");
+    assert_eq!(document.title, "Inspection 2024");
+    assert_eq!(document.uploaded_by, "testuser_docs");
+
+    let resp = test::call_service(&app, upload("inspection_report", b"plain text")).await;
+    assert_eq!(resp.status(), 400);
+    let resp = test::call_service(&app, upload("receipt", PDF)).await;
+    assert_eq!(resp.status(), 400);
+
+    // Documents are only visible to signed-in users
+    let req = test::TestRequest::get().uri(&format!("/vehicles/{}/documents", vehicle_id)).to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 401);
+    let req = test::TestRequest::get().uri(&document.url).to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 401);
+
+    let req = test::TestRequest::get()
+        .uri(&document.url)
+        .insert_header(("Session-Code", login.session_code.as_str()))
+        .to_request();
+    let body = test::call_and_read_body(&app, req).await;
+    assert_eq!(&body[..], PDF);
+
+    // Nor can they be fetched through the public media route
+    let req = test::TestRequest::get()
+        .uri(&format!("/media/documents/{}/anything", vehicle_id))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 404);
+
+    let report = json!({
+        "inspection_date": "2024-05-02",
+        "rust_rating": 2,
+        "overall_notes": "Solid car, minor sill bubbling",
+        "third_party_inspected": true,
+        "inspector_name": "Classic Inspections Ltd",
+        "inspection_document_id": document.id,
+        "panels": [
+            { "item": "left_sill", "rating": "fair", "notes": "Bubbling at rear" },
+            { "item": "bonnet", "rating": "good" }
+        ],
+        "mechanical": [{ "item": "gearbox", "rating": "excellent" }],
+        "photo_ids": [image_id]
+    });
+    let req = test::TestRequest::post()
+        .uri(&format!("/vehicles/{}/condition-reports", vehicle_id))
+        .insert_header(("Session-Code", login.session_code.as_str()))
+        .set_json(&report)
+        .to_request();
+    let created: ConditionReport = test::call_and_read_body_json(&app, req).await;
+    assert!(created.third_party_inspected);
+    assert_eq!(created.panels.len(), 2);
+    assert_eq!(created.mechanical[0].item, "gearbox");
+    assert_eq!(created.photos.iter().map(|p| p.id).collect::<Vec<_>>(), vec![image_id]);
+
+    // Third-party reports need an inspector, items come from fixed lists, photos from this vehicle
+    let req = test::TestRequest::post()
+        .uri(&format!("/vehicles/{}/condition-reports", vehicle_id))
+        .insert_header(("Session-Code", login.session_code.as_str()))
+        .set_json(json!({
+            "inspection_date": "2024-05-02", "rust_rating": 2, "third_party_inspected": true,
+            "panels": [{ "item": "flux_capacitor", "rating": "good" }]
+        }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 400);
+    let body: ErrorBody = test::read_body_json(resp).await;
+    let fields: Vec<&str> = body.details.iter().map(|d| d.field.as_str()).collect();
+    assert_eq!(fields, vec!["inspector_name", "panels[0]"]);
+
+    let req = test::TestRequest::post()
+        .uri(&format!("/vehicles/{}/condition-reports", vehicle_id))
+        .insert_header(("Session-Code", login.session_code.as_str()))
+        .set_json(json!({ "inspection_date": "2024-05-02", "rust_rating": 2, "photo_ids": [other_image_id] }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    let body: ErrorBody = test::read_body_json(resp).await;
+    assert_eq!(body.code, ErrorCode::ValidationFailed);
+    assert_eq!(body.details[0].field, "photo_ids");
+
+    // Reports are public
+    let req = test::TestRequest::get()
+        .uri(&format!("/vehicles/{}/condition-reports", vehicle_id))
+        .to_request();
+    let reports: Vec<ConditionReport> = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(reports.len(), 1);
+    assert_eq!(reports[0].inspector_name.as_deref(), Some("Classic Inspections Ltd"));
+
+    // Documents and reports go with a deleted vehicle
+    let req = test::TestRequest::delete()
+        .uri(&format!("/vehicles/delete/{}", vehicle_id))
+        .insert_header(("Session-Code", login.session_code.as_str()))
+        .to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), 204);
+    let req = test::TestRequest::get()
+        .uri(&document.url)
+        .insert_header(("Session-Code", login.session_code.as_str()))
+        .to_request();
+    let body: ErrorBody = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body.code, ErrorCode::VehicleNotFound);
+    let req = test::TestRequest::get()
+        .uri(&format!("/vehicles/{}/condition-reports", vehicle_id))
+        .to_request();
+    let body: ErrorBody = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body.code, ErrorCode::VehicleNotFound);
+    sqlx::query!("UPDATE vehicles SET deleted_at = NULL, deleted_by = NULL WHERE id = $1", vehicle_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let req = test::TestRequest::delete()
+        .uri(&format!("/vehicles/{}/documents/{}", vehicle_id, document.id))
+        .insert_header(("Session-Code", login.session_code.as_str()))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 204);
+    let req = test::TestRequest::get()
+        .uri(&document.url)
+        .insert_header(("Session-Code", login.session_code.as_str()))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 404);
+}
@@ -0,0 +1,197 @@
+use actix_web::{test, App, web};
+use redis::Client;
+use sqlx::PgPool;
+use std::sync::Arc;
+use argon2::{password_hash::SaltString, Argon2, PasswordHasher};
+use serde_json::json;
+use vehicle_auctions::routes::history::{get_vehicle_history, add_provenance_entry};
+use vehicle_auctions::routes::auction::close_auction;
+use vehicle_auctions::routes::document::delete_vehicle_document;
+use vehicle_auctions::routes::vehicle::create_vehicle;
+use vehicle_auctions::routes::user::user_login;
+use vehicle_auctions::models::{LoginResponse, OwnershipEntry, Vehicle};
+use vehicle_auctions::errors::{ErrorBody, ErrorCode};
+use vehicle_auctions::storage::{LocalStorage, Storage};
+
+#[actix_web::test]
+async fn test_ownership_history_and_provenance() {
+    dotenv::dotenv().ok();
+    let database_url = std::env::var("DATABASE_URL").expect("DATABASE_URL must be set");
+    let pool = PgPool::connect(&database_url)
+        .await
+        .expect("Failed to connect to database");
+
+    let salt = SaltString::generate(&mut rand::thread_rng());
+    let hashed_password = Argon2::default()
+        .hash_password("password_history".as_bytes(), &salt)
+        .unwrap()
+        .to_string();
+
+    let seller_id = sqlx::query_scalar!(
+        "INSERT INTO users (username, password) VALUES ($1, $2) RETURNING id",
+        "testuser_history_seller",
+        hashed_password
+    ).fetch_one(&pool)
+    .await
+    .unwrap();
+    let buyer_id = sqlx::query_scalar!(
+        "INSERT INTO users (username, password) VALUES ($1, $2) RETURNING id",
+        "testuser_history_buyer",
+        hashed_password
+    ).fetch_one(&pool)
+    .await
+    .unwrap();
+
+    let redis_client_url = std::env::var("REDIS_URL").expect("REDIS_URL must be set");
+    let redis_client = Client::open(redis_client_url).expect("Failed to connect to Redis");
+    let storage_dir = tempfile::tempdir().unwrap();
+    let storage: Arc<dyn Storage> = Arc::new(LocalStorage::new(storage_dir.path()));
+
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(pool.clone()))
+            .app_data(web::Data::new(redis_client.clone()))
+            .app_data(web::Data::from(storage))
+            .route("/login", web::post().to(user_login))
+            .route("/vehicles/create", web::post().to(create_vehicle))
+            .route("/vehicles/{id}/history", web::get().to(get_vehicle_history))
+            .route("/vehicles/{id}/history", web::post().to(add_provenance_entry))
+            .route("/vehicles/{id}/documents/{document_id}", web::delete().to(delete_vehicle_document))
+            .route("/auctions/close/{id}", web::post().to(close_auction)),
+    )
+    .await;
+
+    let req = test::TestRequest::post()
+        .uri("/login")
+        .set_json(json!({ "username": "testuser_history_seller", "password": "password_history" }))
+        .to_request();
+    let login: LoginResponse = test::call_and_read_body_json(&app, req).await;
+
+    let req = test::TestRequest::post()
+        .uri("/vehicles/create")
+        .insert_header(("Session-Code", login.session_code.as_str()))
+        .set_json(json!({ "name": "Facel Vega HK500", "description": "", "starting_price": 150000.0 }))
+        .to_request();
+    let vehicle: Vehicle = test::call_and_read_body_json(&app, req).await;
+
+    let mut document_ids = Vec::new();
+    for doc_type in ["title", "service_history"] {
+        let document_id = sqlx::query_scalar!(
+            "INSERT INTO vehicle_documents (vehicle_id, doc_type, title, storage_key, content_type, byte_size, uploaded_by)
+             VALUES ($1, $2::TEXT, $2, 'documents/' || $2, 'application/pdf', 10, $3) RETURNING id",
+            vehicle.id,
+            doc_type,
+            seller_id
+        ).fetch_one(&pool)
+        .await
+        .unwrap();
+        document_ids.push(document_id);
+    }
+    let (title_id, service_history_id) = (document_ids[0], document_ids[1]);
+
+    let add_entry = |body: serde_json::Value| {
+        test::TestRequest::post()
+            .uri(&format!("/vehicles/{}/history", vehicle.id))
+            .insert_header(("Session-Code", login.session_code.clone()))
+            .set_json(body)
+            .to_request()
+    };
+
+    let history: Vec<OwnershipEntry> = test::call_and_read_body_json(
+        &app,
+        add_entry(json!({
+            "from_name": "Facel S.A.",
+            "to_name": "First Owner",
+            "transferred_on": "1959-04-12",
+            "price": 7000.0,
+            "notes": "Delivered new in Paris",
+            "evidence_document_id": title_id
+        })),
+    )
+    .await;
+    assert_eq!(history.len(), 2);
+    assert_eq!(history[0].kind, "provenance");
+    assert_eq!(history[0].to_owner.as_deref(), Some("First Owner"));
+    assert_eq!(history[0].evidence_document_id, Some(title_id));
+    assert_eq!(history[1].kind, "registration");
+    assert_eq!(history[1].to_owner.as_deref(), Some("testuser_history_seller"));
+
+    // Evidence must be a title, registration or invoice, and the entry must predate the listing
+    let resp = test::call_service(
+        &app,
+        add_entry(json!({ "to_name": "Later Owner", "transferred_on": "1975-01-01", "evidence_document_id": service_history_id })),
+    )
+    .await;
+    let body: ErrorBody = test::read_body_json(resp).await;
+    assert_eq!(body.details[0].field, "evidence_document_id");
+
+    sqlx::query!(
+        "UPDATE vehicle_ownership SET transferred_at = NOW() - INTERVAL '10 days' WHERE vehicle_id = $1 AND kind = 'registration'",
+        vehicle.id
+    )
+    .execute(&pool)
+    .await
+    .expect_err("ownership history is append-only");
+
+    let today = chrono::Utc::now().date_naive().to_string();
+    let resp = test::call_service(
+        &app,
+        add_entry(json!({ "to_name": "Later Owner", "transferred_on": today, "evidence_document_id": title_id })),
+    )
+    .await;
+    let body: ErrorBody = test::read_body_json(resp).await;
+    assert_eq!(body.details[0].field, "transferred_on");
+
+    // Settling an auction appends the sale
+    let auction_id = sqlx::query_scalar!(
+        "INSERT INTO auctions (vehicle_id, starting_price, end_time, seller_id) VALUES ($1, 150000, '2099-01-01', $2) RETURNING id",
+        vehicle.id,
+        seller_id
+    ).fetch_one(&pool)
+    .await
+    .unwrap();
+    sqlx::query!(
+        "INSERT INTO bids (auction_id, bidder_id, bid_amount) VALUES ($1, $2, 162500)",
+        auction_id,
+        buyer_id
+    ).execute(&pool)
+    .await
+    .unwrap();
+
+    let req = test::TestRequest::post()
+        .uri(&format!("/auctions/close/{}", auction_id))
+        .insert_header(("Session-Code", login.session_code.as_str()))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 200);
+
+    let req = test::TestRequest::get().uri(&format!("/vehicles/{}/history", vehicle.id)).to_request();
+    let history: Vec<OwnershipEntry> = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(history.iter().map(|e| e.kind.as_str()).collect::<Vec<_>>(), vec!["provenance", "registration", "sale"]);
+    let sale = &history[2];
+    assert_eq!(sale.from_owner.as_deref(), Some("testuser_history_seller"));
+    assert_eq!(sale.to_owner.as_deref(), Some("testuser_history_buyer"));
+    assert_eq!(sale.auction_id, Some(auction_id));
+    assert_eq!(sale.price, Some(bigdecimal::BigDecimal::from(162500)));
+
+    // Only the current owner adds provenance
+    let resp = test::call_service(
+        &app,
+        add_entry(json!({ "to_name": "Someone", "transferred_on": "1960-01-01", "evidence_document_id": title_id })),
+    )
+    .await;
+    assert_eq!(resp.status(), 403);
+
+    // Evidence cannot be removed from under a provenance entry
+    sqlx::query!("UPDATE vehicles SET owner_id = $1 WHERE id = $2", seller_id, vehicle.id)
+        .execute(&pool)
+        .await
+        .unwrap();
+    let req = test::TestRequest::delete()
+        .uri(&format!("/vehicles/{}/documents/{}", vehicle.id, title_id))
+        .insert_header(("Session-Code", login.session_code.as_str()))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    let body: ErrorBody = test::read_body_json(resp).await;
+    assert_eq!(body.code, ErrorCode::DocumentInUse);
+}
@@ -0,0 +1,201 @@
+use actix_web::{middleware, test, App, HttpResponse, web};
+use redis::Client;
+use sqlx::PgPool;
+use argon2::{password_hash::SaltString, Argon2, PasswordHasher};
+use chrono::{Duration, Utc};
+use serde_json::json;
+use vehicle_auctions::idempotency::{idempotency, IDEMPOTENCY_KEY_HEADER, MAX_BODY_BYTES, REPLAYED_HEADER};
+use vehicle_auctions::routes::auction::{create_auction, place_bid};
+use vehicle_auctions::routes::vehicle::create_vehicle;
+use vehicle_auctions::routes::user::user_login;
+use vehicle_auctions::models::{AuctionSummary, BidReceipt, LoginResponse, Vehicle};
+use vehicle_auctions::errors::{ErrorBody, ErrorCode};
+
+#[actix_web::test]
+async fn test_idempotency_keys_replay_and_reject() {
+    dotenv::dotenv().ok();
+    let database_url = std::env::var("DATABASE_URL").expect("DATABASE_URL must be set");
+    let pool = PgPool::connect(&database_url)
+        .await
+        .expect("Failed to connect to database");
+
+    let salt = SaltString::generate(&mut rand::thread_rng());
+    let hashed_password = Argon2::default()
+        .hash_password("password_idempotency".as_bytes(), &salt)
+        .unwrap()
+        .to_string();
+
+    for username in ["testuser_idempotency_seller", "testuser_idempotency_bidder"] {
+        sqlx::query!(
+            "INSERT INTO users (username, password) VALUES ($1, $2)",
+            username,
+            hashed_password
+        ).execute(&pool)
+        .await
+        .unwrap();
+    }
+
+    let redis_client_url = std::env::var("REDIS_URL").expect("REDIS_URL must be set");
+    let redis_client = Client::open(redis_client_url).expect("Failed to connect to Redis");
+
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(pool.clone()))
+            .app_data(web::Data::new(redis_client.clone()))
+            .wrap(middleware::from_fn(idempotency))
+            .route("/login", web::post().to(user_login))
+            .route("/vehicles/create", web::post().to(create_vehicle))
+            .route("/auctions/create", web::post().to(create_auction))
+            .route("/auctions/bid", web::post().to(place_bid))
+            .route(
+                "/created",
+                web::post().to(|| async {
+                    HttpResponse::Created().insert_header(("Location", "/vehicles/1")).json(json!({}))
+                }),
+            ),
+    )
+    .await;
+
+    let login = |username: &str| {
+        test::TestRequest::post()
+            .uri("/login")
+            .set_json(json!({ "username": username, "password": "password_idempotency" }))
+            .to_request()
+    };
+    let seller: LoginResponse = test::call_and_read_body_json(&app, login("testuser_idempotency_seller")).await;
+    let bidder: LoginResponse = test::call_and_read_body_json(&app, login("testuser_idempotency_bidder")).await;
+
+    // Keys are scoped by user id, which a recreated test database hands out again while Redis keeps the keys
+    let run = uuid::Uuid::new_v4();
+    let create = |session: &str, key: Option<&str>, name: &str| {
+        let mut req = test::TestRequest::post()
+            .uri("/vehicles/create")
+            .insert_header(("Session-Code", session.to_string()))
+            .set_json(json!({ "name": name, "description": "Retried upload", "starting_price": 14000 }));
+        if let Some(key) = key {
+            req = req.insert_header((IDEMPOTENCY_KEY_HEADER, format!("{}-{}", key, run)));
+        }
+        req.to_request()
+    };
+    let count = |name: &'static str| {
+        let pool = pool.clone();
+        async move {
+            sqlx::query_scalar!("SELECT COUNT(*) FROM vehicles WHERE name = $1", name)
+                .fetch_one(&pool)
+                .await
+                .unwrap()
+        }
+    };
+
+    // A retry gets the first response back instead of a second vehicle
+    let resp = test::call_service(&app, create(&seller.session_code, Some("vehicle-1"), "Monteverdi 375L")).await;
+    assert!(resp.headers().get(REPLAYED_HEADER).is_none());
+    let original: Vehicle = test::read_body_json(resp).await;
+    let resp = test::call_service(&app, create(&seller.session_code, Some("vehicle-1"), "Monteverdi 375L")).await;
+    assert_eq!(resp.status(), 200);
+    assert_eq!(resp.headers().get(REPLAYED_HEADER).unwrap(), "true");
+    let replayed: Vehicle = test::read_body_json(resp).await;
+    assert_eq!(replayed.id, original.id);
+    assert_eq!(count("Monteverdi 375L").await, Some(1));
+
+    let resp = test::call_service(&app, create(&seller.session_code, Some("vehicle-1"), "Monteverdi Hai")).await;
+    assert_eq!(resp.status(), 422);
+    let body: ErrorBody = test::read_body_json(resp).await;
+    assert_eq!(body.code, ErrorCode::IdempotencyKeyReused);
+    assert_eq!(count("Monteverdi Hai").await, Some(0));
+
+    // Keys belong to a user, whichever session they retry from
+    let relogin: LoginResponse = test::call_and_read_body_json(&app, login("testuser_idempotency_seller")).await;
+    let resp = test::call_service(&app, create(&relogin.session_code, Some("vehicle-1"), "Monteverdi 375L")).await;
+    assert_eq!(resp.headers().get(REPLAYED_HEADER).unwrap(), "true");
+    let replayed: Vehicle = test::read_body_json(resp).await;
+    assert_eq!(replayed.id, original.id);
+    let resp = test::call_service(&app, create(&bidder.session_code, Some("vehicle-1"), "Monteverdi 375L")).await;
+    assert!(resp.headers().get(REPLAYED_HEADER).is_none());
+    for _ in 0..2 {
+        let resp = test::call_service(&app, create(&seller.session_code, None, "Monteverdi 375L")).await;
+        assert_eq!(resp.status(), 200);
+    }
+    assert_eq!(count("Monteverdi 375L").await, Some(4));
+
+    // Requests without a session have no owner for the key and are not deduplicated
+    let anonymous_login = || {
+        let mut req = login("testuser_idempotency_seller");
+        req.headers_mut().insert(IDEMPOTENCY_KEY_HEADER.parse().unwrap(), "login-1".parse().unwrap());
+        req
+    };
+    let first = test::call_service(&app, anonymous_login()).await;
+    let second = test::call_service(&app, anonymous_login()).await;
+    assert!(second.headers().get(REPLAYED_HEADER).is_none());
+    let first: LoginResponse = test::read_body_json(first).await;
+    let second: LoginResponse = test::read_body_json(second).await;
+    assert_ne!(first.session_code, second.session_code);
+
+    // Replays carry the headers of the first response, not only its body
+    let created = || {
+        test::TestRequest::post()
+            .uri("/created")
+            .insert_header(("Session-Code", seller.session_code.clone()))
+            .insert_header((IDEMPOTENCY_KEY_HEADER, format!("created-{}", run)))
+            .to_request()
+    };
+    test::call_service(&app, created()).await;
+    let resp = test::call_service(&app, created()).await;
+    assert_eq!(resp.status(), 201);
+    assert_eq!(resp.headers().get(REPLAYED_HEADER).unwrap(), "true");
+    assert_eq!(resp.headers().get("Location").unwrap(), "/vehicles/1");
+    assert_eq!(resp.headers().get("Content-Type").unwrap(), "application/json");
+
+    let long_key = "k".repeat(256);
+    let resp = test::call_service(&app, create(&seller.session_code, Some(&long_key), "Monteverdi Hai")).await;
+    let body: ErrorBody = test::read_body_json(resp).await;
+    assert_eq!(body.code, ErrorCode::InvalidIdempotencyKey);
+
+    // Keyed bodies are buffered, so they are capped before any handler sees them
+    let req = test::TestRequest::post()
+        .uri("/vehicles/create")
+        .insert_header(("Session-Code", seller.session_code.clone()))
+        .insert_header((IDEMPOTENCY_KEY_HEADER, "oversized"))
+        .insert_header(("Content-Type", "application/json"))
+        .set_payload(vec![b' '; MAX_BODY_BYTES + 1])
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 400);
+    let body: ErrorBody = test::read_body_json(resp).await;
+    assert_eq!(body.code, ErrorCode::ValidationFailed);
+
+    let req = test::TestRequest::post()
+        .uri("/auctions/create")
+        .insert_header(("Session-Code", seller.session_code.clone()))
+        .set_json(json!({
+            "vehicle_id": original.id,
+            "starting_price": 14000,
+            "end_time": (Utc::now() + Duration::days(2)).format("%Y-%m-%dT%H:%M:%S").to_string(),
+        }))
+        .to_request();
+    let auction: AuctionSummary = test::call_and_read_body_json(&app, req).await;
+
+    // Double-submitted bids count once; rejections are replayed too
+    let bid = |key: &str, amount: i32| {
+        test::TestRequest::post()
+            .uri("/auctions/bid")
+            .insert_header(("Session-Code", bidder.session_code.clone()))
+            .insert_header((IDEMPOTENCY_KEY_HEADER, format!("{}-{}", key, run)))
+            .set_json(json!({ "auction_id": auction.id, "bid_amount": amount }))
+            .to_request()
+    };
+    let first: BidReceipt = test::call_and_read_body_json(&app, bid("bid-1", 14000)).await;
+    let retried: BidReceipt = test::call_and_read_body_json(&app, bid("bid-1", 14000)).await;
+    assert_eq!(first.id, retried.id);
+    for _ in 0..2 {
+        let resp = test::call_service(&app, bid("bid-2", 100)).await;
+        assert_eq!(resp.status(), 400);
+        let body: ErrorBody = test::read_body_json(resp).await;
+        assert_eq!(body.code, ErrorCode::BidTooLow);
+    }
+    let bids = sqlx::query_scalar!("SELECT COUNT(*) FROM bids WHERE auction_id = $1", auction.id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(bids, Some(1));
+}
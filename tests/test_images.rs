@@ -0,0 +1,252 @@
+use actix_web::{test, App, HttpRequest, HttpResponse, HttpServer, web};
+use redis::Client;
+use sqlx::PgPool;
+use std::collections::HashMap;
+use std::io::Cursor;
+use std::sync::{Arc, Mutex};
+use vehicle_auctions::routes::image::{
+    upload_vehicle_image, list_vehicle_images, reorder_vehicle_images, set_primary_image, delete_vehicle_image,
+    serve_media,
+};
+use vehicle_auctions::models::{LoginResponse, VehicleImage};
+use vehicle_auctions::errors::ErrorBody;
+use vehicle_auctions::routes::user::user_login;
+use vehicle_auctions::storage::{LocalStorage, S3Storage, Storage};
+use argon2::{password_hash::SaltString, Argon2, PasswordHasher};
+use serde_json::json;
+
+const BOUNDARY: &str = "vehicle-image-boundary";
+
+fn multipart_body(data: &[u8]) -> Vec<u8> {
+    let mut body = format!(
+        "--{}\r\nContent-Disposition: form-data; name=\"image\"; filename=\"photo\"\r\nContent-Type: application/octet-stream\r\n\r\n",
+        BOUNDARY
+    )
+    .into_bytes();
+    body.extend_from_slice(data);
+    body.extend_from_slice(format!("\r\n--{}--\r\n", BOUNDARY).as_bytes());
+    body
+}
+
+fn encode(width: u32, height: u32, format: image::ImageFormat) -> Vec<u8> {
+    let image = image::RgbImage::from_fn(width, height, |x, y| image::Rgb([(x % 256) as u8, (y % 256) as u8, 128]));
+    let mut buffer = Cursor::new(Vec::new());
+    image.write_to(&mut buffer, format).unwrap();
+    buffer.into_inner()
+}
+
+/// A JPEG carrying an EXIF block with a GPS latitude reference.
+fn jpeg_with_gps(width: u32, height: u32) -> Vec<u8> {
+    let jpeg = encode(width, height, image::ImageFormat::Jpeg);
+    let tiff: Vec<u8> = [
+        &b"II*\0"[..], &8u32.to_le_bytes(),
+        // IFD0: a single GPSInfo pointer to offset 26
+        &1u16.to_le_bytes(), &0x8825u16.to_le_bytes(), &4u16.to_le_bytes(), &1u32.to_le_bytes(), &26u32.to_le_bytes(),
+        &0u32.to_le_bytes(),
+        // GPS IFD: GPSLatitudeRef = "N"
+        &1u16.to_le_bytes(), &1u16.to_le_bytes(), &2u16.to_le_bytes(), &2u32.to_le_bytes(), b"N\0\0\0",
+        &0u32.to_le_bytes(),
+    ]
+    .concat();
+    let mut app1 = b"Exif\0\0".to_vec();
+    app1.extend_from_slice(&tiff);
+
+    let mut result = jpeg[..2].to_vec();
+    result.extend_from_slice(&[0xFF, 0xE1]);
+    result.extend_from_slice(&((app1.len() + 2) as u16).to_be_bytes());
+    result.extend_from_slice(&app1);
+    result.extend_from_slice(&jpeg[2..]);
+    result
+}
+
+#[actix_web::test]
+async fn test_vehicle_image_gallery() {
+    dotenv::dotenv().ok();
+    let database_url = std::env::var("DATABASE_URL").expect("DATABASE_URL must be set");
+    let pool = PgPool::connect(&database_url)
+        .await
+        .expect("Failed to connect to database");
+
+    let salt = SaltString::generate(&mut rand::thread_rng());
+    let hashed_password = Argon2::default()
+        .hash_password("password_images".as_bytes(), &salt)
+        .unwrap()
+        .to_string();
+
+    let user_id = sqlx::query_scalar!(
+        "INSERT INTO users (username, password) VALUES ($1, $2) RETURNING id",
+        "testuser_images",
+        hashed_password
+    ).fetch_one(&pool)
+    .await
+    .unwrap();
+
+    let vehicle_id = sqlx::query_scalar!(
+        "INSERT INTO vehicles (name, description, starting_price, owner_id) VALUES ('Jaguar E-Type', '', 80000, $1) RETURNING id",
+        user_id
+    ).fetch_one(&pool)
+    .await
+    .unwrap();
+
+    let redis_client_url = std::env::var("REDIS_URL").expect("REDIS_URL must be set");
+    let redis_client = Client::open(redis_client_url).expect("Failed to connect to Redis");
+
+    let storage_dir = tempfile::tempdir().unwrap();
+    let storage: Arc<dyn Storage> = Arc::new(LocalStorage::new(storage_dir.path()));
+
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(pool.clone()))
+            .app_data(web::Data::new(redis_client.clone()))
+            .app_data(web::Data::from(storage))
+            .route("/login", web::post().to(user_login))
+            .route("/vehicles/{id}/images", web::post().to(upload_vehicle_image))
+            .route("/vehicles/{id}/images", web::get().to(list_vehicle_images))
+            .route("/vehicles/{id}/images/order", web::put().to(reorder_vehicle_images))
+            .route("/vehicles/{id}/images/{image_id}/primary", web::post().to(set_primary_image))
+            .route("/vehicles/{id}/images/{image_id}", web::delete().to(delete_vehicle_image))
+            .route("/media/{key:.*}", web::get().to(serve_media)),
+    )
+    .await;
+
+    let req = test::TestRequest::post()
+        .uri("/login")
+        .set_json(json!({ "username": "testuser_images", "password": "password_images" }))
+        .to_request();
+    let login: LoginResponse = test::call_and_read_body_json(&app, req).await;
+
+    let upload = |data: Vec<u8>| {
+        test::TestRequest::post()
+            .uri(&format!("/vehicles/{}/images", vehicle_id))
+            .insert_header(("Session-Code", login.session_code.clone()))
+            .insert_header(("Content-Type", format!("multipart/form-data; boundary={}", BOUNDARY)))
+            .set_payload(multipart_body(&data))
+            .to_request()
+    };
+
+    // Large photo is scaled down, becomes the primary image and loses its EXIF block
+    let first: VehicleImage = test::call_and_read_body_json(&app, upload(jpeg_with_gps(2000, 1000))).await;
+    assert_eq!((first.width, first.height), (1600, 800));
+    assert!(first.is_primary);
+    assert_eq!(first.position, 0);
+
+    let req = test::TestRequest::get().uri(&first.url).to_request();
+    let stored = test::call_and_read_body(&app, req).await;
+    assert!(!stored.windows(4).any(|w| w == b"Exif"));
+    let req = test::TestRequest::get().uri(&first.thumbnail_url).to_request();
+    let thumbnail = test::call_and_read_body(&app, req).await;
+    let thumbnail = image::load_from_memory(&thumbnail).unwrap();
+    assert_eq!((thumbnail.width(), thumbnail.height()), (320, 160));
+
+    let second: VehicleImage = test::call_and_read_body_json(&app, upload(encode(100, 50, image::ImageFormat::Png))).await;
+    assert!(!second.is_primary);
+    assert_eq!(second.position, 1);
+
+    // Anything that is not a supported image is rejected
+    let resp = test::call_service(&app, upload(b"not an image".to_vec())).await;
+    assert_eq!(resp.status(), 400);
+    let body: ErrorBody = test::read_body_json(resp).await;
+    assert_eq!(body.details[0].field, "image");
+
+    let req = test::TestRequest::put()
+        .uri(&format!("/vehicles/{}/images/order", vehicle_id))
+        .insert_header(("Session-Code", login.session_code.as_str()))
+        .set_json(json!({ "image_ids": [second.id, first.id] }))
+        .to_request();
+    let images: Vec<VehicleImage> = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(images.iter().map(|i| i.id).collect::<Vec<_>>(), vec![second.id, first.id]);
+
+    let req = test::TestRequest::put()
+        .uri(&format!("/vehicles/{}/images/order", vehicle_id))
+        .insert_header(("Session-Code", login.session_code.as_str()))
+        .set_json(json!({ "image_ids": [second.id] }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 400);
+
+    let req = test::TestRequest::post()
+        .uri(&format!("/vehicles/{}/images/{}/primary", vehicle_id, second.id))
+        .insert_header(("Session-Code", login.session_code.as_str()))
+        .to_request();
+    let images: Vec<VehicleImage> = test::call_and_read_body_json(&app, req).await;
+    assert!(images.iter().all(|i| i.is_primary == (i.id == second.id)));
+
+    // Deleting the primary image promotes the next one and removes the files
+    let req = test::TestRequest::delete()
+        .uri(&format!("/vehicles/{}/images/{}", vehicle_id, second.id))
+        .insert_header(("Session-Code", login.session_code.as_str()))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 204);
+
+    let req = test::TestRequest::get().uri(&format!("/vehicles/{}/images", vehicle_id)).to_request();
+    let images: Vec<VehicleImage> = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(images.len(), 1);
+    assert!(images[0].is_primary);
+
+    let req = test::TestRequest::get().uri(&second.url).to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 404);
+}
+
+type Bucket = Arc<Mutex<HashMap<String, Vec<u8>>>>;
+
+/// Minimal S3 stand-in: path-style objects kept in memory, signed requests required.
+async fn fake_s3(req: HttpRequest, body: web::Bytes, bucket: web::Data<Bucket>) -> HttpResponse {
+    let signed = req
+        .headers()
+        .get("authorization")
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("AWS4-HMAC-SHA256 Credential=minio/"))
+        && req.headers().contains_key("x-amz-date");
+    if !signed {
+        return HttpResponse::Forbidden().finish();
+    }
+
+    let key = req.path().to_string();
+    let mut objects = bucket.lock().unwrap();
+    match req.method().as_str() {
+        "PUT" => {
+            objects.insert(key, body.to_vec());
+            HttpResponse::Ok().finish()
+        }
+        "GET" => match objects.get(&key) {
+            Some(data) => HttpResponse::Ok().body(data.clone()),
+            None => HttpResponse::NotFound().finish(),
+        },
+        "DELETE" => {
+            objects.remove(&key);
+            HttpResponse::NoContent().finish()
+        }
+        _ => HttpResponse::MethodNotAllowed().finish(),
+    }
+}
+
+#[actix_web::test]
+async fn test_s3_storage_round_trip() {
+    let bucket: Bucket = Arc::default();
+    let server_bucket = bucket.clone();
+    let server = HttpServer::new(move || {
+        App::new()
+            .app_data(web::Data::new(server_bucket.clone()))
+            .default_service(web::to(fake_s3))
+    })
+    .workers(1)
+    .bind(("127.0.0.1", 0))
+    .unwrap();
+    let addr = server.addrs()[0];
+    actix_web::rt::spawn(server.run());
+
+    let storage = S3Storage::new(&format!("http://{}", addr), "media", "us-east-1", "minio", "minio-secret");
+    storage
+        .put("vehicles/1/photo-web.jpg", "image/jpeg", web::Bytes::from_static(b"jpeg bytes"))
+        .await
+        .unwrap();
+    assert!(bucket.lock().unwrap().contains_key("/media/vehicles/1/photo-web.jpg"));
+
+    let data = storage.get("vehicles/1/photo-web.jpg").await.unwrap();
+    assert_eq!(data.as_deref(), Some(&b"jpeg bytes"[..]));
+
+    storage.delete("vehicles/1/photo-web.jpg").await.unwrap();
+    assert!(storage.get("vehicles/1/photo-web.jpg").await.unwrap().is_none());
+}
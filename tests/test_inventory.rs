@@ -0,0 +1,118 @@
+use actix_web::{test, App, web};
+use redis::Client;
+use sqlx::PgPool;
+use argon2::{password_hash::SaltString, Argon2, PasswordHasher};
+use serde_json::json;
+use vehicle_auctions::routes::inventory::{import_vehicles, export_vehicles};
+use vehicle_auctions::routes::user::user_login;
+use vehicle_auctions::models::{ImportReport, LoginResponse};
+use vehicle_auctions::errors::ErrorBody;
+
+const VALID_CSV: &str = "name,description,starting_price,make,year,vin,fuel_type,matching_numbers\n\
+    Porsche 993 Carrera,\"Guards red, 6-speed\",98000,,,WP0AA2993SS498765,petrol,true\n\
+    Honda Accord,,4500.50,Honda,,1HGCM82623A117788,,\n";
+
+#[actix_web::test]
+async fn test_bulk_import_and_export() {
+    dotenv::dotenv().ok();
+    let database_url = std::env::var("DATABASE_URL").expect("DATABASE_URL must be set");
+    let pool = PgPool::connect(&database_url)
+        .await
+        .expect("Failed to connect to database");
+
+    let salt = SaltString::generate(&mut rand::thread_rng());
+    let hashed_password = Argon2::default()
+        .hash_password("password_dealer".as_bytes(), &salt)
+        .unwrap()
+        .to_string();
+
+    let dealer_id = sqlx::query_scalar!(
+        "INSERT INTO users (username, password) VALUES ($1, $2) RETURNING id",
+        "testuser_dealer",
+        hashed_password
+    ).fetch_one(&pool)
+    .await
+    .unwrap();
+
+    let redis_client_url = std::env::var("REDIS_URL").expect("REDIS_URL must be set");
+    let redis_client = Client::open(redis_client_url).expect("Failed to connect to Redis");
+
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(pool.clone()))
+            .app_data(web::Data::new(redis_client.clone()))
+            .route("/login", web::post().to(user_login))
+            .route("/vehicles/import", web::post().to(import_vehicles))
+            .route("/vehicles/export", web::get().to(export_vehicles)),
+    )
+    .await;
+
+    let req = test::TestRequest::post()
+        .uri("/login")
+        .set_json(json!({ "username": "testuser_dealer", "password": "password_dealer" }))
+        .to_request();
+    let login: LoginResponse = test::call_and_read_body_json(&app, req).await;
+
+    let import = |uri: &str, content_type: &str, body: &str| {
+        test::TestRequest::post()
+            .uri(uri)
+            .insert_header(("Session-Code", login.session_code.clone()))
+            .insert_header(("Content-Type", content_type.to_string()))
+            .set_payload(body.to_string())
+            .to_request()
+    };
+    let vehicle_count = || {
+        sqlx::query_scalar!(r#"SELECT COUNT(*) as "count!" FROM vehicles WHERE owner_id = $1"#, dealer_id).fetch_one(&pool)
+    };
+
+    let report: ImportReport =
+        test::call_and_read_body_json(&app, import("/vehicles/import?dry_run=true", "text/csv", VALID_CSV)).await;
+    assert!(report.dry_run);
+    assert_eq!(report.row_count, 2);
+    assert!(report.vehicles.is_empty());
+    assert_eq!(vehicle_count().await.unwrap(), 0);
+
+    // Every bad row is reported and nothing is saved
+    let broken = format!("{}Lotus Elan,,cheap,,,,,\nLotus Europa,,9000,,,,steam,\nHonda Accord,,4500,,,1HGCM82623A117788,,\n", VALID_CSV);
+    let resp = test::call_service(&app, import("/vehicles/import", "text/csv; charset=utf-8", &broken)).await;
+    assert_eq!(resp.status(), 400);
+    let body: ErrorBody = test::read_body_json(resp).await;
+    let fields: Vec<&str> = body.details.iter().map(|d| d.field.as_str()).collect();
+    assert_eq!(fields, vec!["rows[2]", "rows[3].fuel_type", "rows[4].vin"]);
+    assert_eq!(vehicle_count().await.unwrap(), 0);
+
+    let report: ImportReport = test::call_and_read_body_json(&app, import("/vehicles/import", "text/csv", VALID_CSV)).await;
+    assert_eq!(report.vehicles.len(), 2);
+    let porsche = &report.vehicles[0];
+    assert_eq!(porsche.description, "Guards red, 6-speed");
+    assert_eq!(porsche.make.as_deref(), Some("Porsche"));
+    assert_eq!(porsche.matching_numbers, Some(true));
+
+    let jsonl = "{\"name\": \"Volvo P1800\", \"description\": \"\", \"starting_price\": 30000, \"year\": 1966}\n\n\
+                 {\"name\": \"Saab 96\", \"description\": \"Two-stroke\", \"starting_price\": 8000}\n";
+    let report: ImportReport =
+        test::call_and_read_body_json(&app, import("/vehicles/import?format=jsonl", "text/plain", jsonl)).await;
+    assert_eq!(report.row_count, 2);
+    assert_eq!(report.vehicles[0].year, Some(1966));
+
+    let resp = test::call_service(&app, import("/vehicles/import", "application/xml", "<vehicles/>")).await;
+    let body: ErrorBody = test::read_body_json(resp).await;
+    assert_eq!(body.details[0].field, "format");
+
+    let req = test::TestRequest::get()
+        .uri("/vehicles/export")
+        .insert_header(("Session-Code", login.session_code.as_str()))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 200);
+    assert!(resp.headers().get("Content-Type").unwrap().to_str().unwrap().starts_with("text/csv"));
+    let body = test::read_body(resp).await;
+
+    let mut reader = csv::Reader::from_reader(&body[..]);
+    assert_eq!(&reader.headers().unwrap()[1], "name");
+    let records: Vec<csv::StringRecord> = reader.records().map(Result::unwrap).collect();
+    assert_eq!(records.len(), 4);
+    assert_eq!(&records[0][1], "Porsche 993 Carrera");
+    assert_eq!(&records[0][2], "Guards red, 6-speed");
+    assert_eq!(&records[3][1], "Saab 96");
+}
@@ -0,0 +1,227 @@
+use actix_web::{test, App, web};
+use redis::Client;
+use sqlx::PgPool;
+use argon2::{password_hash::SaltString, Argon2, PasswordHasher};
+use serde_json::json;
+use vehicle_auctions::routes::saved_search::{
+    create_saved_search, list_saved_searches, update_saved_search, delete_saved_search,
+};
+use vehicle_auctions::routes::notification::{list_notifications, mark_notification_read, NOTIFICATION_PAGE_SIZE};
+use vehicle_auctions::routes::auction::create_auction;
+use vehicle_auctions::routes::user::{user_login, export_my_data};
+use vehicle_auctions::models::{AuctionSummary, LoginResponse, Notification, SavedSearch, UserExport};
+use vehicle_auctions::errors::{ErrorBody, ErrorCode};
+use vehicle_auctions::notifications::send_daily_digests;
+
+#[actix_web::test]
+async fn test_saved_search_alerts() {
+    dotenv::dotenv().ok();
+    let database_url = std::env::var("DATABASE_URL").expect("DATABASE_URL must be set");
+    let pool = PgPool::connect(&database_url)
+        .await
+        .expect("Failed to connect to database");
+
+    let salt = SaltString::generate(&mut rand::thread_rng());
+    let hashed_password = Argon2::default()
+        .hash_password("password_alerts".as_bytes(), &salt)
+        .unwrap()
+        .to_string();
+
+    let seller_id = sqlx::query_scalar!(
+        "INSERT INTO users (username, password) VALUES ($1, $2) RETURNING id",
+        "testuser_alert_seller",
+        hashed_password
+    ).fetch_one(&pool)
+    .await
+    .unwrap();
+    sqlx::query!(
+        "INSERT INTO users (username, password) VALUES ($1, $2)",
+        "testuser_alert_collector",
+        hashed_password
+    ).execute(&pool)
+    .await
+    .unwrap();
+
+    let vehicle_id = sqlx::query_scalar!(
+        "INSERT INTO vehicles (name, description, starting_price, owner_id, make, model, year)
+         VALUES ('Jensen Interceptor III', 'Chrysler V8, original leather', 40000, $1, 'Jensen', 'Interceptor', 1972)
+         RETURNING id",
+        seller_id
+    ).fetch_one(&pool)
+    .await
+    .unwrap();
+
+    let redis_client_url = std::env::var("REDIS_URL").expect("REDIS_URL must be set");
+    let redis_client = Client::open(redis_client_url).expect("Failed to connect to Redis");
+
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(pool.clone()))
+            .app_data(web::Data::new(redis_client.clone()))
+            .route("/login", web::post().to(user_login))
+            .route("/auctions/create", web::post().to(create_auction))
+            .route("/users/me/export", web::get().to(export_my_data))
+            .route("/users/me/saved-searches", web::post().to(create_saved_search))
+            .route("/users/me/saved-searches", web::get().to(list_saved_searches))
+            .route("/users/me/saved-searches/{id}", web::patch().to(update_saved_search))
+            .route("/users/me/saved-searches/{id}", web::delete().to(delete_saved_search))
+            .route("/users/me/notifications", web::get().to(list_notifications))
+            .route("/users/me/notifications/{id}/read", web::post().to(mark_notification_read)),
+    )
+    .await;
+
+    let login = |username: &str| {
+        test::TestRequest::post()
+            .uri("/login")
+            .set_json(json!({ "username": username, "password": "password_alerts" }))
+            .to_request()
+    };
+    let seller: LoginResponse = test::call_and_read_body_json(&app, login("testuser_alert_seller")).await;
+    let collector: LoginResponse = test::call_and_read_body_json(&app, login("testuser_alert_collector")).await;
+
+    let save = |body: serde_json::Value| {
+        test::TestRequest::post()
+            .uri("/users/me/saved-searches")
+            .insert_header(("Session-Code", collector.session_code.clone()))
+            .set_json(body)
+            .to_request()
+    };
+
+    let immediate: SavedSearch = test::call_and_read_body_json(
+        &app,
+        save(json!({ "name": "Any Jensen", "criteria": { "make": "jensen", "price_max": 50000 } })),
+    )
+    .await;
+    assert_eq!(immediate.frequency, "immediate");
+    assert_eq!(immediate.criteria.price_max, Some(50000.0));
+
+    let daily: SavedSearch = test::call_and_read_body_json(
+        &app,
+        save(json!({ "name": "Interceptors", "frequency": "daily", "criteria": { "q": "interceptor", "year_max": 1975 } })),
+    )
+    .await;
+
+    // A search that does not match this listing
+    let unrelated: SavedSearch = test::call_and_read_body_json(
+        &app,
+        save(json!({ "name": "Cheap Jensens", "criteria": { "make": "Jensen", "price_max": 10000 } })),
+    )
+    .await;
+
+    let resp = test::call_service(&app, save(json!({ "name": "", "frequency": "weekly", "criteria": {} }))).await;
+    assert_eq!(resp.status(), 400);
+    let body: ErrorBody = test::read_body_json(resp).await;
+    let fields: Vec<&str> = body.details.iter().map(|d| d.field.as_str()).collect();
+    assert_eq!(fields, vec!["name", "frequency", "criteria"]);
+
+    let req = test::TestRequest::post()
+        .uri("/auctions/create")
+        .insert_header(("Session-Code", seller.session_code.as_str()))
+        .set_json(json!({ "vehicle_id": vehicle_id, "starting_price": 42000.0, "end_time": "2099-03-01T12:00:00" }))
+        .to_request();
+    let auction: AuctionSummary = test::call_and_read_body_json(&app, req).await;
+
+    let notifications_of = |session: &str, query: &str| {
+        test::TestRequest::get()
+            .uri(&format!("/users/me/notifications{}", query))
+            .insert_header(("Session-Code", session.to_string()))
+            .to_request()
+    };
+
+    // Immediate searches alert right away, daily ones wait for the digest
+    let notifications: Vec<Notification> =
+        test::call_and_read_body_json(&app, notifications_of(&collector.session_code, "")).await;
+    assert_eq!(notifications.len(), 1);
+    assert_eq!(notifications[0].kind, "saved_search_match");
+    assert_eq!(notifications[0].payload["auction_id"], auction.id);
+    assert_eq!(notifications[0].payload["saved_search_id"], immediate.id);
+
+    // Sellers are not alerted about their own listings
+    let notifications: Vec<Notification> =
+        test::call_and_read_body_json(&app, notifications_of(&seller.session_code, "")).await;
+    assert!(notifications.is_empty());
+
+    assert!(send_daily_digests(&pool).await.unwrap() >= 1);
+    assert_eq!(send_daily_digests(&pool).await.unwrap(), 0);
+
+    let notifications: Vec<Notification> =
+        test::call_and_read_body_json(&app, notifications_of(&collector.session_code, "?unread_only=true")).await;
+    assert_eq!(notifications.len(), 2);
+    let digest = notifications.iter().find(|n| n.kind == "saved_search_digest").unwrap();
+    assert_eq!(digest.payload["saved_search_id"], daily.id);
+    assert_eq!(digest.payload["auction_ids"], json!([auction.id]));
+    assert!(!notifications.iter().any(|n| n.payload["saved_search_id"] == unrelated.id));
+
+    let req = test::TestRequest::post()
+        .uri(&format!("/users/me/notifications/{}/read", digest.id))
+        .insert_header(("Session-Code", collector.session_code.as_str()))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 204);
+    let notifications: Vec<Notification> =
+        test::call_and_read_body_json(&app, notifications_of(&collector.session_code, "?unread_only=true")).await;
+    assert_eq!(notifications.len(), 1);
+
+    // Editing replaces the criteria; others cannot touch the search
+    let req = test::TestRequest::patch()
+        .uri(&format!("/users/me/saved-searches/{}", daily.id))
+        .insert_header(("Session-Code", collector.session_code.as_str()))
+        .set_json(json!({ "frequency": "immediate", "criteria": { "model": "Interceptor" } }))
+        .to_request();
+    let updated: SavedSearch = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(updated.name, "Interceptors");
+    assert_eq!(updated.frequency, "immediate");
+    assert_eq!(updated.criteria.model.as_deref(), Some("Interceptor"));
+    assert!(updated.criteria.q.is_none());
+
+    let req = test::TestRequest::delete()
+        .uri(&format!("/users/me/saved-searches/{}", daily.id))
+        .insert_header(("Session-Code", seller.session_code.as_str()))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    let body: ErrorBody = test::read_body_json(resp).await;
+    assert_eq!(body.code, ErrorCode::SavedSearchNotFound);
+
+    let req = test::TestRequest::delete()
+        .uri(&format!("/users/me/saved-searches/{}", unrelated.id))
+        .insert_header(("Session-Code", collector.session_code.as_str()))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 204);
+
+    let req = test::TestRequest::get()
+        .uri("/users/me/saved-searches")
+        .insert_header(("Session-Code", collector.session_code.as_str()))
+        .to_request();
+    let searches: Vec<SavedSearch> = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(searches.iter().map(|s| s.id).collect::<Vec<_>>(), vec![immediate.id, daily.id]);
+
+    let req = test::TestRequest::get()
+        .uri("/users/me/export")
+        .insert_header(("Session-Code", collector.session_code.as_str()))
+        .to_request();
+    let export: UserExport = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(export.saved_searches.len(), 2);
+    assert_eq!(export.notifications.len(), 2);
+
+    // The notification list is capped, the export is not
+    sqlx::query!(
+        "INSERT INTO notifications (user_id, kind, message)
+         SELECT id, 'saved_search_match', 'Older match' FROM users, generate_series(1, $2)
+         WHERE username = $1",
+        "testuser_alert_collector",
+        NOTIFICATION_PAGE_SIZE as i32
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+    let notifications: Vec<Notification> =
+        test::call_and_read_body_json(&app, notifications_of(&collector.session_code, "")).await;
+    assert_eq!(notifications.len() as i64, NOTIFICATION_PAGE_SIZE);
+    let req = test::TestRequest::get()
+        .uri("/users/me/export")
+        .insert_header(("Session-Code", collector.session_code.as_str()))
+        .to_request();
+    let export: UserExport = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(export.notifications.len() as i64, NOTIFICATION_PAGE_SIZE + 2);
+}
@@ -0,0 +1,202 @@
+use actix_web::{test, App, web};
+use redis::Client;
+use sqlx::PgPool;
+use argon2::{password_hash::SaltString, Argon2, PasswordHasher};
+use bigdecimal::BigDecimal;
+use chrono::{Duration, Utc};
+use serde_json::json;
+use vehicle_auctions::routes::auction::{create_auction, place_bid, close_auction, get_auction};
+use vehicle_auctions::routes::bid::list_auction_bids;
+use vehicle_auctions::routes::user::{export_my_data, user_login};
+use vehicle_auctions::models::{
+    AuctionBid, AuctionDetail, AuctionSettlement, AuctionSummary, AuctionType, BidReceipt, LoginResponse, UserExport,
+};
+use vehicle_auctions::errors::{ErrorBody, ErrorCode};
+use vehicle_auctions::sealed;
+
+#[actix_web::test]
+async fn test_sealed_bids_stay_hidden_until_settlement() {
+    dotenv::dotenv().ok();
+    let database_url = std::env::var("DATABASE_URL").expect("DATABASE_URL must be set");
+    let pool = PgPool::connect(&database_url)
+        .await
+        .expect("Failed to connect to database");
+
+    let salt = SaltString::generate(&mut rand::thread_rng());
+    let hashed_password = Argon2::default()
+        .hash_password("password_sealed".as_bytes(), &salt)
+        .unwrap()
+        .to_string();
+
+    let mut user_ids = Vec::new();
+    for username in ["testuser_sealed_seller", "testuser_sealed_first", "testuser_sealed_second"] {
+        let user_id = sqlx::query_scalar!(
+            "INSERT INTO users (username, password) VALUES ($1, $2) RETURNING id",
+            username,
+            hashed_password
+        ).fetch_one(&pool)
+        .await
+        .unwrap();
+        user_ids.push(user_id);
+    }
+    let vehicle_id = sqlx::query_scalar!(
+        "INSERT INTO vehicles (name, description, starting_price, owner_id)
+         VALUES ('Daimler SP250', 'Estate sale', 30000, $1) RETURNING id",
+        user_ids[0]
+    ).fetch_one(&pool)
+    .await
+    .unwrap();
+
+    let redis_client_url = std::env::var("REDIS_URL").expect("REDIS_URL must be set");
+    let redis_client = Client::open(redis_client_url).expect("Failed to connect to Redis");
+
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(pool.clone()))
+            .app_data(web::Data::new(redis_client.clone()))
+            .route("/login", web::post().to(user_login))
+            .route("/auctions/create", web::post().to(create_auction))
+            .route("/auctions/bid", web::post().to(place_bid))
+            .route("/auctions/close/{id}", web::post().to(close_auction))
+            .route("/auctions/{id}", web::get().to(get_auction))
+            .route("/auctions/{id}/bids", web::get().to(list_auction_bids))
+            .route("/users/me/export", web::get().to(export_my_data)),
+    )
+    .await;
+
+    let login = |username: &str| {
+        test::TestRequest::post()
+            .uri("/login")
+            .set_json(json!({ "username": username, "password": "password_sealed" }))
+            .to_request()
+    };
+    let seller: LoginResponse = test::call_and_read_body_json(&app, login("testuser_sealed_seller")).await;
+    let first: LoginResponse = test::call_and_read_body_json(&app, login("testuser_sealed_first")).await;
+    let second: LoginResponse = test::call_and_read_body_json(&app, login("testuser_sealed_second")).await;
+
+    let req = test::TestRequest::post()
+        .uri("/auctions/create")
+        .insert_header(("Session-Code", seller.session_code.clone()))
+        .set_json(json!({
+            "vehicle_id": vehicle_id,
+            "starting_price": 30000,
+            "end_time": (Utc::now() + Duration::days(10)).format("%Y-%m-%dT%H:%M:%S").to_string(),
+            "auction_type": "sealed",
+        }))
+        .to_request();
+    if sealed::ensure_configured().is_err() {
+        // Without SEALED_BID_KEY the server runs, but refuses sealed auctions
+        let body: ErrorBody = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body.code, ErrorCode::SealedBiddingUnavailable);
+        return;
+    }
+    let auction: AuctionSummary = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(auction.auction_type, AuctionType::Sealed);
+
+    let bid = |session: &str, amount: i32| {
+        test::TestRequest::post()
+            .uri("/auctions/bid")
+            .insert_header(("Session-Code", session.to_string()))
+            .set_json(json!({ "auction_id": auction.id, "bid_amount": amount }))
+            .to_request()
+    };
+
+    let body: ErrorBody = test::call_and_read_body_json(&app, bid(&first.session_code, 29000)).await;
+    assert_eq!(body.code, ErrorCode::BidTooLow);
+    let placed: BidReceipt = test::call_and_read_body_json(&app, bid(&first.session_code, 31000)).await;
+    assert_eq!(placed.bid_amount, BigDecimal::from(31000));
+
+    // No minimum increment over others, since nobody can see them
+    let resp = test::call_service(&app, bid(&second.session_code, 30500)).await;
+    assert_eq!(resp.status(), 200);
+
+    // One bid per bidder, which can only go up
+    let body: ErrorBody = test::call_and_read_body_json(&app, bid(&first.session_code, 30000)).await;
+    assert_eq!(body.code, ErrorCode::BidTooLow);
+    let raised: BidReceipt = test::call_and_read_body_json(&app, bid(&first.session_code, 32000)).await;
+    assert_eq!(raised.id, placed.id);
+
+    // Nobody, the seller included, sees amounts before the close; they are not stored in the clear
+    let req = test::TestRequest::get().uri(&format!("/auctions/{}", auction.id)).to_request();
+    let detail: AuctionDetail = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(detail.highest_bid, None);
+    assert_eq!(detail.bid_count, 0);
+    let list_bids = || {
+        test::TestRequest::get()
+            .uri(&format!("/auctions/{}/bids", auction.id))
+            .insert_header(("Session-Code", seller.session_code.clone()))
+            .to_request()
+    };
+    let bids: Vec<AuctionBid> = test::call_and_read_body_json(&app, list_bids()).await;
+    assert!(bids.is_empty());
+
+    let sealed = sqlx::query_scalar!("SELECT sealed_amount FROM sealed_bids WHERE auction_id = $1", auction.id)
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+    assert_eq!(sealed.len(), 2);
+    assert!(sealed.iter().all(|amount| {
+        let amount = String::from_utf8_lossy(amount);
+        !amount.contains("32000") && !amount.contains("30500")
+    }));
+
+    // Bidders still get their own amount in their data export
+    let req = test::TestRequest::get()
+        .uri("/users/me/export")
+        .insert_header(("Session-Code", first.session_code.clone()))
+        .to_request();
+    let export: UserExport = test::call_and_read_body_json(&app, req).await;
+    assert!(export.bids.is_empty());
+    let exported: Vec<_> = export.sealed_bids.iter().map(|bid| (bid.auction_id, bid.bid_amount.clone())).collect();
+    assert_eq!(exported, vec![(auction.id, BigDecimal::from(32000))]);
+
+    let close = || {
+        test::TestRequest::post()
+            .uri(&format!("/auctions/close/{}", auction.id))
+            .insert_header(("Session-Code", seller.session_code.clone()))
+            .to_request()
+    };
+    let body: ErrorBody = test::call_and_read_body_json(&app, close()).await;
+    assert_eq!(body.code, ErrorCode::AuctionNotEnded);
+    let raised: BidReceipt = test::call_and_read_body_json(&app, bid(&first.session_code, 33000)).await;
+    assert_eq!(raised.bid_amount, BigDecimal::from(33000));
+
+    // After the end every bid is revealed at once and the highest wins at its own price
+    sqlx::query!("UPDATE auctions SET end_time = NOW() - INTERVAL '1 minute' WHERE id = $1", auction.id)
+        .execute(&pool)
+        .await
+        .unwrap();
+    let settlement: AuctionSettlement = test::call_and_read_body_json(&app, close()).await;
+    assert_eq!(settlement.winner_username.as_deref(), Some("testuser_sealed_first"));
+    assert_eq!(settlement.winning_bid, Some(BigDecimal::from(33000)));
+
+    let remaining = sqlx::query_scalar!("SELECT COUNT(*) FROM sealed_bids WHERE auction_id = $1", auction.id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(remaining, Some(0));
+    let bids: Vec<AuctionBid> = test::call_and_read_body_json(&app, list_bids()).await;
+    let revealed: Vec<_> = bids.iter().map(|bid| (bid.bidder_username.as_str(), bid.bid_amount.clone())).collect();
+    assert_eq!(
+        revealed,
+        vec![
+            ("testuser_sealed_first", BigDecimal::from(33000)),
+            ("testuser_sealed_second", BigDecimal::from(30500)),
+        ]
+    );
+}
+
+#[actix_web::test]
+async fn test_sealed_bid_key_requires_a_long_trimmed_secret() {
+    let secret = "test-only-sealed-bid-key-never-deployed";
+    let key = sealed::key_from_secret(Some(secret)).unwrap();
+    // Surrounding whitespace neither counts towards the length nor changes the key
+    assert_eq!(sealed::key_from_secret(Some(&format!("  {}\n", secret))).unwrap(), key);
+    let short = format!(" {} ", &secret[..sealed::MIN_SECRET_LENGTH - 1]);
+    assert!(short.len() > sealed::MIN_SECRET_LENGTH);
+
+    for secret in [None, Some(""), Some(short.as_str())] {
+        let err = sealed::key_from_secret(secret).unwrap_err();
+        assert_eq!(err.code, ErrorCode::SealedBiddingUnavailable);
+    }
+}
@@ -0,0 +1,84 @@
+use actix_web::{test, App, web};
+use sqlx::PgPool;
+use vehicle_auctions::routes::search::search_vehicles;
+use vehicle_auctions::models::SearchResults;
+
+#[actix_web::test]
+async fn test_full_text_search_with_fuzzy_fallback_and_facets() {
+    dotenv::dotenv().ok();
+    let database_url = std::env::var("DATABASE_URL").expect("DATABASE_URL must be set");
+    let pool = PgPool::connect(&database_url)
+        .await
+        .expect("Failed to connect to database");
+
+    let owner_id = sqlx::query_scalar!(
+        "INSERT INTO users (username, password) VALUES ($1, $2) RETURNING id",
+        "testuser_search",
+        "hashedpassword"
+    )
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+
+    let zagato_id = sqlx::query_scalar!(
+        "INSERT INTO vehicles (name, description, starting_price, owner_id, make, model, year)
+         VALUES ('Lancia Flavia Sport Zagato', 'Aluminium body with the <double-bubble> roof, fully restored', 180000, $1,
+                 'Lancia', 'Flavia', 1964)
+         RETURNING id",
+        owner_id
+    )
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+
+    sqlx::query!(
+        "INSERT INTO vehicles (name, description, starting_price, owner_id, make, model, year)
+         VALUES ('Lancia Aurelia B20', 'Zagato-style coachwork conversion', 45000, $1, 'Lancia', 'Aurelia', 1957)",
+        owner_id
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(pool.clone()))
+            .route("/vehicles/search", web::get().to(search_vehicles)),
+    )
+    .await;
+
+    // Name matches rank above description matches
+    let req = test::TestRequest::get().uri("/vehicles/search?q=lancia%20zagato").to_request();
+    let results: SearchResults = test::call_and_read_body_json(&app, req).await;
+    assert!(!results.fuzzy);
+    assert_eq!(results.total_count, 2);
+    assert_eq!(results.items[0].vehicle.id, zagato_id);
+    assert!(results.items[0].rank > results.items[1].rank);
+    assert!(results.items[0].name_highlight.contains("<mark>Zagato</mark>"));
+    assert!(results.items[0].description_highlight.contains("&lt;double-bubble&gt;"));
+    assert!(!results.items[0].description_highlight.contains("<double"));
+
+    let lancia = results.facets.makes.iter().find(|f| f.value == "Lancia").unwrap();
+    assert_eq!(lancia.count, 2);
+    let decades: Vec<&str> = results.facets.decades.iter().map(|f| f.value.as_str()).collect();
+    assert!(decades.contains(&"1960s") && decades.contains(&"1950s"));
+    assert!(results.facets.price_buckets.iter().any(|f| f.value == "100k_250k" && f.count == 1));
+
+    // Facet filters narrow the result set
+    let req = test::TestRequest::get()
+        .uri("/vehicles/search?q=lancia%20zagato&decade=1950&price_max=50000")
+        .to_request();
+    let results: SearchResults = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(results.total_count, 1);
+    assert_eq!(results.items[0].vehicle.name, "Lancia Aurelia B20");
+
+    // Typos fall back to trigram matching
+    let req = test::TestRequest::get().uri("/vehicles/search?q=flavia%20zagatto").to_request();
+    let results: SearchResults = test::call_and_read_body_json(&app, req).await;
+    assert!(results.fuzzy);
+    assert_eq!(results.items[0].vehicle.id, zagato_id);
+
+    let req = test::TestRequest::get().uri("/vehicles/search?q=%20").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 400);
+}
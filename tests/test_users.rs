@@ -115,11 +115,20 @@
 mod tests {
     use actix_web::{test, web, App};
     use redis::Client;
-    use sqlx::PgPool;
+    use sqlx::postgres::PgPoolOptions;
+    use sqlx::{Executor, PgPool};
     use serde_json::json;
     use argon2::{password_hash::SaltString, Argon2, PasswordHasher};
     use redis::AsyncCommands;
-    use vehicle_auctions::routes::user::{user_register, user_login};
+    use vehicle_auctions::routes::user::{
+        user_register, user_login, get_my_profile, update_my_profile, get_public_profile, export_my_data,
+        delete_my_account, change_username,
+    };
+    use vehicle_auctions::models::{LoginResponse, UserSummary, UserProfile, PublicProfile, UserExport};
+    use vehicle_auctions::errors::{ErrorBody, ErrorCode};
+    use vehicle_auctions::storage::{LocalStorage, Storage};
+    use std::sync::Arc;
+    use chrono::{Duration, Utc};
     
 
     #[actix_web::test]
@@ -148,8 +157,8 @@ mod tests {
         let resp = test::call_service(&app, req).await;
         assert_eq!(resp.status(), 200);
 
-        let body = test::read_body(resp).await;
-        assert_eq!(body, "User Registered");
+        let user: UserSummary = test::read_body_json(resp).await;
+        assert_eq!(user.username, "testuser");
     }
 
     #[actix_web::test]
@@ -182,11 +191,41 @@ mod tests {
             .set_json(json!({ "username": "testuser2", "password": "testpassword" }))
             .to_request();
 
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 409);
+
+        let body: ErrorBody = test::read_body_json(resp).await;
+        assert_eq!(body.code, ErrorCode::UsernameTaken);
+        assert_eq!(body.message, "Username already taken");
+    }
+
+    #[actix_web::test]
+    async fn test_user_register_validation_error() {
+        dotenv::dotenv().ok();
+        let database_url = std::env::var("DATABASE_URL").expect("DATABASE_URL must be set");
+        let pool = PgPool::connect(&database_url)
+            .await
+            .expect("Failed to connect to database");
+
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(pool.clone()))
+                .route("/users/register", web::post().to(user_register)),
+        )
+        .await;
+
+        let req = test::TestRequest::post()
+            .uri("/users/register")
+            .set_json(json!({ "username": " ", "password": "" }))
+            .to_request();
+
         let resp = test::call_service(&app, req).await;
         assert_eq!(resp.status(), 400);
 
-        let body = test::read_body(resp).await;
-        assert_eq!(body, "Username already taken");
+        let body: ErrorBody = test::read_body_json(resp).await;
+        assert_eq!(body.code, ErrorCode::ValidationFailed);
+        let fields: Vec<&str> = body.details.iter().map(|d| d.field.as_str()).collect();
+        assert_eq!(fields, vec!["username", "password"]);
     }
 
     #[actix_web::test]
@@ -234,12 +273,391 @@ mod tests {
         let resp = test::call_service(&app, req).await;
         assert_eq!(resp.status(), 200);
 
-        let body: String = test::read_body_json(resp).await;
-        assert!(body.contains("Login successful"));
+        let body: LoginResponse = test::read_body_json(resp).await;
+        assert_eq!(body.user.username, "testuser3");
 
         // Verify session in Redis
-        let session_key = format!("session:{}", body.split(':').last().unwrap().trim());
-        let username: String = redis_conn.get(session_key).await.unwrap();
-        assert_eq!(username, "testuser3");
+        let session_key = format!("session:{}", body.session_code);
+        let user_id: i32 = redis_conn.get(session_key).await.unwrap();
+        assert_eq!(user_id, body.user.id);
+    }
+
+    #[actix_web::test]
+    async fn test_user_profile_update_and_public_page() {
+        dotenv::dotenv().ok();
+        let database_url = std::env::var("DATABASE_URL").expect("DATABASE_URL must be set");
+        let pool = PgPool::connect(&database_url)
+            .await
+            .expect("Failed to connect to database");
+
+        let salt = SaltString::generate(&mut rand::thread_rng());
+        let hashed_password = Argon2::default()
+            .hash_password("profilepassword".as_bytes(), &salt)
+            .unwrap()
+            .to_string();
+
+        sqlx::query!(
+            "INSERT INTO users (username, password) VALUES ($1, $2)",
+            "testuser_profile",
+            hashed_password
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        let redis_client_url = std::env::var("REDIS_URL").expect("REDIS_URL must be set");
+        let redis_client = Client::open(redis_client_url).expect("Failed to connect to Redis");
+
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(pool.clone()))
+                .app_data(web::Data::new(redis_client.clone()))
+                .route("/login", web::post().to(user_login))
+                .route("/users/me", web::get().to(get_my_profile))
+                .route("/users/me", web::patch().to(update_my_profile))
+                .route("/users/{username}", web::get().to(get_public_profile)),
+        )
+        .await;
+
+        let req = test::TestRequest::post()
+            .uri("/login")
+            .set_json(json!({ "username": "testuser_profile", "password": "profilepassword" }))
+            .to_request();
+        let login: LoginResponse = test::call_and_read_body_json(&app, req).await;
+
+        let req = test::TestRequest::patch()
+            .uri("/users/me")
+            .insert_header(("Session-Code", login.session_code.as_str()))
+            .set_json(json!({ "display_name": "Classic Cars Ltd", "location": "Novi Sad", "is_dealer": true }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 200);
+        let profile: UserProfile = test::read_body_json(resp).await;
+        assert_eq!(profile.display_name.as_deref(), Some("Classic Cars Ltd"));
+        assert!(profile.is_dealer);
+
+        // Empty strings clear a field, absent fields are left alone
+        let req = test::TestRequest::patch()
+            .uri("/users/me")
+            .insert_header(("Session-Code", login.session_code.as_str()))
+            .set_json(json!({ "location": "" }))
+            .to_request();
+        let profile: UserProfile = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(profile.location, None);
+        assert_eq!(profile.display_name.as_deref(), Some("Classic Cars Ltd"));
+
+        let req = test::TestRequest::get().uri("/users/testuser_profile").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 200);
+        let page: PublicProfile = test::read_body_json(resp).await;
+        assert_eq!(page.profile.username, "testuser_profile");
+        assert!(page.vehicles.is_empty());
+        assert_eq!(page.sales_count, 0);
+
+        let req = test::TestRequest::get().uri("/users/no_such_user_profile").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 404);
+
+        // An auction ending within the hour is active whatever the database session's time zone
+        let auction_id = sqlx::query_scalar!(
+            "WITH vehicle AS (
+                 INSERT INTO vehicles (name, description, starting_price, owner_id)
+                 SELECT 'Lancia Fulvia', '', 12000, id FROM users WHERE username = 'testuser_profile'
+                 RETURNING id, owner_id
+             )
+             INSERT INTO auctions (vehicle_id, starting_price, end_time, seller_id, status)
+             SELECT id, 12000, $1, owner_id, 'live' FROM vehicle RETURNING id",
+            Utc::now().naive_utc() + Duration::hours(1)
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        let far_east_pool = PgPoolOptions::new()
+            .after_connect(|conn, _| Box::pin(async move {
+                conn.execute("SET TIME ZONE 'Pacific/Kiritimati'").await?;
+                Ok(())
+            }))
+            .connect(&database_url)
+            .await
+            .unwrap();
+        let far_east_app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(far_east_pool))
+                .route("/users/{username}", web::get().to(get_public_profile)),
+        )
+        .await;
+        let req = test::TestRequest::get().uri("/users/testuser_profile").to_request();
+        let page: PublicProfile = test::call_and_read_body_json(&far_east_app, req).await;
+        assert_eq!(page.active_auctions.iter().map(|a| a.id).collect::<Vec<_>>(), vec![auction_id]);
+    }
+
+    #[actix_web::test]
+    async fn test_user_export_and_delete_account() {
+        dotenv::dotenv().ok();
+        let database_url = std::env::var("DATABASE_URL").expect("DATABASE_URL must be set");
+        let pool = PgPool::connect(&database_url)
+            .await
+            .expect("Failed to connect to database");
+
+        let salt = SaltString::generate(&mut rand::thread_rng());
+        let hashed_password = Argon2::default()
+            .hash_password("gdprpassword".as_bytes(), &salt)
+            .unwrap()
+            .to_string();
+
+        let user_id = sqlx::query_scalar!(
+            "INSERT INTO users (username, password) VALUES ($1, $2) RETURNING id",
+            "testuser_gdpr",
+            hashed_password
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+
+        // A vehicle with an open auction blocks deletion
+        let vehicle_id = sqlx::query_scalar!(
+            "INSERT INTO vehicles (name, description, starting_price, owner_id) VALUES ($1, $2, 1000, $3) RETURNING id",
+            "GDPR Car",
+            "Listed by a user who wants to leave",
+            user_id
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        let auction_id = sqlx::query_scalar!(
+            "INSERT INTO auctions (vehicle_id, starting_price, end_time, seller_id)
+             VALUES ($1, 1000, NOW() + INTERVAL '1 day', $2) RETURNING id",
+            vehicle_id,
+            user_id
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+
+        // A never auctioned vehicle goes with the account, and so do its stored files
+        let garage_vehicle_id = sqlx::query_scalar!(
+            "INSERT INTO vehicles (name, description, starting_price, owner_id)
+             VALUES ('GDPR Garage Car', 'Never listed', 1000, $1) RETURNING id",
+            user_id
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        let stored_keys = [
+            format!("images/{}/gdpr-web.jpg", garage_vehicle_id),
+            format!("images/{}/gdpr-thumb.jpg", garage_vehicle_id),
+            format!("documents/{}/gdpr-title.pdf", garage_vehicle_id),
+        ];
+        let storage_dir = tempfile::tempdir().unwrap();
+        let storage: Arc<dyn Storage> = Arc::new(LocalStorage::new(storage_dir.path()));
+        for key in &stored_keys {
+            storage.put(key, "application/octet-stream", "stored".into()).await.unwrap();
+        }
+        sqlx::query!(
+            "INSERT INTO vehicle_images
+                 (vehicle_id, position, is_primary, web_key, thumbnail_key, content_type, width, height)
+             VALUES ($1, 0, TRUE, $2, $3, 'image/jpeg', 1600, 1200)",
+            garage_vehicle_id,
+            stored_keys[0],
+            stored_keys[1]
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        let title_document_id = sqlx::query_scalar!(
+            "INSERT INTO vehicle_documents
+                 (vehicle_id, doc_type, title, storage_key, content_type, byte_size, uploaded_by)
+             VALUES ($1, 'title', 'Title', $2, 'application/pdf', 6, $3) RETURNING id",
+            garage_vehicle_id,
+            stored_keys[2],
+            user_id
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        // The title backs an earlier owner in the vehicle's history
+        sqlx::query!(
+            "INSERT INTO vehicle_ownership (vehicle_id, kind, to_name, transferred_at, evidence_document_id)
+             VALUES ($1, 'provenance', 'First owner', '1965-04-01', $2)",
+            garage_vehicle_id,
+            title_document_id
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let redis_client_url = std::env::var("REDIS_URL").expect("REDIS_URL must be set");
+        let redis_client = Client::open(redis_client_url).expect("Failed to connect to Redis");
+
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(pool.clone()))
+                .app_data(web::Data::new(redis_client.clone()))
+                .app_data(web::Data::from(storage.clone()))
+                .route("/login", web::post().to(user_login))
+                .route("/users/me", web::get().to(get_my_profile))
+                .route("/users/me", web::delete().to(delete_my_account))
+                .route("/users/me/export", web::get().to(export_my_data)),
+        )
+        .await;
+
+        let req = test::TestRequest::post()
+            .uri("/login")
+            .set_json(json!({ "username": "testuser_gdpr", "password": "gdprpassword" }))
+            .to_request();
+        let login: LoginResponse = test::call_and_read_body_json(&app, req).await;
+
+        let req = test::TestRequest::get()
+            .uri("/users/me/export")
+            .insert_header(("Session-Code", login.session_code.as_str()))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 200);
+        let export: UserExport = test::read_body_json(resp).await;
+        assert_eq!(export.profile.username, "testuser_gdpr");
+        assert_eq!(export.vehicles.len(), 2);
+        assert_eq!(export.auctions.len(), 1);
+
+        let req = test::TestRequest::delete()
+            .uri("/users/me")
+            .insert_header(("Session-Code", login.session_code.as_str()))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 409);
+        let body: ErrorBody = test::read_body_json(resp).await;
+        assert_eq!(body.code, ErrorCode::AccountDeletionBlocked);
+
+        sqlx::query!("UPDATE auctions SET status = 'ended_unsold' WHERE id = $1", auction_id)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let req = test::TestRequest::delete()
+            .uri("/users/me")
+            .insert_header(("Session-Code", login.session_code.as_str()))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 204);
+
+        // The session is revoked and the auction history is kept under an anonymized name
+        let req = test::TestRequest::get()
+            .uri("/users/me")
+            .insert_header(("Session-Code", login.session_code.as_str()))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 401);
+
+        let seller = sqlx::query_scalar!(
+            "SELECT u.username FROM auctions a INNER JOIN users u ON u.id = a.seller_id WHERE a.id = $1",
+            auction_id
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(seller, format!("deleted-user-{}", user_id));
+
+        for key in &stored_keys {
+            assert!(storage.get(key).await.unwrap().is_none());
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_change_username_keeps_session() {
+        dotenv::dotenv().ok();
+        let database_url = std::env::var("DATABASE_URL").expect("DATABASE_URL must be set");
+        let pool = PgPool::connect(&database_url)
+            .await
+            .expect("Failed to connect to database");
+
+        let salt = SaltString::generate(&mut rand::thread_rng());
+        let hashed_password = Argon2::default()
+            .hash_password("renamepassword".as_bytes(), &salt)
+            .unwrap()
+            .to_string();
+
+        sqlx::query!(
+            "INSERT INTO users (username, password) VALUES ($1, $2), ($3, $2)",
+            "testuser_rename",
+            hashed_password,
+            "testuser_rename_taken"
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let redis_client_url = std::env::var("REDIS_URL").expect("REDIS_URL must be set");
+        let redis_client = Client::open(redis_client_url).expect("Failed to connect to Redis");
+
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(pool.clone()))
+                .app_data(web::Data::new(redis_client.clone()))
+                .route("/login", web::post().to(user_login))
+                .route("/users/me", web::get().to(get_my_profile))
+                .route("/users/me/username", web::patch().to(change_username)),
+        )
+        .await;
+
+        let req = test::TestRequest::post()
+            .uri("/login")
+            .set_json(json!({ "username": "testuser_rename", "password": "renamepassword" }))
+            .to_request();
+        let login: LoginResponse = test::call_and_read_body_json(&app, req).await;
+
+        let req = test::TestRequest::patch()
+            .uri("/users/me/username")
+            .insert_header(("Session-Code", login.session_code.as_str()))
+            .set_json(json!({ "username": "testuser_rename_taken" }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 409);
+
+        let req = test::TestRequest::patch()
+            .uri("/users/me/username")
+            .insert_header(("Session-Code", login.session_code.as_str()))
+            .set_json(json!({ "username": "testuser_renamed" }))
+            .to_request();
+        let renamed: UserSummary = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(renamed.id, login.user.id);
+        assert_eq!(renamed.username, "testuser_renamed");
+
+        let req = test::TestRequest::get()
+            .uri("/users/me")
+            .insert_header(("Session-Code", login.session_code.as_str()))
+            .to_request();
+        let profile: UserProfile = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(profile.username, "testuser_renamed");
+
+        // A rename that commits while ours is checking makes ours wait on the unique index, then conflict
+        let mut rival = pool.begin().await.unwrap();
+        sqlx::query!("UPDATE users SET username = 'testuser_rename_race' WHERE username = 'testuser_rename_taken'")
+            .execute(&mut *rival)
+            .await
+            .unwrap();
+        let req = test::TestRequest::patch()
+            .uri("/users/me/username")
+            .insert_header(("Session-Code", login.session_code.as_str()))
+            .set_json(json!({ "username": "testuser_rename_race" }))
+            .to_request();
+        let (resp, _) = tokio::join!(test::call_service(&app, req), async {
+            actix_web::rt::time::sleep(std::time::Duration::from_millis(300)).await;
+            rival.commit().await.unwrap();
+        });
+        assert_eq!(resp.status(), 409);
+        let body: ErrorBody = test::read_body_json(resp).await;
+        assert_eq!(body.code, ErrorCode::UsernameTaken);
+
+        // Sessions from before user ids were stored hold a username and count as expired
+        let mut redis_conn = redis_client.get_multiplexed_async_connection().await.unwrap();
+        let legacy_code = uuid::Uuid::new_v4().to_string();
+        let _: () = redis_conn
+            .set_ex(format!("session:{}", legacy_code), "testuser_renamed", 60)
+            .await
+            .unwrap();
+        let req = test::TestRequest::get()
+            .uri("/users/me")
+            .insert_header(("Session-Code", legacy_code.as_str()))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 401);
     }
-}
\ No newline at end of file
+}
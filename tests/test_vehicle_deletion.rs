@@ -0,0 +1,178 @@
+use actix_web::{test, App, web};
+use redis::Client;
+use sqlx::PgPool;
+use argon2::{password_hash::SaltString, Argon2, PasswordHasher};
+use serde_json::json;
+use vehicle_auctions::routes::vehicle::{delete_vehicle, get_vehicle, list_vehicles, restore_vehicle};
+use vehicle_auctions::routes::auction::get_auction;
+use vehicle_auctions::routes::user::user_login;
+use vehicle_auctions::models::{AuctionDetail, LoginResponse, Vehicle, VehiclePage};
+use vehicle_auctions::errors::{ErrorBody, ErrorCode};
+
+#[actix_web::test]
+async fn test_soft_delete_and_restore_vehicle() {
+    dotenv::dotenv().ok();
+    let database_url = std::env::var("DATABASE_URL").expect("DATABASE_URL must be set");
+    let pool = PgPool::connect(&database_url)
+        .await
+        .expect("Failed to connect to database");
+
+    let salt = SaltString::generate(&mut rand::thread_rng());
+    let hashed_password = Argon2::default()
+        .hash_password("password_archive".as_bytes(), &salt)
+        .unwrap()
+        .to_string();
+
+    let mut user_ids = Vec::new();
+    for (username, is_admin) in [("testuser_archive_seller", false), ("testuser_archive_bidder", false), ("testuser_archive_admin", true)] {
+        let user_id = sqlx::query_scalar!(
+            "INSERT INTO users (username, password, is_admin) VALUES ($1, $2, $3) RETURNING id",
+            username,
+            hashed_password,
+            is_admin
+        ).fetch_one(&pool)
+        .await
+        .unwrap();
+        user_ids.push(user_id);
+    }
+    let (seller_id, bidder_id) = (user_ids[0], user_ids[1]);
+
+    let mut vehicle_ids = Vec::new();
+    for (name, vin) in [("Triumph TR4", None), ("Sunbeam Tiger", Some("1HGCM82633A004352"))] {
+        let vehicle_id = sqlx::query_scalar!(
+            "INSERT INTO vehicles (name, description, starting_price, owner_id, vin) VALUES ($1, '', 25000, $2, $3) RETURNING id",
+            name,
+            seller_id,
+            vin
+        ).fetch_one(&pool)
+        .await
+        .unwrap();
+        vehicle_ids.push(vehicle_id);
+    }
+    let (bid_on, archived) = (vehicle_ids[0], vehicle_ids[1]);
+
+    let new_auction = |vehicle_id: i32, closed: bool| {
+        sqlx::query_scalar!(
+            "INSERT INTO auctions (vehicle_id, starting_price, end_time, seller_id, status)
+             VALUES ($1, 25000, '2099-01-01', $2, CASE WHEN $3 THEN 'ended_unsold' ELSE 'live' END::auction_status)
+             RETURNING id",
+            vehicle_id,
+            seller_id,
+            closed
+        )
+        .fetch_one(&pool)
+    };
+    let live_auction = new_auction(bid_on, false).await.unwrap();
+    let past_auction = new_auction(archived, true).await.unwrap();
+    let open_auction = new_auction(archived, false).await.unwrap();
+    for auction_id in [live_auction, past_auction] {
+        sqlx::query!(
+            "INSERT INTO bids (auction_id, bidder_id, bid_amount) VALUES ($1, $2, 26000)",
+            auction_id,
+            bidder_id
+        ).execute(&pool)
+        .await
+        .unwrap();
+    }
+
+    let redis_client_url = std::env::var("REDIS_URL").expect("REDIS_URL must be set");
+    let redis_client = Client::open(redis_client_url).expect("Failed to connect to Redis");
+
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(pool.clone()))
+            .app_data(web::Data::new(redis_client.clone()))
+            .route("/login", web::post().to(user_login))
+            .route("/vehicles/list", web::get().to(list_vehicles))
+            .route("/vehicles/delete/{id}", web::delete().to(delete_vehicle))
+            .route("/vehicles/{id}", web::get().to(get_vehicle))
+            .route("/auctions/{id}", web::get().to(get_auction))
+            .route("/admin/vehicles/{id}/restore", web::post().to(restore_vehicle)),
+    )
+    .await;
+
+    let login = |username: &str| {
+        test::TestRequest::post()
+            .uri("/login")
+            .set_json(json!({ "username": username, "password": "password_archive" }))
+            .to_request()
+    };
+    let seller: LoginResponse = test::call_and_read_body_json(&app, login("testuser_archive_seller")).await;
+    let admin: LoginResponse = test::call_and_read_body_json(&app, login("testuser_archive_admin")).await;
+
+    let delete = |vehicle_id: i32| {
+        test::TestRequest::delete()
+            .uri(&format!("/vehicles/delete/{}", vehicle_id))
+            .insert_header(("Session-Code", seller.session_code.clone()))
+            .to_request()
+    };
+
+    // Bidders keep their auction
+    let resp = test::call_service(&app, delete(bid_on)).await;
+    let body: ErrorBody = test::read_body_json(resp).await;
+    assert_eq!(body.code, ErrorCode::VehicleHasLiveBids);
+
+    let resp = test::call_service(&app, delete(archived)).await;
+    assert_eq!(resp.status(), 204);
+
+    let req = test::TestRequest::get().uri(&format!("/vehicles/{}", archived)).to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 404);
+
+    let req = test::TestRequest::get().uri("/vehicles/list?owner=testuser_archive_seller").to_request();
+    let page: VehiclePage = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(page.items.iter().map(|v| v.id).collect::<Vec<_>>(), vec![bid_on]);
+
+    // The auction without bids ended with the listing; history is untouched
+    let req = test::TestRequest::get().uri(&format!("/auctions/{}", open_auction)).to_request();
+    let detail: AuctionDetail = test::call_and_read_body_json(&app, req).await;
+    assert!(detail.closed);
+    let req = test::TestRequest::get().uri(&format!("/auctions/{}", past_auction)).to_request();
+    let detail: AuctionDetail = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(detail.bid_count, 1);
+
+    // A hard delete is refused by the database
+    sqlx::query!("DELETE FROM vehicles WHERE id = $1", archived)
+        .execute(&pool)
+        .await
+        .expect_err("auctions must not cascade away");
+
+    let restore = |session: &str| {
+        test::TestRequest::post()
+            .uri(&format!("/admin/vehicles/{}/restore", archived))
+            .insert_header(("Session-Code", session.to_string()))
+            .to_request()
+    };
+
+    let resp = test::call_service(&app, restore(&seller.session_code)).await;
+    let body: ErrorBody = test::read_body_json(resp).await;
+    assert_eq!(body.code, ErrorCode::NotAdmin);
+
+    // The VIN was listed again meanwhile
+    let relisted = sqlx::query_scalar!(
+        "INSERT INTO vehicles (name, description, starting_price, owner_id, vin)
+         VALUES ('Sunbeam Tiger', '', 27000, $1, '1HGCM82633A004352') RETURNING id",
+        bidder_id
+    ).fetch_one(&pool)
+    .await
+    .unwrap();
+
+    let resp = test::call_service(&app, restore(&admin.session_code)).await;
+    let body: ErrorBody = test::read_body_json(resp).await;
+    assert_eq!(body.code, ErrorCode::DuplicateVin);
+
+    sqlx::query!("UPDATE vehicles SET vin = NULL WHERE id = $1", relisted)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let restored: Vehicle = test::call_and_read_body_json(&app, restore(&admin.session_code)).await;
+    assert_eq!(restored.id, archived);
+
+    let req = test::TestRequest::get().uri(&format!("/vehicles/{}", archived)).to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 200);
+
+    let resp = test::call_service(&app, restore(&admin.session_code)).await;
+    assert_eq!(resp.status(), 404);
+}
@@ -2,8 +2,10 @@ use actix_web::{test, App, web};
 use actix_web::http::header::HeaderValue;
 use redis::Client;
 use sqlx::{PgPool, Executor};
-use vehicle_auctions::routes::vehicle::{create_vehicle, list_vehicles}; // Replace with your app module path
-use vehicle_auctions::models::{CreateVehicle, Vehicle};
+use vehicle_auctions::routes::vehicle::{create_vehicle, list_vehicles, get_vehicle, update_vehicle, decode_vin}; // Replace with your app module path
+use vehicle_auctions::models::{CreateVehicle, Vehicle, LoginResponse, VehicleDetail, VehiclePage, VehicleSpec};
+use vehicle_auctions::errors::{ErrorBody, ErrorCode};
+use vehicle_auctions::vin::VinInfo;
 use vehicle_auctions::routes::user::user_login;
 use argon2::{password_hash::SaltString, Argon2, PasswordHasher};
 use serde_json::json;
@@ -57,18 +59,16 @@ async fn test_create_vehicle_success() {
     let resp = test::call_service(&app, req).await;
     assert_eq!(resp.status(), 200);
 
-        let body: String = test::read_body_json(resp).await;
-        assert!(body.contains("Login successful"));
+        let body: LoginResponse = test::read_body_json(resp).await;
+        let session_key = body.session_code;
 
-        // Verify session in Redis
-        let session_key = format!("{}", body.split(':').last().unwrap().trim());
-        
 
     // Mock request
     let form = CreateVehicle {
         name: "Test Vehicle".to_string(),
         description: "A vehicle for testing".to_string(),
         starting_price: 1000.01,
+        ..Default::default()
     };
 
     let req2 = test::TestRequest::post()
@@ -84,8 +84,8 @@ async fn test_create_vehicle_success() {
 
     // Assert response
     assert_eq!(resp2.status(), 200, "Expected HTTP 200 OK");
-    let body2 = test::read_body(resp2).await;
-    assert_eq!(body2, "Vehicle Created");
+    let vehicle: Vehicle = test::read_body_json(resp2).await;
+    assert_eq!(vehicle.name, "Test Vehicle");
 }
 
 
@@ -117,24 +117,33 @@ async fn test_list_vehicles_postgres() {
 
    //sqlx::query!("DELETE FROM vehicles").execute(&pool).await.unwrap();
 
+    let owner_id = sqlx::query_scalar!(
+        "INSERT INTO users (username, password) VALUES ($1, $2) RETURNING id",
+        "testuser_list",
+        "hashedpassword"
+    )
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+
     // Insert mock vehicles
     sqlx::query!(
-        "INSERT INTO vehicles (name, description, starting_price, owner_username) VALUES ($1, $2, $3, $4)",
+        "INSERT INTO vehicles (name, description, starting_price, owner_id) VALUES ($1, $2, $3, $4)",
         "Car 1",
         "Description 1",
         starting_price1,
-        "testuser4"
+        owner_id
     )
     .execute(&pool)
     .await
     .unwrap();
 
     sqlx::query!(
-        "INSERT INTO vehicles (name, description, starting_price, owner_username) VALUES ($1, $2, $3, $4)",
+        "INSERT INTO vehicles (name, description, starting_price, owner_id) VALUES ($1, $2, $3, $4)",
         "Car 2",
         "Description 2",
         starting_price2,
-        "testuser4"
+        owner_id
     )
     .execute(&pool)
     .await
@@ -148,13 +157,384 @@ async fn test_list_vehicles_postgres() {
     )
     .await;
 
-    let req = test::TestRequest::get().uri("/list_vehicles").to_request();
+    // Cheapest first, one per page, following the cursor
+    let req = test::TestRequest::get()
+        .uri("/list_vehicles?owner=testuser_list&sort=price_asc&limit=1")
+        .to_request();
     let resp = test::call_service(&app, req).await;
 
     assert_eq!(resp.status(), 200, "Expected 200 OK");
-    let vehicles: Vec<Vehicle> = test::read_body_json(resp).await;
+    let page: VehiclePage = test::read_body_json(resp).await;
+    assert_eq!(page.total_count, 2);
+    assert_eq!(page.items.len(), 1);
+    assert_eq!(page.items[0].name, "Car 1");
+    let cursor = page.next_cursor.expect("a second page");
+
+    let req = test::TestRequest::get()
+        .uri(&format!("/list_vehicles?owner=testuser_list&sort=price_asc&limit=1&cursor={}", cursor))
+        .to_request();
+    let page: VehiclePage = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(page.items[0].name, "Car 2");
+    assert!(page.next_cursor.is_none());
+
+    // A cursor only works with the sort it was issued for
+    let req = test::TestRequest::get()
+        .uri(&format!("/list_vehicles?owner=testuser_list&sort=newest&cursor={}", cursor))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 400);
+
+    // A well-formed cursor whose value does not fit the sort column is rejected before reaching the database
+    let tampered = hex::encode(json!({ "sort": "price_asc", "value": "abc", "id": 1 }).to_string());
+    let req = test::TestRequest::get()
+        .uri(&format!("/list_vehicles?owner=testuser_list&sort=price_asc&cursor={}", tampered))
+        .to_request();
+    let body: ErrorBody = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body.code, ErrorCode::ValidationFailed);
+    assert_eq!(body.details[0].field, "cursor");
+
+    let req = test::TestRequest::get()
+        .uri("/list_vehicles?owner=testuser_list&price_min=15000&q=description%202")
+        .to_request();
+    let page: VehiclePage = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(page.items.iter().map(|v| v.name.as_str()).collect::<Vec<_>>(), vec!["Car 2"]);
+
+    // Sold vehicles drop out of the default listing
+    let car_2 = page.items[0].id;
+    sqlx::query!(
+        "INSERT INTO auctions (vehicle_id, starting_price, end_time, status, seller_id, winner_id)
+         VALUES ($1, 20000, NOW(), 'sold', $2, $2)",
+        car_2,
+        owner_id
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let req = test::TestRequest::get().uri("/list_vehicles?owner=testuser_list").to_request();
+    let page: VehiclePage = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(page.total_count, 1);
+    let req = test::TestRequest::get()
+        .uri("/list_vehicles?owner=testuser_list&include_sold=true&has_open_auction=false")
+        .to_request();
+    let page: VehiclePage = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(page.total_count, 2);
+}
+
+
+#[actix_web::test]
+async fn test_update_vehicle_during_auction() {
+    dotenv::dotenv().ok();
+    let database_url = std::env::var("DATABASE_URL").expect("DATABASE_URL must be set");
+    let pool = PgPool::connect(&database_url)
+        .await
+        .expect("Failed to connect to database");
+
+    let salt = SaltString::generate(&mut rand::thread_rng());
+    let hashed_password = Argon2::default()
+        .hash_password("password_edit".as_bytes(), &salt)
+        .unwrap()
+        .to_string();
+
+    sqlx::query!(
+        "INSERT INTO users (username, password) VALUES ($1, $2)",
+        "testuser_edit",
+        hashed_password
+    ).execute(&pool)
+    .await
+    .unwrap();
+
+    let redis_client_url = std::env::var("REDIS_URL").expect("REDIS_URL must be set");
+    let redis_client = Client::open(redis_client_url).expect("Failed to connect to Redis");
+
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(pool.clone()))
+            .app_data(web::Data::new(redis_client.clone()))
+            .route("/login", web::post().to(user_login))
+            .route("/create_vehicle", web::post().to(create_vehicle))
+            .route("/vehicles/{id}", web::get().to(get_vehicle))
+            .route("/vehicles/{id}", web::patch().to(update_vehicle)),
+    )
+    .await;
+
+    let req = test::TestRequest::post()
+        .uri("/login")
+        .set_json(json!({ "username": "testuser_edit", "password": "password_edit" }))
+        .to_request();
+    let login: LoginResponse = test::call_and_read_body_json(&app, req).await;
+    let session_key = login.session_code;
+
+    let req = test::TestRequest::post()
+        .uri("/create_vehicle")
+        .insert_header(("Session-Code", session_key.as_str()))
+        .set_json(json!({ "name": "Jaguar E-Typ", "description": "Series 1", "starting_price": 90000.0 }))
+        .to_request();
+    let vehicle: Vehicle = test::call_and_read_body_json(&app, req).await;
+
+    // Before any auction every field is editable and nothing is logged
+    let req = test::TestRequest::patch()
+        .uri(&format!("/vehicles/{}", vehicle.id))
+        .insert_header(("Session-Code", session_key.as_str()))
+        .set_json(json!({ "name": "Jaguar E-Type" }))
+        .to_request();
+    let detail: VehicleDetail = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(detail.vehicle.name, "Jaguar E-Type");
+    assert!(detail.edits.is_empty());
+
+    let auction_id = sqlx::query_scalar!(
+        "INSERT INTO auctions (vehicle_id, starting_price, end_time, seller_id)
+         VALUES ($1, 90000, NOW() + INTERVAL '1 day', $2) RETURNING id",
+        vehicle.id,
+        login.user.id
+    )
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+
+    let req = test::TestRequest::patch()
+        .uri(&format!("/vehicles/{}", vehicle.id))
+        .insert_header(("Session-Code", session_key.as_str()))
+        .set_json(json!({ "starting_price": 50000.0 }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 409);
+
+    let req = test::TestRequest::patch()
+        .uri(&format!("/vehicles/{}", vehicle.id))
+        .insert_header(("Session-Code", session_key.as_str()))
+        .set_json(json!({ "description": "Series 1, 3.8 litre" }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 200);
+
+    let req = test::TestRequest::get()
+        .uri(&format!("/vehicles/{}", vehicle.id))
+        .to_request();
+    let detail: VehicleDetail = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(detail.open_auction_id, Some(auction_id));
+    assert_eq!(detail.edits.len(), 1);
+    assert_eq!(detail.edits[0].old_value.as_deref(), Some("Series 1"));
+    assert_eq!(detail.edits[0].new_value.as_deref(), Some("Series 1, 3.8 litre"));
+}
+
+
+#[actix_web::test]
+async fn test_vehicle_specification_and_filters() {
+    dotenv::dotenv().ok();
+    let database_url = std::env::var("DATABASE_URL").expect("DATABASE_URL must be set");
+    let pool = PgPool::connect(&database_url)
+        .await
+        .expect("Failed to connect to database");
+
+    let salt = SaltString::generate(&mut rand::thread_rng());
+    let hashed_password = Argon2::default()
+        .hash_password("password_spec".as_bytes(), &salt)
+        .unwrap()
+        .to_string();
+
+    sqlx::query!(
+        "INSERT INTO users (username, password) VALUES ($1, $2)",
+        "testuser_spec",
+        hashed_password
+    ).execute(&pool)
+    .await
+    .unwrap();
+
+    let redis_client_url = std::env::var("REDIS_URL").expect("REDIS_URL must be set");
+    let redis_client = Client::open(redis_client_url).expect("Failed to connect to Redis");
+
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(pool.clone()))
+            .app_data(web::Data::new(redis_client.clone()))
+            .route("/login", web::post().to(user_login))
+            .route("/create_vehicle", web::post().to(create_vehicle))
+            .route("/list_vehicles", web::get().to(list_vehicles)),
+    )
+    .await;
+
+    let req = test::TestRequest::post()
+        .uri("/login")
+        .set_json(json!({ "username": "testuser_spec", "password": "password_spec" }))
+        .to_request();
+    let login: LoginResponse = test::call_and_read_body_json(&app, req).await;
+
+    let form = CreateVehicle {
+        name: "Mercedes-Benz 280 SL Pagoda".to_string(),
+        description: "Restored W113".to_string(),
+        starting_price: 120000.0,
+        category_id: None,
+        spec: VehicleSpec {
+            make: Some("Mercedes-Benz".to_string()),
+            model: Some("280 SL".to_string()),
+            year: Some(1969),
+            mileage: Some(84000),
+            mileage_unit: Some("km".to_string()),
+            fuel_type: Some("petrol".to_string()),
+            transmission: Some("automatic".to_string()),
+            body_style: Some("roadster".to_string()),
+            matching_numbers: Some(true),
+            registration_country: Some("DE".to_string()),
+            condition_grade: Some(2),
+            ..Default::default()
+        },
+    };
+    let req = test::TestRequest::post()
+        .uri("/create_vehicle")
+        .insert_header(("Session-Code", login.session_code.as_str()))
+        .set_json(&form)
+        .to_request();
+    let vehicle: Vehicle = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(vehicle.year, Some(1969));
+    assert_eq!(vehicle.body_style.as_deref(), Some("roadster"));
+
+    let req = test::TestRequest::get()
+        .uri("/list_vehicles?make=mercedes-benz&year_min=1963&year_max=1971&body_style=roadster&condition_grade_max=2")
+        .to_request();
+    let page: VehiclePage = test::call_and_read_body_json(&app, req).await;
+    assert!(page.items.iter().any(|v| v.id == vehicle.id));
+    assert!(page.items.iter().all(|v| v.body_style.as_deref() == Some("roadster")));
+
+    let req = test::TestRequest::get()
+        .uri("/list_vehicles?make=mercedes-benz&year_min=1990")
+        .to_request();
+    let page: VehiclePage = test::call_and_read_body_json(&app, req).await;
+    assert!(page.items.iter().all(|v| v.id != vehicle.id));
+
+    // Invalid attributes are reported per field
+    let req = test::TestRequest::post()
+        .uri("/create_vehicle")
+        .insert_header(("Session-Code", login.session_code.as_str()))
+        .set_json(json!({
+            "name": "Broken", "description": "", "starting_price": 1.0,
+            "year": 1700, "mileage": 1000, "fuel_type": "steam", "registration_country": "germany"
+        }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 400);
+    let body: ErrorBody = test::read_body_json(resp).await;
+    assert_eq!(body.code, ErrorCode::ValidationFailed);
+    let fields: Vec<&str> = body.details.iter().map(|d| d.field.as_str()).collect();
+    assert_eq!(fields, vec!["year", "mileage_unit", "fuel_type", "registration_country"]);
+}
+
+
+#[actix_web::test]
+async fn test_vin_validation_and_decoding() {
+    dotenv::dotenv().ok();
+    let database_url = std::env::var("DATABASE_URL").expect("DATABASE_URL must be set");
+    let pool = PgPool::connect(&database_url)
+        .await
+        .expect("Failed to connect to database");
+
+    let salt = SaltString::generate(&mut rand::thread_rng());
+    let hashed_password = Argon2::default()
+        .hash_password("password_vin".as_bytes(), &salt)
+        .unwrap()
+        .to_string();
+
+    sqlx::query!(
+        "INSERT INTO users (username, password) VALUES ($1, $2)",
+        "testuser_vin",
+        hashed_password
+    ).execute(&pool)
+    .await
+    .unwrap();
+
+    let redis_client_url = std::env::var("REDIS_URL").expect("REDIS_URL must be set");
+    let redis_client = Client::open(redis_client_url).expect("Failed to connect to Redis");
+
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(pool.clone()))
+            .app_data(web::Data::new(redis_client.clone()))
+            .route("/login", web::post().to(user_login))
+            .route("/create_vehicle", web::post().to(create_vehicle))
+            .route("/vin/{vin}", web::get().to(decode_vin)),
+    )
+    .await;
+
+    let req = test::TestRequest::post()
+        .uri("/login")
+        .set_json(json!({ "username": "testuser_vin", "password": "password_vin" }))
+        .to_request();
+    let login: LoginResponse = test::call_and_read_body_json(&app, req).await;
+
+    // Make and year are filled in from the VIN when not given
+    let form = CreateVehicle {
+        name: "Porsche 993 Carrera".to_string(),
+        description: "Air-cooled".to_string(),
+        starting_price: 90000.0,
+        category_id: None,
+        spec: VehicleSpec {
+            vin: Some(" wp0aa2996ss312345 ".to_string()),
+            ..Default::default()
+        },
+    };
+    let req = test::TestRequest::post()
+        .uri("/create_vehicle")
+        .insert_header(("Session-Code", login.session_code.as_str()))
+        .set_json(&form)
+        .to_request();
+    let vehicle: Vehicle = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(vehicle.vin.as_deref(), Some("WP0AA2996SS312345"));
+    assert_eq!(vehicle.make.as_deref(), Some("Porsche"));
+    assert_eq!(vehicle.year, Some(1995));
+
+    // The same VIN cannot be listed twice
+    let req = test::TestRequest::post()
+        .uri("/create_vehicle")
+        .insert_header(("Session-Code", login.session_code.as_str()))
+        .set_json(&form)
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 409);
+    let body: ErrorBody = test::read_body_json(resp).await;
+    assert_eq!(body.code, ErrorCode::DuplicateVin);
+
+    // Writers racing past the check are stopped by the database
+    let err = sqlx::query!(
+        "INSERT INTO vehicles (name, description, starting_price, owner_id, vin)
+         VALUES ('Porsche 993 Targa', '', 90000, $1, 'WP0AA2996SS312345')",
+        login.user.id
+    )
+    .execute(&pool)
+    .await
+    .unwrap_err();
+    assert_eq!(
+        err.as_database_error().and_then(|err| err.constraint()),
+        Some("idx_vehicles_vin_listed")
+    );
+
+    // A wrong check digit is rejected
+    let req = test::TestRequest::post()
+        .uri("/create_vehicle")
+        .insert_header(("Session-Code", login.session_code.as_str()))
+        .set_json(json!({
+            "name": "Typo", "description": "", "starting_price": 1.0, "vin": "WP0AA2990SS312345"
+        }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 400);
+    let body: ErrorBody = test::read_body_json(resp).await;
+    assert_eq!(body.details[0].field, "vin");
+
+    // Pre-1981 chassis numbers follow relaxed rules
+    let req = test::TestRequest::post()
+        .uri("/create_vehicle")
+        .insert_header(("Session-Code", login.session_code.as_str()))
+        .set_json(json!({
+            "name": "Mercedes-Benz 280 SL", "description": "", "starting_price": 1.0,
+            "make": "Mercedes-Benz", "year": 1969, "vin": "113.044-10-012345"
+        }))
+        .to_request();
+    let vehicle: Vehicle = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(vehicle.vin.as_deref(), Some("113.044-10-012345"));
 
-    assert_eq!(vehicles.len(), 3);
-    assert_eq!(vehicles[1].name, "Car 1");
-    assert_eq!(vehicles[2].name, "Car 2");
+    let req = test::TestRequest::get().uri("/vin/1G1YY2183H5100001").to_request();
+    let info: VinInfo = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(info.manufacturer.as_deref(), Some("Chevrolet"));
+    assert_eq!(info.model_year, Some(1987));
+    assert_eq!(info.plant.as_deref(), Some("Bowling Green"));
 }
@@ -0,0 +1,161 @@
+use actix_web::{test, App, web};
+use redis::Client;
+use sqlx::postgres::PgPoolOptions;
+use sqlx::{Executor, PgPool};
+use argon2::{password_hash::SaltString, Argon2, PasswordHasher};
+use serde_json::json;
+use vehicle_auctions::routes::watchlist::{watch_auction, unwatch_auction, get_my_watchlist};
+use vehicle_auctions::routes::notification::list_notifications;
+use vehicle_auctions::routes::auction::get_auction;
+use vehicle_auctions::routes::user::user_login;
+use vehicle_auctions::models::{AuctionDetail, LoginResponse, Notification, WatchedAuction};
+use vehicle_auctions::errors::{ErrorBody, ErrorCode};
+use vehicle_auctions::notifications::send_ending_soon_reminders;
+
+#[actix_web::test]
+async fn test_watchlist_and_ending_soon_reminders() {
+    dotenv::dotenv().ok();
+    let database_url = std::env::var("DATABASE_URL").expect("DATABASE_URL must be set");
+    let pool = PgPool::connect(&database_url)
+        .await
+        .expect("Failed to connect to database");
+
+    let salt = SaltString::generate(&mut rand::thread_rng());
+    let hashed_password = Argon2::default()
+        .hash_password("password_watch".as_bytes(), &salt)
+        .unwrap()
+        .to_string();
+
+    let seller_id = sqlx::query_scalar!(
+        "INSERT INTO users (username, password) VALUES ($1, $2) RETURNING id",
+        "testuser_watch_seller",
+        hashed_password
+    ).fetch_one(&pool)
+    .await
+    .unwrap();
+    sqlx::query!(
+        "INSERT INTO users (username, password) VALUES ($1, $2)",
+        "testuser_watcher",
+        hashed_password
+    ).execute(&pool)
+    .await
+    .unwrap();
+
+    let mut auction_ids = Vec::new();
+    for (name, ends_in, closed) in [("Alvis TD21", "30 minutes", false), ("Bristol 403", "5 days", false), ("Humber Super Snipe", "-1 day", true)] {
+        let vehicle_id = sqlx::query_scalar!(
+            "INSERT INTO vehicles (name, description, starting_price, owner_id) VALUES ($1, '', 20000, $2) RETURNING id",
+            name,
+            seller_id
+        ).fetch_one(&pool)
+        .await
+        .unwrap();
+        let auction_id = sqlx::query_scalar!(
+            "INSERT INTO auctions (vehicle_id, starting_price, end_time, seller_id, status)
+             VALUES ($1, 20000, NOW() + $2::TEXT::INTERVAL, $3,
+                     CASE WHEN $4 THEN 'ended_unsold' ELSE 'live' END::auction_status) RETURNING id",
+            vehicle_id,
+            ends_in,
+            seller_id,
+            closed
+        ).fetch_one(&pool)
+        .await
+        .unwrap();
+        auction_ids.push(auction_id);
+    }
+    let (ending_soon, ending_later, closed) = (auction_ids[0], auction_ids[1], auction_ids[2]);
+
+    let redis_client_url = std::env::var("REDIS_URL").expect("REDIS_URL must be set");
+    let redis_client = Client::open(redis_client_url).expect("Failed to connect to Redis");
+
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(pool.clone()))
+            .app_data(web::Data::new(redis_client.clone()))
+            .route("/login", web::post().to(user_login))
+            .route("/auctions/{id}", web::get().to(get_auction))
+            .route("/auctions/{id}/watch", web::post().to(watch_auction))
+            .route("/auctions/{id}/watch", web::delete().to(unwatch_auction))
+            .route("/users/me/watchlist", web::get().to(get_my_watchlist))
+            .route("/users/me/notifications", web::get().to(list_notifications)),
+    )
+    .await;
+
+    let req = test::TestRequest::post()
+        .uri("/login")
+        .set_json(json!({ "username": "testuser_watcher", "password": "password_watch" }))
+        .to_request();
+    let login: LoginResponse = test::call_and_read_body_json(&app, req).await;
+
+    let watch = |auction_id: i32| {
+        test::TestRequest::post()
+            .uri(&format!("/auctions/{}/watch", auction_id))
+            .insert_header(("Session-Code", login.session_code.clone()))
+            .to_request()
+    };
+
+    for auction_id in [ending_later, ending_soon, ending_soon] {
+        let resp = test::call_service(&app, watch(auction_id)).await;
+        assert_eq!(resp.status(), 204);
+    }
+
+    let resp = test::call_service(&app, watch(closed)).await;
+    let body: ErrorBody = test::read_body_json(resp).await;
+    assert_eq!(body.code, ErrorCode::AuctionClosed);
+
+    let req = test::TestRequest::post().uri(&format!("/auctions/{}/watch", ending_soon)).to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 401);
+
+    let req = test::TestRequest::get().uri(&format!("/auctions/{}", ending_soon)).to_request();
+    let detail: AuctionDetail = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(detail.watcher_count, 1);
+
+    let watchlist_req = || {
+        test::TestRequest::get()
+            .uri("/users/me/watchlist")
+            .insert_header(("Session-Code", login.session_code.clone()))
+            .to_request()
+    };
+    let watchlist: Vec<WatchedAuction> = test::call_and_read_body_json(&app, watchlist_req()).await;
+    assert_eq!(watchlist.iter().map(|w| w.auction_id).collect::<Vec<_>>(), vec![ending_soon, ending_later]);
+    assert_eq!(watchlist[0].vehicle_name, "Alvis TD21");
+
+    // Inside both windows, but only one reminder goes out, and only once
+    assert_eq!(send_ending_soon_reminders(&pool, &[24 * 60, 60]).await.unwrap(), 1);
+    assert_eq!(send_ending_soon_reminders(&pool, &[24 * 60, 60]).await.unwrap(), 0);
+    // A tighter window configured later still fires, whatever the database session's time zone
+    let far_east_pool = PgPoolOptions::new()
+        .after_connect(|conn, _| Box::pin(async move {
+            conn.execute("SET TIME ZONE 'Pacific/Kiritimati'").await?;
+            Ok(())
+        }))
+        .connect(&database_url)
+        .await
+        .unwrap();
+    assert_eq!(send_ending_soon_reminders(&far_east_pool, &[24 * 60, 60, 45]).await.unwrap(), 1);
+
+    let req = test::TestRequest::get()
+        .uri("/users/me/notifications")
+        .insert_header(("Session-Code", login.session_code.as_str()))
+        .to_request();
+    let notifications: Vec<Notification> = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(notifications.len(), 2);
+    assert!(notifications.iter().all(|n| n.kind == "auction_ending_soon" && n.payload["auction_id"] == ending_soon));
+    assert_eq!(notifications[0].payload["offset_minutes"], 45);
+
+    let req = test::TestRequest::delete()
+        .uri(&format!("/auctions/{}/watch", ending_soon))
+        .insert_header(("Session-Code", login.session_code.as_str()))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 204);
+
+    let watchlist: Vec<WatchedAuction> = test::call_and_read_body_json(&app, watchlist_req()).await;
+    assert_eq!(watchlist.len(), 1);
+    assert_eq!(watchlist[0].auction_id, ending_later);
+
+    let req = test::TestRequest::get().uri(&format!("/auctions/{}", ending_soon)).to_request();
+    let detail: AuctionDetail = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(detail.watcher_count, 0);
+}